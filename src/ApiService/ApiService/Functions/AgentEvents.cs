@@ -10,6 +10,10 @@ public class AgentEvents {
     private readonly ILogger _log;
     private readonly IOnefuzzContext _context;
 
+    // prefix of the `Done` error reported by the agent when it fails for
+    // reasons unrelated to its work set (see `DoneCause::NodeError`)
+    private const string NODE_ERROR_PREFIX = "node error: ";
+
     public AgentEvents(ILogger<AgentEvents> log, IOnefuzzContext context) {
         _log = log;
         _context = context;
@@ -152,6 +156,16 @@ public class AgentEvents {
             if (ev.Data is NodeDoneEventData doneData) {
                 if (doneData.Error is not null) {
                     var errorText = EntityConverter.ToJsonString(doneData);
+
+                    // the agent itself failed, not the task it was running, so
+                    // the node is reimaged without failing its tasks
+                    if (doneData.Error.StartsWith(NODE_ERROR_PREFIX)) {
+                        _log.LogError("node 'done' due to node error {MachineId} - {Error}", machineId, errorText);
+                        // discard result: node not used after this point
+                        _ = await _context.NodeOperations.ToReimage(node, done: true);
+                        return null;
+                    }
+
                     error = Error.Create(ErrorCode.TASK_FAILED, errorText);
                     _log.LogError("node 'done' {MachineId} - {Error}", machineId, errorText);
                 }
@@ -176,9 +190,142 @@ public class AgentEvents {
             return await OnWorkerEventRunning(machineId, ev.Running);
         }
 
+        if (ev.Metric is not null) {
+            return OnWorkerEventMetric(machineId, ev.Metric);
+        }
+
+        if (ev.Stats is not null) {
+            return OnWorkerEventStats(machineId, ev.Stats);
+        }
+
+        if (ev.SetupOutput is not null) {
+            return await OnWorkerEventSetupOutput(machineId, ev.SetupOutput);
+        }
+
+        if (ev.CorpusPruned is not null) {
+            return OnWorkerEventCorpusPruned(machineId, ev.CorpusPruned);
+        }
+
+        if (ev.Resumed is not null) {
+            return OnWorkerEventResumed(machineId, ev.Resumed);
+        }
+
+        if (ev.Log is not null) {
+            return await OnWorkerEventLog(machineId, ev.Log);
+        }
+
+        if (ev.Pong is not null) {
+            return OnWorkerEventPong(machineId, ev.Pong);
+        }
+
         return Error.Create(
             ErrorCode.INVALID_REQUEST,
-            "WorkerEvent should have either 'done' or 'running' set");
+            "WorkerEvent should have either 'done', 'running', 'metric', 'stats', 'setup_output', 'corpus_pruned', 'resumed', 'log' or 'pong' set");
+    }
+
+    private Error? OnWorkerEventPong(Guid machineId, WorkerPongEvent pong) {
+        _log.AddTags(new[] {
+            ("MachineId", machineId.ToString()),
+        });
+        _log.LogInformation("node replied to ping. {Token}", pong.Token);
+        return null;
+    }
+
+    private async Async.Task<Error?> OnWorkerEventLog(Guid machineId, WorkerLogEvent log) {
+        // store the message with the task's events, so that it can be seen with the task
+        var taskEvent = new TaskEvent(
+            TaskId: log.TaskId,
+            MachineId: machineId,
+            EventData: new WorkerEvent(Log: log with { Message = LimitText(log.Message) }));
+        var r = await _context.TaskEventOperations.Replace(taskEvent);
+        if (!r.IsOk) {
+            _log.AddHttpStatus(r.ErrorV);
+            _log.LogError("failed to replace taskEvent {TaskId}", taskEvent.TaskId);
+        }
+
+        return null;
+    }
+
+    private Error? OnWorkerEventResumed(Guid machineId, WorkerResumedEvent resumed) {
+        if (resumed.Tags is not null) {
+            _log.AddTags(resumed.Tags);
+        }
+
+        _log.AddTags(new[] {
+            ("MachineId", machineId.ToString()),
+            ("TaskId", resumed.TaskId.ToString()),
+        });
+        _log.LogWarning("worker resumed after agent restart. {PreviousElapsed}", resumed.PreviousElapsed);
+        return null;
+    }
+
+    private Error? OnWorkerEventCorpusPruned(Guid machineId, WorkerCorpusPrunedEvent pruned) {
+        if (pruned.Tags is not null) {
+            _log.AddTags(pruned.Tags);
+        }
+
+        _log.AddTags(new[] {
+            ("MachineId", machineId.ToString()),
+            ("TaskId", pruned.TaskId.ToString()),
+        });
+        _log.LogInformation("worker pruned corpus. {Removed} {Kept}", pruned.Removed, pruned.Kept);
+        return null;
+    }
+
+    private async Async.Task<Error?> OnWorkerEventSetupOutput(Guid machineId, WorkerSetupOutputEvent setupOutput) {
+        // trim stdout/stderr if too long
+        var scriptOutput = setupOutput.ScriptOutput with {
+            Stderr = LimitText(setupOutput.ScriptOutput.Stderr),
+            Stdout = LimitText(setupOutput.ScriptOutput.Stdout),
+        };
+
+        _log.LogInformation("setup script ran on node. {MachineId} {Status}", machineId, scriptOutput.ExitStatus);
+
+        // setup is shared by the tasks of the node, so record the output in the log of each
+        await foreach (var nodeTask in _context.NodeTasksOperations.GetByMachineId(machineId)) {
+            var taskEvent = new TaskEvent(
+                TaskId: nodeTask.TaskId,
+                MachineId: machineId,
+                EventData: new WorkerEvent(SetupOutput: new WorkerSetupOutputEvent(scriptOutput)));
+            var r = await _context.TaskEventOperations.Replace(taskEvent);
+            if (!r.IsOk) {
+                _log.AddHttpStatus(r.ErrorV);
+                _log.LogError("failed to replace taskEvent {TaskId}", taskEvent.TaskId);
+            }
+        }
+
+        return null;
+    }
+
+    private Error? OnWorkerEventStats(Guid machineId, WorkerStatsEvent stats) {
+        if (stats.Tags is not null) {
+            _log.AddTags(stats.Tags);
+        }
+
+        _log.AddTags(new[] {
+            ("MachineId", machineId.ToString()),
+            ("TaskId", stats.TaskId.ToString()),
+        });
+        _log.LogMetric("libfuzzer_iterations", stats.Stats.Iterations);
+        _log.LogMetric("libfuzzer_coverage", stats.Stats.Coverage);
+        _log.LogMetric("libfuzzer_features", stats.Stats.Features);
+        _log.LogMetric("libfuzzer_corpus_size", stats.Stats.CorpusSize);
+        _log.LogMetric("libfuzzer_exec_per_sec", stats.Stats.ExecPerSec);
+        _log.LogMetric("libfuzzer_rss_mb", stats.Stats.RssMb);
+        return null;
+    }
+
+    private Error? OnWorkerEventMetric(Guid machineId, WorkerMetricEvent metric) {
+        if (metric.Tags is not null) {
+            _log.AddTags(metric.Tags);
+        }
+
+        _log.AddTags(new[] {
+            ("MachineId", machineId.ToString()),
+            ("TaskId", metric.TaskId.ToString()),
+        });
+        _log.LogMetric(metric.Name, metric.Value);
+        return null;
     }
 
     private async Async.Task<Error?> OnWorkerEventRunning(Guid machineId, WorkerRunningEvent running) {
@@ -318,6 +318,14 @@ public enum AgentMode {
     Proxy
 }
 
+public enum WorkerLogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
 public enum NodeState {
     Init,
     Free,
@@ -51,6 +51,14 @@ public record StopTaskNodeCommand(Guid TaskId);
 
 public record NodeCommandAddSshKey(string PublicKey);
 
+public record NodeCommandAttachDebugger(Guid TaskId, string Command);
+
+public record NodeCommandSetLogLevel(string Level);
+
+public record NodeCommandPing(string Token);
+
+public record NodeCommandRequeueTask(Guid TaskId, WorkUnit WorkUnit);
+
 
 public record NodeCommand
 (
@@ -64,7 +72,19 @@ public record NodeCommand
     NodeCommandAddSshKey? AddSshKey = default,
 
     [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
-    NodeCommandStopIfFree? StopIfFree = default
+    NodeCommandStopIfFree? StopIfFree = default,
+
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    NodeCommandAttachDebugger? AttachDebugger = default,
+
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    NodeCommandSetLogLevel? SetLogLevel = default,
+
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    NodeCommandPing? Ping = default,
+
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    NodeCommandRequeueTask? RequeueTask = default
 );
 
 public enum NodeTaskState {
@@ -961,6 +981,7 @@ public record TaskDefinition(
 );
 
 public record WorkSet(
+    Guid Id,
     bool Reboot,
     Uri SetupUrl,
     Uri? ExtraSetupUrl,
@@ -59,17 +59,75 @@ public record WorkerEvent(
     [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     WorkerDoneEvent? Done = null,
     [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
-    WorkerRunningEvent? Running = null
+    WorkerRunningEvent? Running = null,
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    WorkerMetricEvent? Metric = null,
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    WorkerStatsEvent? Stats = null,
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    WorkerSetupOutputEvent? SetupOutput = null,
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    WorkerCorpusPrunedEvent? CorpusPruned = null,
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    WorkerResumedEvent? Resumed = null,
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    WorkerLogEvent? Log = null,
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    WorkerPongEvent? Pong = null
 ) : NodeEventBase;
 
 public record WorkerRunningEvent(
-    [property: Required] Guid TaskId);
+    [property: Required] Guid TaskId,
+    Dictionary<string, string>? Tags = null);
+
+public record WorkerMetricEvent(
+    [property: Required] Guid TaskId,
+    [property: Required] string Name,
+    [property: Required] double Value,
+    Dictionary<string, string>? Tags = null);
+
+public record LibFuzzerStats(
+    [property: Required] ulong Iterations,
+    [property: Required] uint Coverage,
+    [property: Required] uint Features,
+    [property: Required] uint CorpusSize,
+    [property: Required] double ExecPerSec,
+    [property: Required] uint RssMb);
+
+public record WorkerStatsEvent(
+    [property: Required] Guid TaskId,
+    [property: Required] LibFuzzerStats Stats,
+    Dictionary<string, string>? Tags = null);
+
+public record WorkerSetupOutputEvent(
+    [property: Required] ProcessOutput ScriptOutput);
+
+public record WorkerCorpusPrunedEvent(
+    [property: Required] Guid TaskId,
+    [property: Required] ulong Removed,
+    [property: Required] ulong Kept,
+    Dictionary<string, string>? Tags = null);
+
+public record WorkerResumedEvent(
+    [property: Required] Guid TaskId,
+    [property: Required] ulong PreviousElapsed,
+    Dictionary<string, string>? Tags = null);
+
+public record WorkerLogEvent(
+    [property: Required] Guid TaskId,
+    [property: Required] WorkerLogLevel Level,
+    [property: Required] string Message,
+    Dictionary<string, string>? Tags = null);
+
+public record WorkerPongEvent(
+    [property: Required] string Token);
 
 public record WorkerDoneEvent(
     [property: Required] Guid TaskId,
     [property: Required] ExitStatus ExitStatus,
     [property: Required] string Stderr,
-    [property: Required] string Stdout);
+    [property: Required] string Stdout,
+    Dictionary<string, string>? Tags = null);
 
 public record NodeStateUpdate(
     [property: Required] NodeState State,
@@ -77,7 +135,7 @@ public record NodeStateUpdate(
     NodeStateData? Data = null
 ) : NodeEventBase;
 
-// NodeSettingUpEventData, NodeDoneEventData, or ProcessOutput
+// NodeSettingUpEventData, NodeBusyEventData, NodeDoneEventData, or ProcessOutput
 [JsonConverter(typeof(SubclassConverter<NodeStateData>))]
 public abstract record NodeStateData;
 
@@ -85,6 +143,13 @@ public record NodeSettingUpEventData(
    [property: Required] List<Guid> Tasks
 ) : NodeStateData;
 
+public record NodeBusyEventData(
+    [property: Required] long StartedAt,
+
+    [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    long? EstimatedDuration
+) : NodeStateData;
+
 public record NodeDoneEventData(
     [property: JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
     string? Error,
@@ -110,6 +110,7 @@ public class Scheduler : IScheduler {
                 : null;
 
             var workSet = new WorkSet(
+                Id: Guid.NewGuid(),
                 Reboot: c.reboot,
                 Script: c.setupScript is not null,
                 SetupUrl: setupUrl,
@@ -2,22 +2,29 @@
 // Licensed under the MIT License.
 
 #![allow(clippy::too_many_arguments)]
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::{Error, Result};
+use anyhow::{Context, Error, Result};
+use onefuzz_telemetry::{Event::resource_usage, EventData};
 use tokio::time;
 
+use crate::checkpoint::Checkpoint;
 use crate::coordinator::*;
 use crate::done::set_done_lock;
+use crate::event_filter::WorkerEventFilter;
 use crate::heartbeat::{AgentHeartbeatClient, HeartbeatSender};
 use crate::reboot::*;
 use crate::scheduler::*;
 use crate::setup::*;
-use crate::work::IWorkQueue;
+use crate::work::{IWorkQueue, WorkSet};
 use crate::worker::{IWorkerRunner, WorkerEvent};
 
 const PENDING_COMMANDS_DELAY: time::Duration = time::Duration::from_secs(10);
 const BUSY_DELAY: time::Duration = time::Duration::from_secs(1);
+const CHECKPOINT_INTERVAL: time::Duration = time::Duration::from_secs(60);
+
+// Same as the default heartbeat period.
+const RESOURCE_USAGE_INTERVAL: time::Duration = time::Duration::from_secs(60 * 5);
 
 pub struct Agent {
     coordinator: Box<dyn ICoordinator>,
@@ -32,6 +39,17 @@ pub struct Agent {
     managed: bool,
     machine_id: uuid::Uuid,
     sleep_duration: Duration,
+    event_filter: WorkerEventFilter,
+
+    /// Last work set seen in the scheduler, for the done report.
+    work_set: Option<WorkSet>,
+    transitions: Vec<StateTransition>,
+
+    /// When the running workers were last checkpointed.
+    last_checkpoint: Option<time::Instant>,
+
+    /// When the resource usage of the running workers was last reported.
+    last_resource_usage: Option<time::Instant>,
 }
 
 impl Agent {
@@ -45,6 +63,7 @@ impl Agent {
         heartbeat: Option<AgentHeartbeatClient>,
         managed: bool,
         machine_id: uuid::Uuid,
+        event_filter: WorkerEventFilter,
     ) -> Self {
         let scheduler = Some(scheduler);
         let previous_state = NodeState::Init;
@@ -63,6 +82,11 @@ impl Agent {
             managed,
             machine_id,
             sleep_duration: Duration::from_secs(30),
+            event_filter,
+            work_set: None,
+            transitions: vec![],
+            last_checkpoint: None,
+            last_resource_usage: None,
         }
     }
 
@@ -86,6 +110,9 @@ impl Agent {
         let mut done = false;
         while !done {
             state.heartbeat.alive();
+            if let Some(scheduler) = &state.scheduler {
+                debug!("agent status: {:?}", scheduler.snapshot());
+            }
             if instant.elapsed() >= PENDING_COMMANDS_DELAY {
                 state = state.execute_pending_commands().await?;
                 instant = time::Instant::now();
@@ -101,7 +128,13 @@ impl Agent {
     async fn update(mut self) -> Result<(Self, bool)> {
         let last = self.scheduler.take().ok_or_else(scheduler_error)?;
         let previous_state = NodeState::from(&last);
-        let (next, done) = match last {
+        debug!("agent state: {:?}", last.as_node_state_with_detail());
+
+        if let Some(work_set) = last.work_set() {
+            self.work_set = Some(work_set.clone());
+        }
+
+        let (mut next, done) = match last {
             Scheduler::Free(s) => (self.free(s, previous_state).await?, false),
             Scheduler::SettingUp(s) => (self.setting_up(s, previous_state).await?, false),
             Scheduler::PendingReboot(s) => (self.pending_reboot(s, previous_state).await?, false),
@@ -111,13 +144,32 @@ impl Agent {
             Scheduler::Done(s) => (self.done(s, previous_state).await?, true),
         };
 
+        if let Some(scheduler) = &next.scheduler {
+            let to = NodeState::from(scheduler);
+            if to != previous_state {
+                match scheduler.work_set_id() {
+                    Some(id) => info!(
+                        "agent state changed from {:?} to {:?}, work set: {}",
+                        previous_state, to, id
+                    ),
+                    None => info!("agent state changed from {:?} to {:?}", previous_state, to),
+                }
+
+                next.transitions.push(StateTransition {
+                    from: previous_state,
+                    to,
+                    at: SystemTime::now(),
+                });
+            }
+        }
+
         Ok((next, done))
     }
 
     async fn emit_state_update_if_changed(&self, event: StateUpdateEvent) -> Result<()> {
         match (&event, self.previous_state) {
             (StateUpdateEvent::Free, NodeState::Free)
-            | (StateUpdateEvent::Busy, NodeState::Busy)
+            | (StateUpdateEvent::Busy { .. }, NodeState::Busy)
             | (StateUpdateEvent::SettingUp { .. }, NodeState::SettingUp)
             | (StateUpdateEvent::Rebooting, NodeState::Rebooting)
             | (StateUpdateEvent::Ready, NodeState::Ready)
@@ -134,7 +186,18 @@ impl Agent {
         self.emit_state_update_if_changed(StateUpdateEvent::Free)
             .await?;
 
-        let msg = self.work_queue.poll().await?;
+        let msg = match self.work_queue.poll().await? {
+            Some(msg) if !state.offers(&msg.work_set) => {
+                // Leave the work set unclaimed. It will reappear in the pool's work queue when
+                // the visibility timeout expires, for a node with the resources to run it.
+                warn!(
+                    "not enough resources to schedule work set: {:?}",
+                    msg.work_set
+                );
+                None
+            }
+            msg => msg,
+        };
 
         let next = if let Some(msg) = msg {
             info!("received work set message: {:?}", msg);
@@ -162,8 +225,14 @@ impl Agent {
                         // node will see it.
                         //
                         // Transition to `SettingUp` state.
-                        let state = state.schedule(work_set);
-                        state.into()
+                        match state.schedule(work_set) {
+                            Ok(state) => state.into(),
+                            Err(err) => {
+                                let error = format!("{err:?}");
+                                error!("unable to schedule work set: {}", error);
+                                Scheduler::setup_error(error)
+                            }
+                        }
                     }
                 }
             } else {
@@ -201,7 +270,7 @@ impl Agent {
                 state.into()
             }
         } else {
-            info!("no work available");
+            info!("no work available, idle for {:?}", state.idle_duration());
             self.sleep().await;
             state.into()
         };
@@ -220,11 +289,16 @@ impl Agent {
         self.emit_state_update_if_changed(StateUpdateEvent::SettingUp { tasks })
             .await?;
 
-        let scheduler = match state.finish(self.setup_runner.as_mut()).await? {
-            SetupDone::Ready(s) => s.into(),
-            SetupDone::PendingReboot(s) => s.into(),
-            SetupDone::Done(s) => s.into(),
-        };
+        let (done, script_output) = state
+            .finish(self.machine_id, self.setup_runner.as_mut())
+            .await?;
+
+        if let Some(script_output) = script_output {
+            self.emit_worker_event(WorkerEvent::SetupOutput { script_output })
+                .await?;
+        }
+
+        let scheduler: Scheduler = done.into();
 
         Ok(Self {
             previous_state: previous,
@@ -236,21 +310,30 @@ impl Agent {
     async fn pending_reboot(
         self,
         state: State<PendingReboot>,
-        _previous: NodeState,
+        previous: NodeState,
     ) -> Result<Self> {
         info!("agent pending reboot");
         self.emit_state_update_if_changed(StateUpdateEvent::Rebooting)
             .await?;
 
-        let ctx = state.reboot_context();
-        self.reboot.save_context(ctx).await?;
-        self.reboot.invoke()?; // noreturn
+        let scheduler = state
+            .wait_for_reboot(self.reboot.as_ref(), REBOOT_TIMEOUT)
+            .await?;
 
-        unreachable!()
+        Ok(Self {
+            previous_state: previous,
+            scheduler: Some(scheduler),
+            ..self
+        })
     }
 
     async fn ready(self, state: State<Ready>, previous: NodeState) -> Result<Self> {
         info!("agent ready");
+
+        for warning in state.preflight_check(self.machine_id) {
+            warn!("preflight check: {}", warning);
+        }
+
         self.emit_state_update_if_changed(StateUpdateEvent::Ready)
             .await?;
         Ok(Self {
@@ -262,8 +345,17 @@ impl Agent {
     }
 
     async fn busy(mut self, state: State<Busy>, previous: NodeState) -> Result<Self> {
-        self.emit_state_update_if_changed(StateUpdateEvent::Busy)
-            .await?;
+        let started_at = state
+            .started_at()
+            .duration_since(UNIX_EPOCH)
+            .context("system time before unix epoch")?
+            .as_secs();
+        let estimated_duration = state.estimated_duration();
+        self.emit_state_update_if_changed(StateUpdateEvent::Busy {
+            started_at,
+            estimated_duration,
+        })
+        .await?;
 
         // Without this sleep, the `Agent.run` loop turns into an extremely tight loop calling
         // `wait4` of the running agents.  This sleep adds a small window to allow the rest of the
@@ -273,13 +365,36 @@ impl Agent {
         // that is done, this sleep should be removed.
         time::sleep(BUSY_DELAY).await;
 
-        let mut events: Vec<WorkerEvent> = vec![];
-        let updated = state
-            .update(&mut events, self.worker_runner.as_mut())
+        let resource_usage_due = self
+            .last_resource_usage
+            .map_or(true, |at| at.elapsed() >= RESOURCE_USAGE_INTERVAL);
+        if resource_usage_due {
+            self.report_resource_usage(&state);
+        }
+
+        let mut events: Vec<BusyEvent> = vec![];
+        let mut updated = state
+            .update(self.machine_id, &mut events, self.worker_runner.as_mut())
             .await?;
 
-        for event in events {
-            self.coordinator.emit_event(event.into()).await?;
+        for BusyEvent { event, .. } in events {
+            self.emit_worker_event(event).await?;
+        }
+
+        if let Updated::Busy(state) = &mut updated {
+            for (task_id, wchan, elapsed) in state.newly_blocked_workers() {
+                warn!(
+                    "worker for task {} may be stuck, it has been waiting in {} for {:?}",
+                    task_id, wchan, elapsed
+                );
+            }
+
+            let checkpoint_due = self
+                .last_checkpoint
+                .map_or(true, |at| at.elapsed() >= CHECKPOINT_INTERVAL);
+            if checkpoint_due {
+                self.checkpoint(state);
+            }
         }
 
         Ok(Self {
@@ -289,24 +404,52 @@ impl Agent {
         })
     }
 
+    // Sent as telemetry, sampled at the heartbeat period rather than every
+    // busy tick, which would be one event per task per second.
+    fn report_resource_usage(&mut self, state: &State<Busy>) {
+        for (task_id, usage) in state.resource_usage() {
+            event!(resource_usage;
+                EventData::TaskId = task_id,
+                EventData::CpuUsage = usage.cpu_percent as f32,
+                EventData::PhysicalMemory = usage.rss_bytes,
+                EventData::VirtualMemory = usage.vsz_bytes
+            );
+        }
+
+        self.last_resource_usage = Some(time::Instant::now());
+    }
+
+    fn checkpoint(&mut self, state: &State<Busy>) {
+        let saved = Checkpoint::path(self.machine_id).and_then(|path| state.checkpoint(&path));
+
+        match saved {
+            Ok(()) => self.last_checkpoint = Some(time::Instant::now()),
+            Err(err) => warn!("unable to checkpoint workers: {:?}", err),
+        }
+    }
+
     async fn done(self, state: State<Done>, previous: NodeState) -> Result<Self> {
         info!("agent done");
         set_done_lock(self.machine_id).await?;
 
-        let event = match state.cause() {
-            DoneCause::SetupError {
-                error,
-                script_output,
-            } => StateUpdateEvent::Done {
-                error: Some(error),
-                script_output,
-            },
-            DoneCause::Stopped | DoneCause::WorkersDone => StateUpdateEvent::Done {
-                error: None,
-                script_output: None,
-            },
-        };
+        // The workers are done, so there is nothing to resume.
+        if let Err(err) =
+            Checkpoint::path(self.machine_id).and_then(|path| Checkpoint::remove(&path))
+        {
+            warn!("unable to remove checkpoint: {:?}", err);
+        }
 
+        let report = state
+            .clone()
+            .into_report(self.machine_id, self.work_set.as_ref())
+            .transitions(self.transitions.clone());
+        info!("done report: {}", serde_json::to_string(&report)?);
+
+        if let DoneCause::CancelledByOperator { reason } = &report.cause {
+            info!("work cancelled: {}", reason);
+        }
+
+        let event = report.state_update_event();
         self.emit_state_update_if_changed(event).await?;
         // `Done` is a final state.
         Ok(Self {
@@ -316,6 +459,18 @@ impl Agent {
         })
     }
 
+    async fn emit_worker_event(&mut self, event: WorkerEvent) -> Result<()> {
+        if self.event_filter.allow(&event) {
+            self.coordinator.emit_event(event.into()).await?;
+        }
+
+        if let Some(dropped) = self.event_filter.take_dropped() {
+            warn!("dropped rate-limited worker events: {:?}", dropped);
+        }
+
+        Ok(())
+    }
+
     async fn execute_pending_commands(mut self) -> Result<Self> {
         let result = self.coordinator.poll_commands().await;
 
@@ -327,9 +482,24 @@ impl Agent {
             Ok(Some(cmd)) => {
                 info!("agent received node command: {:?}", cmd);
                 let managed = self.managed;
-                let scheduler = self.scheduler.take().ok_or_else(scheduler_error)?;
+                let mut scheduler = self.scheduler.take().ok_or_else(scheduler_error)?;
+
+                // Stopping cancels the workers, so report any that already exited first.
+                if let NodeCommand::Stop {} = cmd {
+                    for event in scheduler.drain_events().await {
+                        self.emit_worker_event(event).await?;
+                    }
+                }
+
                 let new_scheduler = scheduler.execute_command(cmd.clone(), managed).await?;
 
+                if let NodeCommand::Ping { token } = cmd {
+                    self.emit_worker_event(WorkerEvent::Pong {
+                        token: token.clone(),
+                    })
+                    .await?;
+                }
+
                 Ok(Self {
                     last_poll_command: result,
                     scheduler: Some(new_scheduler),
@@ -346,19 +516,23 @@ impl Agent {
                 })
             }
             Err(PollCommandError::RequestParseFailed(err)) => {
-                bail!("poll commands failed: {:?}", err);
+                let message = format!("poll commands failed: {err:?}");
+                error!("{}", message);
+                self.node_error(message, result).await
             }
             Err(PollCommandError::ClaimFailed(err)) => {
                 // If we failed to claim two commands in a row, it means the
                 // service is up (since we received the commands we're trying to
                 // claim), but something else is going wrong, consistently. This
                 // is suspicious, and less likely to be a transient service or
-                // networking error, so bail.
+                // networking error, so stop the node.
                 if matches!(
                     self.last_poll_command,
                     Err(PollCommandError::ClaimFailed(..))
                 ) {
-                    bail!("repeated command claim attempt failures: {:?}", err);
+                    let message = format!("repeated command claim attempt failures: {err:?}");
+                    error!("{}", message);
+                    return self.node_error(message, result).await;
                 }
                 error!("error claiming command from the service: {:?}", err);
                 Ok(Self {
@@ -369,6 +543,25 @@ impl Agent {
         }
     }
 
+    // Self-terminate on an unrecoverable agent error, killing any workers
+    // first. The next `update()` will report the failure to the service via a
+    // `done` state update.
+    async fn node_error(
+        mut self,
+        message: String,
+        last_poll_command: Result<Option<NodeCommand>, PollCommandError>,
+    ) -> Result<Self> {
+        let scheduler = self.scheduler.take().ok_or_else(scheduler_error)?;
+        let cause = DoneCause::NodeError { message };
+        let done = scheduler.force_done(cause).await;
+
+        Ok(Self {
+            last_poll_command,
+            scheduler: Some(done.into()),
+            ..self
+        })
+    }
+
     async fn sleep(&self) {
         time::sleep(self.sleep_duration).await;
     }
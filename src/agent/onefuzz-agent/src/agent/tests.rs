@@ -1,17 +1,21 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+use std::collections::HashMap;
+use std::path::PathBuf;
+
 use onefuzz::blob::BlobContainerUrl;
 use uuid::Uuid;
 
 use crate::coordinator::double::*;
 use crate::reboot::double::*;
+use crate::resources::ResourceLimits;
 use crate::setup::double::*;
 use crate::work::double::*;
 use crate::work::*;
 use crate::worker::double::*;
 use crate::worker::WorkerEvent;
-use onefuzz::process::ExitStatus;
+use onefuzz::process::{ExitStatus, Output};
 
 use super::*;
 
@@ -36,6 +40,7 @@ impl Fixture {
             None,
             true,
             Uuid::new_v4(),
+            WorkerEventFilter::default(),
         )
     }
 
@@ -56,13 +61,19 @@ impl Fixture {
         }
     }
 
+    pub fn work_set_id(&self) -> Uuid {
+        "0d6b1d7a-6f3c-4c55-9d2e-4a3f1b8e7c21".parse().unwrap()
+    }
+
     pub fn work_set(&self) -> WorkSet {
         WorkSet {
+            id: self.work_set_id(),
             reboot: false,
             setup_url: self.setup_url(),
             extra_setup_url: None,
             script: false,
             work_units: vec![self.work_unit()],
+            estimated_duration: None,
         }
     }
 
@@ -78,6 +89,12 @@ impl Fixture {
             job_id: self.job_id(),
             task_id: self.task_id(),
             config,
+            tags: Default::default(),
+            expected_files: vec![],
+            max_retries: 0,
+            output_dir: None,
+            stdin_file: None,
+            max_corpus_size: None,
         }
     }
 }
@@ -90,7 +107,7 @@ async fn test_update_free_no_work() {
     let (agent, done) = agent.update().await.unwrap();
     assert!(!done);
 
-    assert!(matches!(agent.scheduler.unwrap(), Scheduler::Free(..)));
+    assert_eq!(agent.scheduler.unwrap(), Scheduler::new(None));
 
     let double: &WorkQueueDouble = agent.work_queue.downcast_ref().unwrap();
     let claimed_worksets = double
@@ -124,6 +141,336 @@ async fn test_update_free_has_work() {
     assert_eq!(claimed_worksets, &[Fixture.work_set()]);
 }
 
+#[tokio::test]
+async fn test_update_free_work_exceeds_limits() {
+    let limits = ResourceLimits {
+        max_workers: Some(0),
+        ..Default::default()
+    };
+    let mut agent = Agent {
+        scheduler: Some(Scheduler::new(None).with_resource_limits(limits)),
+        ..Fixture.agent()
+    };
+    agent.sleep_duration = Duration::from_secs(5);
+    agent
+        .work_queue
+        .downcast_mut::<WorkQueueDouble>()
+        .unwrap()
+        .available
+        .push(Fixture.message());
+
+    let (agent, done) = agent.update().await.unwrap();
+    assert!(!done);
+    assert!(matches!(agent.scheduler.unwrap(), Scheduler::Free(..)));
+
+    let double: &WorkQueueDouble = agent.work_queue.downcast_ref().unwrap();
+    assert!(double.claimed.is_empty());
+}
+
+#[tokio::test]
+async fn test_update_pending_reboot() {
+    let mut agent = Fixture.agent();
+    agent
+        .work_queue
+        .downcast_mut::<WorkQueueDouble>()
+        .unwrap()
+        .available
+        .push(Message {
+            work_set: WorkSet {
+                reboot: true,
+                ..Fixture.work_set()
+            },
+            queue_message: None,
+        });
+
+    let mut done;
+    for _i in 0..3 {
+        (agent, done) = agent.update().await.unwrap();
+        assert!(!done);
+    }
+
+    // The simulated reboot resumes from the saved context.
+    assert!(matches!(
+        agent.scheduler.as_ref().unwrap(),
+        Scheduler::Ready(..)
+    ));
+
+    let reboot: &RebootDouble = agent.reboot.downcast_ref().unwrap();
+    assert!(reboot.invoked.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+async fn node_error_scheduler() -> Scheduler {
+    let cause = DoneCause::NodeError {
+        message: "unexpected failure".into(),
+    };
+    Scheduler::new(None).force_done(cause).await.into()
+}
+
+#[tokio::test]
+async fn test_scheduler_snapshot() {
+    let snapshot = Scheduler::new(None).snapshot();
+    assert_eq!(snapshot.state, NodeState::Free);
+    assert!(snapshot.tasks.is_empty());
+    assert!(snapshot.done_cause.is_none());
+
+    let snapshot = Scheduler::new(Some(RebootContext::new(Fixture.work_set()))).snapshot();
+    assert_eq!(snapshot.state, NodeState::Ready);
+    assert_eq!(snapshot.tasks, vec![Fixture.task_id()]);
+    assert!(snapshot.elapsed_seconds.is_none());
+
+    let snapshot = node_error_scheduler().await.snapshot();
+    assert_eq!(snapshot.state, NodeState::Done);
+    assert!(matches!(
+        snapshot.done_cause,
+        Some(DoneCause::NodeError { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_scheduler_work_set_id() {
+    assert_eq!(Scheduler::new(None).work_set_id(), None);
+
+    let scheduler = Scheduler::new(Some(RebootContext::new(Fixture.work_set())));
+    assert_eq!(scheduler.work_set_id(), Some(Fixture.work_set_id()));
+
+    assert_eq!(node_error_scheduler().await.work_set_id(), None);
+}
+
+#[tokio::test]
+async fn test_scheduler_force_done() {
+    let done = Scheduler::new(None).force_done(DoneCause::Stopped).await;
+    assert!(matches!(done.cause(), DoneCause::Stopped));
+
+    let done = Scheduler::new(Some(RebootContext::new(Fixture.work_set())))
+        .force_done(DoneCause::Stopped)
+        .await;
+    assert!(matches!(done.cause(), DoneCause::Stopped));
+
+    // A node that is already done keeps its cause.
+    let done = node_error_scheduler()
+        .await
+        .force_done(DoneCause::Stopped)
+        .await;
+    assert!(matches!(done.cause(), DoneCause::NodeError { .. }));
+}
+
+#[tokio::test]
+async fn test_busy_workers_not_blocked_before_spawn() {
+    let machine_id = Uuid::new_v4();
+    let mut busy = match Scheduler::new(Some(RebootContext::new(Fixture.work_set()))) {
+        Scheduler::Ready(state) => state.run(machine_id).await.unwrap(),
+        scheduler => panic!("unexpected scheduler state: {:?}", scheduler),
+    }
+    .blocked_threshold(Duration::ZERO);
+
+    // Workers without a child process have no wait channel.
+    let mut events = vec![];
+    let mut runner = WorkerRunnerDouble::default();
+    busy = match busy
+        .update(machine_id, &mut events, &mut runner)
+        .await
+        .unwrap()
+    {
+        Updated::Busy(state) => state,
+        Updated::Done(state) => panic!("unexpected done: {:?}", state.cause()),
+    };
+
+    assert!(!busy.is_any_worker_blocked());
+    assert!(busy.newly_blocked_workers().is_empty());
+    assert!(!Scheduler::from(busy).snapshot().any_worker_blocked);
+}
+
+#[tokio::test]
+async fn test_busy_update_drops_stale_events() {
+    let machine_id = Uuid::new_v4();
+    let mut busy = match Scheduler::new(Some(RebootContext::new(Fixture.work_set()))) {
+        Scheduler::Ready(state) => state.run(machine_id).await.unwrap(),
+        scheduler => panic!("unexpected scheduler state: {:?}", scheduler),
+    };
+
+    let mut events = vec![];
+    let mut runner = WorkerRunnerDouble::default();
+    busy = match busy
+        .update(machine_id, &mut events, &mut runner)
+        .await
+        .unwrap()
+    {
+        Updated::Busy(state) => state,
+        Updated::Done(state) => panic!("unexpected done: {:?}", state.cause()),
+    };
+
+    let metric = |name: &str| WorkerEvent::Metric {
+        task_id: Fixture.task_id(),
+        name: name.into(),
+        value: 1.0,
+        tags: HashMap::new(),
+    };
+    let stale = BusyEvent {
+        generation: 0,
+        event: metric("stale"),
+    };
+    let unhandled = BusyEvent {
+        generation: 1,
+        event: metric("unhandled"),
+    };
+    events = vec![stale.clone(), unhandled.clone()];
+
+    match busy
+        .update(machine_id, &mut events, &mut runner)
+        .await
+        .unwrap()
+    {
+        Updated::Busy(_) => {}
+        Updated::Done(state) => panic!("unexpected done: {:?}", state.cause()),
+    };
+
+    assert!(!events.contains(&stale));
+    assert!(events.contains(&unhandled));
+}
+
+#[tokio::test]
+async fn test_busy_checkpoint() {
+    let machine_id = Uuid::new_v4();
+    let mut busy = match Scheduler::new(Some(RebootContext::new(Fixture.work_set()))) {
+        Scheduler::Ready(state) => state.run(machine_id).await.unwrap(),
+        scheduler => panic!("unexpected scheduler state: {:?}", scheduler),
+    };
+    let path = std::env::temp_dir().join(format!("checkpoint-{}.json", Uuid::new_v4()));
+
+    // Workers that have not been spawned are not checkpointed.
+    busy.checkpoint(&path).unwrap();
+    assert_eq!(
+        Checkpoint::load(&path).unwrap(),
+        Some(Checkpoint::default())
+    );
+
+    let mut events = vec![];
+    let mut runner = WorkerRunnerDouble::default();
+    busy = match busy
+        .update(machine_id, &mut events, &mut runner)
+        .await
+        .unwrap()
+    {
+        Updated::Busy(state) => state,
+        Updated::Done(state) => panic!("unexpected done: {:?}", state.cause()),
+    };
+
+    busy.checkpoint(&path).unwrap();
+    let checkpoint = Checkpoint::load(&path).unwrap().unwrap();
+    let task_ids: Vec<_> = checkpoint.tasks.iter().map(|task| task.task_id).collect();
+    assert_eq!(task_ids, vec![Fixture.task_id()]);
+
+    Checkpoint::remove(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_busy_resumed_working_dir() {
+    let machine_id = Uuid::new_v4();
+    let working_dir = std::env::temp_dir().join(format!("resumed-{}", Uuid::new_v4()));
+    let working_dirs = HashMap::from([(Fixture.task_id(), working_dir.clone())]);
+
+    let scheduler = Scheduler::new(Some(RebootContext::new(Fixture.work_set())))
+        .with_working_dirs(working_dirs);
+    let busy = match scheduler {
+        Scheduler::Ready(state) => state.run(machine_id).await.unwrap(),
+        scheduler => panic!("unexpected scheduler state: {:?}", scheduler),
+    };
+
+    let mut events = vec![];
+    let mut runner = WorkerRunnerDouble::default();
+    let busy = match busy
+        .update(machine_id, &mut events, &mut runner)
+        .await
+        .unwrap()
+    {
+        Updated::Busy(state) => state,
+        Updated::Done(state) => panic!("unexpected done: {:?}", state.cause()),
+    };
+
+    let path = std::env::temp_dir().join(format!("checkpoint-{}.json", Uuid::new_v4()));
+    busy.checkpoint(&path).unwrap();
+    let checkpoint = Checkpoint::load(&path).unwrap().unwrap();
+    assert_eq!(checkpoint.tasks[0].working_dir, working_dir);
+
+    Checkpoint::remove(&path).unwrap();
+    let _ = std::fs::remove_dir_all(&working_dir);
+}
+
+#[tokio::test]
+async fn test_busy_force_requeue() {
+    let busy = || async {
+        match Scheduler::new(Some(RebootContext::new(Fixture.work_set()))) {
+            Scheduler::Ready(state) => state.run(Uuid::new_v4()).await.unwrap(),
+            scheduler => panic!("unexpected scheduler state: {:?}", scheduler),
+        }
+    };
+
+    let mut work = Fixture.work_unit();
+    work.max_retries = 3;
+
+    let busy = busy()
+        .await
+        .force_requeue(Fixture.task_id(), work.clone())
+        .await
+        .unwrap();
+    assert_eq!(busy.task_ids(), vec![Fixture.task_id()]);
+
+    let scheduler = Scheduler::from(busy);
+    let work_set = scheduler.work_set().unwrap();
+    assert_eq!(
+        work_set.work_unit_by_task_id(Fixture.task_id()),
+        Some(&work)
+    );
+
+    let mut other = Fixture.work_unit();
+    other.task_id = Uuid::new_v4();
+
+    // the task must have a worker
+    assert!(busy()
+        .await
+        .force_requeue(other.task_id, other.clone())
+        .await
+        .is_err());
+
+    // the work unit must be for the same task
+    assert!(busy()
+        .await
+        .force_requeue(Fixture.task_id(), other)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_requeue_task_command() {
+    let busy: Scheduler = match Scheduler::new(Some(RebootContext::new(Fixture.work_set()))) {
+        Scheduler::Ready(state) => state.run(Uuid::new_v4()).await.unwrap().into(),
+        scheduler => panic!("unexpected scheduler state: {:?}", scheduler),
+    };
+
+    let mut work_unit = Fixture.work_unit();
+    work_unit.max_retries = 3;
+    let cmd = NodeCommand::RequeueTask {
+        task_id: Fixture.task_id(),
+        work_unit: work_unit.clone(),
+    };
+    let scheduler = busy.execute_command(cmd, true).await.unwrap();
+    assert_eq!(
+        scheduler
+            .work_set()
+            .unwrap()
+            .work_unit_by_task_id(Fixture.task_id()),
+        Some(&work_unit)
+    );
+
+    // Unknown tasks are ignored, rather than failing the agent.
+    let cmd = NodeCommand::RequeueTask {
+        task_id: Uuid::new_v4(),
+        work_unit,
+    };
+    let scheduler = scheduler.execute_command(cmd, true).await.unwrap();
+    assert!(matches!(scheduler, Scheduler::Busy(..)));
+}
+
 #[tokio::test]
 async fn test_emitted_state() {
     let mut agent = Agent {
@@ -136,6 +483,7 @@ async fn test_emitted_state() {
                 }),
                 ..ChildDouble::default()
             },
+            ..WorkerRunnerDouble::default()
         }),
         ..Fixture.agent()
     };
@@ -155,15 +503,31 @@ async fn test_emitted_state() {
         }
     }
 
+    let coordinator: &CoordinatorDouble = agent.coordinator.downcast_ref().unwrap();
+    let events = &coordinator.events.read().await;
+
+    // The start time is taken from the wall clock, so read it back from the emitted event.
+    let started_at = events
+        .iter()
+        .find_map(|event| match event {
+            NodeEvent::StateUpdate(StateUpdateEvent::Busy { started_at, .. }) => Some(*started_at),
+            _ => None,
+        })
+        .unwrap();
+
     let expected_events: Vec<NodeEvent> = vec![
         NodeEvent::StateUpdate(StateUpdateEvent::Free),
         NodeEvent::StateUpdate(StateUpdateEvent::SettingUp {
             tasks: vec![Fixture.task_id()],
         }),
         NodeEvent::StateUpdate(StateUpdateEvent::Ready),
-        NodeEvent::StateUpdate(StateUpdateEvent::Busy),
+        NodeEvent::StateUpdate(StateUpdateEvent::Busy {
+            started_at,
+            estimated_duration: None,
+        }),
         NodeEvent::WorkerEvent(WorkerEvent::Running {
             task_id: Fixture.task_id(),
+            tags: Default::default(),
         }),
         NodeEvent::WorkerEvent(WorkerEvent::Done {
             task_id: Fixture.task_id(),
@@ -174,14 +538,13 @@ async fn test_emitted_state() {
             },
             stderr: String::default(),
             stdout: String::default(),
+            tags: Default::default(),
         }),
         NodeEvent::StateUpdate(StateUpdateEvent::Done {
             error: None,
             script_output: None,
         }),
     ];
-    let coordinator: &CoordinatorDouble = agent.coordinator.downcast_ref().unwrap();
-    let events = &coordinator.events.read().await;
     assert_eq!(&events.to_vec(), &expected_events);
 }
 
@@ -234,3 +597,276 @@ async fn test_emitted_state_failed_setup() {
         .await
         .unwrap();
 }
+
+#[tokio::test]
+async fn test_emitted_state_failed_setup_script() {
+    let script_output = Output {
+        exit_status: ExitStatus {
+            code: Some(1),
+            signal: None,
+            success: false,
+        },
+        stderr: "setup failed".to_owned(),
+        stdout: "setting up".to_owned(),
+    };
+    let mut agent = Agent {
+        setup_runner: Box::new(SetupRunnerDouble {
+            script: Some(script_output.clone()),
+            ..SetupRunnerDouble::default()
+        }),
+        ..Fixture.agent()
+    };
+
+    agent
+        .work_queue
+        .downcast_mut::<WorkQueueDouble>()
+        .unwrap()
+        .available
+        .push(Fixture.message());
+
+    let mut done;
+    for _i in 0..10 {
+        (agent, done) = agent.update().await.unwrap();
+        if done {
+            break;
+        }
+    }
+
+    let expected_events: Vec<NodeEvent> = vec![
+        NodeEvent::StateUpdate(StateUpdateEvent::Free),
+        NodeEvent::StateUpdate(StateUpdateEvent::SettingUp {
+            tasks: vec![Fixture.task_id()],
+        }),
+        NodeEvent::WorkerEvent(WorkerEvent::SetupOutput {
+            script_output: script_output.clone(),
+        }),
+        NodeEvent::StateUpdate(StateUpdateEvent::Done {
+            error: Some(String::from("error running target setup script")),
+            script_output: Some(script_output),
+        }),
+    ];
+    let coordinator: &CoordinatorDouble = agent.coordinator.downcast_ref().unwrap();
+    let events = &coordinator.events.read().await.to_vec();
+    assert_eq!(events, &expected_events);
+
+    tokio::fs::remove_file(crate::done::done_path(agent.machine_id).unwrap())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_emitted_state_missing_expected_files() {
+    let mut work_unit = Fixture.work_unit();
+    work_unit.expected_files = vec![PathBuf::from("missing-file.exe")];
+    let work_set = WorkSet {
+        work_units: vec![work_unit],
+        ..Fixture.work_set()
+    };
+
+    let mut agent = Fixture.agent();
+    agent
+        .work_queue
+        .downcast_mut::<WorkQueueDouble>()
+        .unwrap()
+        .available
+        .push(Message {
+            work_set,
+            queue_message: None,
+        });
+
+    let mut done = false;
+    for _i in 0..10 {
+        (agent, done) = agent.update().await.unwrap();
+        if done {
+            break;
+        }
+    }
+    assert!(done);
+
+    let coordinator: &CoordinatorDouble = agent.coordinator.downcast_ref().unwrap();
+    let events = coordinator.events.read().await.to_vec();
+    match events.last() {
+        Some(NodeEvent::StateUpdate(StateUpdateEvent::Done {
+            error: Some(error), ..
+        })) => assert!(error.contains("missing-file.exe")),
+        event => panic!("expected done event with error, got: {event:?}"),
+    }
+
+    tokio::fs::remove_file(crate::done::done_path(agent.machine_id).unwrap())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_emitted_state_node_error() {
+    let mut agent = Agent {
+        scheduler: Some(node_error_scheduler().await),
+        ..Fixture.agent()
+    };
+
+    let done;
+    (agent, done) = agent.update().await.unwrap();
+    assert!(done);
+
+    let expected_events: Vec<NodeEvent> = vec![NodeEvent::StateUpdate(StateUpdateEvent::Done {
+        error: Some(String::from("node error: unexpected failure")),
+        script_output: None,
+    })];
+    let coordinator: &CoordinatorDouble = agent.coordinator.downcast_ref().unwrap();
+    let events = &coordinator.events.read().await.to_vec();
+    assert_eq!(events, &expected_events);
+
+    tokio::fs::remove_file(crate::done::done_path(agent.machine_id).unwrap())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_emitted_state_out_of_retries() {
+    let mut work_unit = Fixture.work_unit();
+    work_unit.max_retries = 1;
+    let work_set = WorkSet {
+        work_units: vec![work_unit],
+        ..Fixture.work_set()
+    };
+
+    let mut agent = Agent {
+        worker_runner: Box::new(WorkerRunnerDouble {
+            child: ChildDouble {
+                exit_status: Some(ExitStatus {
+                    code: Some(1),
+                    signal: None,
+                    success: false,
+                }),
+                ..ChildDouble::default()
+            },
+            ..WorkerRunnerDouble::default()
+        }),
+        ..Fixture.agent()
+    };
+    agent
+        .work_queue
+        .downcast_mut::<WorkQueueDouble>()
+        .unwrap()
+        .available
+        .push(Message {
+            work_set,
+            queue_message: None,
+        });
+
+    let mut done = false;
+    for _i in 0..20 {
+        (agent, done) = agent.update().await.unwrap();
+        if done {
+            break;
+        }
+    }
+    assert!(done);
+
+    let coordinator: &CoordinatorDouble = agent.coordinator.downcast_ref().unwrap();
+    let events = coordinator.events.read().await.to_vec();
+
+    // The worker is started once, then restarted once.
+    let running = events
+        .iter()
+        .filter(|event| matches!(event, NodeEvent::WorkerEvent(WorkerEvent::Running { .. })))
+        .count();
+    assert_eq!(running, 2);
+
+    assert_eq!(
+        events.last(),
+        Some(&NodeEvent::StateUpdate(StateUpdateEvent::Done {
+            error: Some(format!(
+                "task {} failed after 2 attempts",
+                Fixture.task_id()
+            )),
+            script_output: None,
+        }))
+    );
+
+    tokio::fs::remove_file(crate::done::done_path(agent.machine_id).unwrap())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_done_report_transitions() {
+    let mut agent = Agent {
+        worker_runner: Box::new(WorkerRunnerDouble {
+            child: ChildDouble {
+                exit_status: Some(ExitStatus {
+                    code: Some(0),
+                    signal: None,
+                    success: true,
+                }),
+                ..ChildDouble::default()
+            },
+            ..WorkerRunnerDouble::default()
+        }),
+        ..Fixture.agent()
+    };
+
+    agent
+        .work_queue
+        .downcast_mut::<WorkQueueDouble>()
+        .unwrap()
+        .available
+        .push(Fixture.message());
+
+    let mut done;
+    for _i in 0..10 {
+        (agent, done) = agent.update().await.unwrap();
+        if done {
+            break;
+        }
+    }
+
+    let states: Vec<(NodeState, NodeState)> = agent
+        .transitions
+        .iter()
+        .map(|transition| (transition.from, transition.to))
+        .collect();
+    assert_eq!(
+        states,
+        vec![
+            (NodeState::Free, NodeState::SettingUp),
+            (NodeState::SettingUp, NodeState::Ready),
+            (NodeState::Ready, NodeState::Busy),
+            (NodeState::Busy, NodeState::Done),
+        ]
+    );
+    assert_eq!(agent.work_set, Some(Fixture.work_set()));
+
+    let report = match agent.scheduler.take().unwrap() {
+        Scheduler::Done(state) => state.into_report(agent.machine_id, agent.work_set.as_ref()),
+        _ => panic!("expected done state"),
+    };
+    assert!(matches!(report.cause, DoneCause::WorkersDone));
+    assert_eq!(report.tasks, vec![Fixture.task_id()]);
+    assert!(report.transitions.is_empty());
+}
+
+#[tokio::test]
+async fn test_ping_replies_with_pong() {
+    let coordinator = CoordinatorDouble::default();
+    coordinator.commands.write().await.push(NodeCommand::Ping {
+        token: "3f8e2a1".to_owned(),
+    });
+
+    let agent = Agent {
+        coordinator: Box::new(coordinator),
+        ..Fixture.agent()
+    };
+
+    let agent = agent.execute_pending_commands().await.unwrap();
+    assert!(matches!(agent.scheduler, Some(Scheduler::Free(..))));
+
+    let coordinator: &CoordinatorDouble = agent.coordinator.downcast_ref().unwrap();
+    let events = coordinator.events.read().await.to_vec();
+    assert_eq!(
+        events,
+        vec![NodeEvent::WorkerEvent(WorkerEvent::Pong {
+            token: "3f8e2a1".to_owned(),
+        })]
+    );
+}
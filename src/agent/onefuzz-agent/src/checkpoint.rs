@@ -0,0 +1,108 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use uuid::Uuid;
+
+use crate::work::*;
+use crate::worker::WorkerEvent;
+
+/// Workers that were running when the checkpoint was saved, so that they can
+/// be resumed if the agent crashes.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Checkpoint {
+    pub tasks: Vec<CheckpointTask>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CheckpointTask {
+    pub task_id: TaskId,
+    pub working_dir: PathBuf,
+
+    /// How long the worker had been running.
+    #[serde(with = "duration_secs")]
+    pub elapsed: Duration,
+}
+
+impl Checkpoint {
+    pub fn path(machine_id: Uuid) -> Result<PathBuf> {
+        Ok(onefuzz::fs::onefuzz_root()?.join(format!("checkpoint-{machine_id}.json")))
+    }
+
+    /// Save the checkpoint, replacing any earlier one. The file is written
+    /// next to `path` and renamed over it, so a crash while saving leaves the
+    /// earlier checkpoint intact.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_vec(self)?;
+
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, data)
+            .with_context(|| format!("unable to write checkpoint: {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("unable to save checkpoint: {}", path.display()))?;
+
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("unable to read checkpoint: {}", path.display()))
+            }
+        };
+
+        let checkpoint = serde_json::from_slice(&data)
+            .with_context(|| format!("unable to parse checkpoint: {}", path.display()))?;
+
+        Ok(Some(checkpoint))
+    }
+
+    /// Remove a saved checkpoint, if there is one.
+    pub fn remove(path: &Path) -> Result<()> {
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => {
+                Err(err).with_context(|| format!("unable to remove checkpoint: {}", path.display()))
+            }
+        }
+    }
+
+    /// A `Resumed` event for each task of `work_set` in the checkpoint. Tasks
+    /// that are not part of the work set are not resumed, and are skipped.
+    pub fn resumed_events(&self, work_set: &WorkSet) -> Vec<WorkerEvent> {
+        self.tasks
+            .iter()
+            .filter_map(|task| {
+                let work = work_set.work_unit_by_task_id(task.task_id)?;
+                Some(WorkerEvent::Resumed {
+                    task_id: task.task_id,
+                    previous_elapsed: task.elapsed,
+                    tags: work.tags.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// The saved working directory of each task of `work_set` in the
+    /// checkpoint, so resumed workers continue where they left off.
+    pub fn working_dirs(&self, work_set: &WorkSet) -> HashMap<TaskId, PathBuf> {
+        self.tasks
+            .iter()
+            .filter(|task| work_set.work_unit_by_task_id(task.task_id).is_some())
+            .map(|task| (task.task_id, task.working_dir.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,72 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::collections::HashMap;
+
+use super::*;
+use crate::work::fixture::work_set;
+
+fn checkpoint() -> Checkpoint {
+    Checkpoint {
+        tasks: vec![
+            CheckpointTask {
+                task_id: "eb8ee6b8-6f2d-43b1-aec2-022e9813e86b".parse().unwrap(),
+                working_dir: PathBuf::from("work-dir"),
+                elapsed: Duration::from_secs(90),
+            },
+            CheckpointTask {
+                task_id: Uuid::new_v4(),
+                working_dir: PathBuf::from("other-work-dir"),
+                elapsed: Duration::from_secs(30),
+            },
+        ],
+    }
+}
+
+#[test]
+fn test_checkpoint_save_load() {
+    let path = std::env::temp_dir().join(format!("checkpoint-{}.json", Uuid::new_v4()));
+    assert_eq!(Checkpoint::load(&path).unwrap(), None);
+
+    let checkpoint = checkpoint();
+    checkpoint.save(&path).unwrap();
+    assert_eq!(Checkpoint::load(&path).unwrap(), Some(checkpoint));
+
+    Checkpoint::remove(&path).unwrap();
+    assert_eq!(Checkpoint::load(&path).unwrap(), None);
+
+    // Removing a missing checkpoint is not an error.
+    Checkpoint::remove(&path).unwrap();
+}
+
+#[test]
+fn test_checkpoint_elapsed_secs() {
+    let data = serde_json::to_value(checkpoint()).unwrap();
+    assert_eq!(data["tasks"][0]["elapsed"], 90);
+}
+
+#[test]
+fn test_checkpoint_resumed_events() {
+    let mut work_set = work_set();
+    work_set.work_units[0].tags = HashMap::from([("commit".to_owned(), "3f8e2a1".to_owned())]);
+    let work = &work_set.work_units[0];
+
+    assert_eq!(
+        checkpoint().resumed_events(&work_set),
+        vec![WorkerEvent::Resumed {
+            task_id: work.task_id,
+            previous_elapsed: Duration::from_secs(90),
+            tags: work.tags.clone(),
+        }]
+    );
+}
+
+#[test]
+fn test_checkpoint_working_dirs() {
+    let work_set = work_set();
+
+    assert_eq!(
+        checkpoint().working_dirs(&work_set),
+        HashMap::from([(work_set.work_units[0].task_id, PathBuf::from("work-dir"))])
+    );
+}
@@ -11,6 +11,7 @@ use onefuzz::{
 use onefuzz_telemetry::{InstanceTelemetryKey, MicrosoftTelemetryKey};
 use reqwest_retry::SendRetry;
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     time::{Duration, Instant},
 };
@@ -18,6 +19,9 @@ use tokio::fs;
 use url::Url;
 use uuid::Uuid;
 
+use crate::resources::ResourceLimits;
+use crate::worker::WorkerEventKind;
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub struct StaticConfig {
     pub credentials: Credentials,
@@ -40,6 +44,14 @@ pub struct StaticConfig {
     pub managed: bool,
 
     pub machine_identity: MachineIdentity,
+
+    /// Maximum worker events per second sent to the service, by event kind.
+    #[serde(default)]
+    pub max_worker_event_rate: HashMap<WorkerEventKind, u32>,
+
+    /// Resources the node must have available to accept a work set.
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
 }
 
 fn default_as_true() -> bool {
@@ -77,6 +89,12 @@ struct RawStaticConfig {
     pub managed: bool,
 
     pub machine_identity: Option<MachineIdentity>,
+
+    #[serde(default)]
+    pub max_worker_event_rate: HashMap<WorkerEventKind, u32>,
+
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
 }
 
 impl StaticConfig {
@@ -120,6 +138,8 @@ impl StaticConfig {
             instance_id: config.instance_id,
             managed: config.managed,
             machine_identity,
+            max_worker_event_rate: config.max_worker_event_rate,
+            resource_limits: config.resource_limits,
         };
 
         Ok(config)
@@ -186,6 +206,8 @@ impl StaticConfig {
             instance_id,
             managed: !is_unmanaged,
             machine_identity,
+            max_worker_event_rate: HashMap::new(),
+            resource_limits: ResourceLimits::default(),
         })
     }
 
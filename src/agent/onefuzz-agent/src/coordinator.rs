@@ -2,6 +2,7 @@
 // Licensed under the MIT License.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{Context, Error, Result};
 use downcast_rs::Downcast;
@@ -16,7 +17,7 @@ use uuid::Uuid;
 
 use crate::commands::SshKeyInfo;
 use crate::config::Registration;
-use crate::work::{TaskId, WorkSet};
+use crate::work::{optional_duration_secs, TaskId, WorkSet, WorkUnit};
 use crate::worker::WorkerEvent;
 
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize, Clone)]
@@ -31,6 +32,32 @@ pub enum NodeCommand {
     StopTask(StopTask),
     Stop {},
     StopIfFree {},
+    /// Attach a debugger to the worker of a running task, run a debugger
+    /// command, and log its output.
+    AttachDebugger {
+        task_id: TaskId,
+        command: String,
+    },
+    /// Change the agent's log level, one of `error`, `warn`, `info`, `debug`
+    /// or `trace`, until it is restarted.
+    SetLogLevel {
+        level: String,
+    },
+    /// Ask the agent to reply with a `Pong` worker event carrying `token`,
+    /// verbatim.
+    ///
+    /// This is the preferred liveness check for a `Busy` node: unlike state
+    /// updates, which are only sent on change, each `Pong` can be matched to
+    /// the specific `Ping` that caused it.
+    Ping {
+        token: String,
+    },
+    /// Give the worker of a running task a new work unit, stopping its child
+    /// process and starting a new one in the same working directory.
+    RequeueTask {
+        task_id: TaskId,
+        work_unit: WorkUnit,
+    },
 }
 
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -67,13 +94,38 @@ pub enum NodeState {
     Done,
 }
 
+/// A `NodeState` extended with details about the work in each state.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "state", content = "data")]
+pub enum NodeStateDetail {
+    Free {
+        idle_seconds: u64,
+    },
+    SettingUp {
+        tasks: Vec<TaskId>,
+    },
+    Rebooting {
+        tasks: Vec<TaskId>,
+    },
+    Ready {
+        tasks: Vec<TaskId>,
+    },
+    Busy {
+        tasks: Vec<TaskId>,
+        active_workers: usize,
+        completed_workers: usize,
+        elapsed_seconds: u64,
+    },
+    Done,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct NodeEventEnvelope {
     pub event: NodeEvent,
     pub machine_id: Uuid,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum NodeEvent {
     StateUpdate(StateUpdateEvent),
@@ -96,7 +148,18 @@ pub enum StateUpdateEvent {
     },
     Rebooting,
     Ready,
-    Busy,
+    Busy {
+        /// Seconds since the Unix epoch at which the node started running its work set.
+        started_at: u64,
+
+        /// Service-provided estimate of how long the work set will run, if any.
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "optional_duration_secs"
+        )]
+        estimated_duration: Option<Duration>,
+    },
     Done {
         error: Option<String>,
         script_output: Option<Output>,
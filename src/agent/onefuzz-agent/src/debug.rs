@@ -22,12 +22,21 @@ pub enum DebugOpt {
     NodeEvent(NodeEventOpt),
 
     RunWorker(RunWorkerOpt),
+
+    /// Run a worker for the work set described by `ONEFUZZ_*` environment
+    /// variables, as set on nodes by the service.
+    RunWorkerFromEnv {
+        /// Print the setup steps for the work set instead of running the worker.
+        #[clap(long)]
+        dry_run: bool,
+    },
 }
 
 pub fn debug(opt: DebugOpt) -> Result<()> {
     match opt {
         DebugOpt::NodeEvent(opt) => debug_node_event(opt)?,
         DebugOpt::RunWorker(opt) => debug_run_worker(opt)?,
+        DebugOpt::RunWorkerFromEnv { dry_run } => run_work_set(WorkSet::from_env()?, dry_run)?,
     }
 
     Ok(())
@@ -64,7 +73,10 @@ fn debug_node_event_state_update(state: NodeState) -> Result<()> {
         }
         NodeState::Rebooting => StateUpdateEvent::Rebooting,
         NodeState::Ready => StateUpdateEvent::Ready,
-        NodeState::Busy => StateUpdateEvent::Busy,
+        NodeState::Busy => StateUpdateEvent::Busy {
+            started_at: 0,
+            estimated_duration: None,
+        },
         NodeState::Done => StateUpdateEvent::Done {
             error: None,
             script_output: None,
@@ -90,7 +102,10 @@ fn debug_node_event_worker_event(opt: WorkerEventOpt) -> Result<()> {
     let task_id = uuid::Uuid::new_v4();
 
     let event = match opt {
-        WorkerEventOpt::Running => WorkerEvent::Running { task_id },
+        WorkerEventOpt::Running => WorkerEvent::Running {
+            task_id,
+            tags: Default::default(),
+        },
         WorkerEventOpt::Done { code, signal } => {
             let (code, signal) = match (code, signal) {
                 // Default to ok exit.
@@ -112,6 +127,7 @@ fn debug_node_event_worker_event(opt: WorkerEventOpt) -> Result<()> {
                 stderr,
                 stdout,
                 task_id,
+                tags: Default::default(),
             }
         }
     };
@@ -146,6 +162,10 @@ pub struct RunWorkerOpt {
 
     #[clap(long)]
     extra_url: Option<Url>,
+
+    /// Print the setup steps for the work set instead of running the worker.
+    #[clap(long)]
+    dry_run: bool,
 }
 
 fn debug_run_worker(opt: RunWorkerOpt) -> Result<()> {
@@ -163,15 +183,36 @@ fn debug_run_worker(opt: RunWorkerOpt) -> Result<()> {
         config: config.into(),
         job_id: Uuid::new_v4(),
         task_id,
+        tags: Default::default(),
+        expected_files: vec![],
+        max_retries: 0,
+        output_dir: None,
+        stdin_file: None,
+        max_corpus_size: None,
     };
     let work_set = WorkSet {
+        id: Uuid::new_v4(),
         reboot: false,
         setup_url: BlobContainerUrl::new(opt.setup_url)?,
         extra_setup_url: opt.extra_url.map(BlobContainerUrl::new).transpose()?,
         script: opt.script,
         work_units: vec![work_unit],
+        estimated_duration: None,
     };
 
+    run_work_set(work_set, opt.dry_run)
+}
+
+fn run_work_set(work_set: WorkSet, dry_run: bool) -> Result<()> {
+    if dry_run {
+        use crate::setup::SetupRunner;
+        let setup_runner = SetupRunner {
+            machine_id: Uuid::new_v4(),
+        };
+        println!("{}", setup_runner.dry_run(&work_set)?);
+        return Ok(());
+    }
+
     let rt = tokio::runtime::Runtime::new()?;
     let events = rt.block_on(run_worker(work_set))?;
 
@@ -0,0 +1,156 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::io::{Read, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::{format_err, Context, Result};
+use tokio::time::{sleep, timeout};
+
+// How long to wait for the debugger to exit after detaching.
+const DETACH_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[cfg(target_family = "windows")]
+const DETACH_COMMANDS: &[&str] = &["qd"];
+
+#[cfg(target_family = "unix")]
+const DETACH_COMMANDS: &[&str] = &["detach", "quit"];
+
+/// A command-line debugger attached to a running process.
+///
+/// The debugger's stdout and stderr are collected in the background. The
+/// debugger is killed if the session is dropped without detaching.
+#[derive(Debug)]
+pub struct DebuggerSession {
+    child: Child,
+    stdin: ChildStdin,
+    output: Arc<Mutex<Vec<u8>>>,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl DebuggerSession {
+    pub fn attach(pid: u32) -> Result<Self> {
+        let mut cmd = debugger_command(pid);
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = cmd
+            .spawn()
+            .with_context(|| format!("unable to start debugger: {cmd:?}"))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| format_err!("debugger stdin not captured"))?;
+
+        let output = Arc::new(Mutex::new(vec![]));
+        let mut readers = vec![];
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(spawn_reader(stdout, output.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(spawn_reader(stderr, output.clone()));
+        }
+
+        Ok(Self {
+            child,
+            stdin,
+            output,
+            readers,
+        })
+    }
+
+    /// Returns the debugger output received since the last call.
+    pub fn read_output(&self) -> String {
+        let mut output = self.output.lock().unwrap_or_else(PoisonError::into_inner);
+        let text = String::from_utf8_lossy(&output).into_owned();
+        output.clear();
+        text
+    }
+
+    pub fn send_command(&mut self, cmd: &str) -> Result<()> {
+        writeln!(self.stdin, "{cmd}")
+            .and_then(|_| self.stdin.flush())
+            .with_context(|| format!("unable to send debugger command: {cmd}"))
+    }
+
+    /// Detach from the process, leaving it running, and wait for the debugger
+    /// to exit. Returns the debugger output not yet read.
+    pub async fn detach(mut self) -> Result<String> {
+        for cmd in DETACH_COMMANDS {
+            self.send_command(cmd)?;
+        }
+
+        let exited = timeout(DETACH_TIMEOUT, async {
+            while self.child.try_wait()?.is_none() {
+                sleep(Duration::from_millis(100)).await;
+            }
+            Ok::<(), anyhow::Error>(())
+        })
+        .await;
+
+        match exited {
+            Ok(result) => result?,
+            Err(_) => {
+                warn!("debugger did not exit after detaching, killing it");
+                self.child.kill()?;
+                self.child.wait()?;
+            }
+        }
+
+        // The output pipes are closed once the debugger exits.
+        for reader in std::mem::take(&mut self.readers) {
+            let _ = reader.join();
+        }
+
+        Ok(self.read_output())
+    }
+}
+
+impl Drop for DebuggerSession {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+fn debugger_command(pid: u32) -> Command {
+    // Attach noninvasively, so detaching cannot affect the worker.
+    let mut cmd = Command::new("cdb.exe");
+    cmd.arg("-pv").arg("-p").arg(pid.to_string());
+    cmd
+}
+
+#[cfg(target_family = "unix")]
+fn debugger_command(pid: u32) -> Command {
+    let mut cmd = Command::new("gdb");
+    cmd.arg("--quiet")
+        .arg("--nx")
+        .arg("--pid")
+        .arg(pid.to_string());
+    cmd
+}
+
+fn spawn_reader(
+    mut stream: impl Read + Send + 'static,
+    output: Arc<Mutex<Vec<u8>>>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(count) => output
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .extend_from_slice(&buf[..count]),
+            }
+        }
+    })
+}
@@ -0,0 +1,172 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::worker::{WorkerEvent, WorkerEventKind};
+
+/// Length of the window that `max_rate` limits are counted over.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// How often the number of dropped events is reported.
+const DROPPED_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Rate-limits worker events before they are sent to the service.
+///
+/// `Done` events carry the exit status of the worker, including crashes, so
+/// they are never dropped, whatever limit is configured for them.
+#[derive(Debug)]
+pub struct WorkerEventFilter {
+    /// Maximum events per second, by event kind. Kinds without an entry are
+    /// not limited.
+    max_rate: HashMap<WorkerEventKind, u32>,
+
+    window_start: Instant,
+    window_counts: HashMap<WorkerEventKind, u32>,
+
+    last_report: Instant,
+    dropped: HashMap<WorkerEventKind, u64>,
+}
+
+impl WorkerEventFilter {
+    pub fn new(max_rate: HashMap<WorkerEventKind, u32>) -> Self {
+        let now = Instant::now();
+
+        Self {
+            max_rate,
+            window_start: now,
+            window_counts: HashMap::new(),
+            last_report: now,
+            dropped: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if the event should be sent, counting it against its rate.
+    pub fn allow(&mut self, event: &WorkerEvent) -> bool {
+        self.allow_at(event, Instant::now())
+    }
+
+    fn allow_at(&mut self, event: &WorkerEvent, now: Instant) -> bool {
+        let kind = event.kind();
+
+        if kind == WorkerEventKind::Done {
+            return true;
+        }
+
+        let max_rate = match self.max_rate.get(&kind) {
+            Some(max_rate) => *max_rate,
+            None => return true,
+        };
+
+        if now.duration_since(self.window_start) >= RATE_WINDOW {
+            self.window_start = now;
+            self.window_counts.clear();
+        }
+
+        let count = self.window_counts.entry(kind).or_default();
+
+        if *count < max_rate {
+            *count += 1;
+            true
+        } else {
+            *self.dropped.entry(kind).or_default() += 1;
+            false
+        }
+    }
+
+    /// Take the counts of events dropped since the last report, if any were
+    /// dropped and the report interval has elapsed.
+    pub fn take_dropped(&mut self) -> Option<HashMap<WorkerEventKind, u64>> {
+        self.take_dropped_at(Instant::now())
+    }
+
+    fn take_dropped_at(&mut self, now: Instant) -> Option<HashMap<WorkerEventKind, u64>> {
+        if self.dropped.is_empty() || now.duration_since(self.last_report) < DROPPED_REPORT_INTERVAL
+        {
+            return None;
+        }
+
+        self.last_report = now;
+        Some(std::mem::take(&mut self.dropped))
+    }
+}
+
+impl Default for WorkerEventFilter {
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use onefuzz::process::ExitStatus;
+    use uuid::Uuid;
+
+    fn running() -> WorkerEvent {
+        WorkerEvent::Running {
+            task_id: Uuid::new_v4(),
+            tags: Default::default(),
+        }
+    }
+
+    fn done() -> WorkerEvent {
+        WorkerEvent::Done {
+            task_id: Uuid::new_v4(),
+            exit_status: ExitStatus {
+                code: Some(1),
+                signal: None,
+                success: false,
+            },
+            stderr: String::default(),
+            stdout: String::default(),
+            tags: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_filter_unlimited() {
+        let mut filter = WorkerEventFilter::default();
+
+        for _ in 0..100 {
+            assert!(filter.allow(&running()));
+        }
+        assert!(filter.take_dropped().is_none());
+    }
+
+    #[test]
+    fn test_filter_rate_limit() {
+        let mut filter = WorkerEventFilter::new(HashMap::from([(WorkerEventKind::Running, 2)]));
+        let start = filter.window_start;
+
+        assert!(filter.allow_at(&running(), start));
+        assert!(filter.allow_at(&running(), start));
+        assert!(!filter.allow_at(&running(), start));
+        assert!(!filter.allow_at(&running(), start + Duration::from_millis(500)));
+
+        // A new window starts counting from zero.
+        assert!(filter.allow_at(&running(), start + RATE_WINDOW));
+
+        assert!(filter.take_dropped_at(start + RATE_WINDOW).is_none());
+
+        let dropped = filter
+            .take_dropped_at(start + DROPPED_REPORT_INTERVAL)
+            .unwrap();
+        assert_eq!(dropped, HashMap::from([(WorkerEventKind::Running, 2)]));
+        assert!(filter
+            .take_dropped_at(start + DROPPED_REPORT_INTERVAL * 2)
+            .is_none());
+    }
+
+    #[test]
+    fn test_filter_never_drops_done() {
+        let mut filter = WorkerEventFilter::new(HashMap::from([(WorkerEventKind::Done, 0)]));
+        let start = filter.window_start;
+
+        for _ in 0..10 {
+            assert!(filter.allow_at(&done(), start));
+        }
+        assert!(filter.dropped.is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::str::FromStr;
+use std::sync::{PoisonError, RwLock};
+
+use anyhow::Result;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+static LOGGER: DynamicLogger = DynamicLogger {
+    env: RwLock::new(None),
+    all: RwLock::new(None),
+    level: RwLock::new(None),
+};
+
+/// Logger whose level can be changed at runtime by the `SetLogLevel` command.
+///
+/// Until the level is set, records are filtered by `RUST_LOG`, as with
+/// `env_logger::init()`. Once set, the level replaces the `RUST_LOG` filter.
+struct DynamicLogger {
+    /// Logger filtered by `RUST_LOG`.
+    env: RwLock<Option<env_logger::Logger>>,
+
+    /// Unfiltered logger, used when the level has been set.
+    all: RwLock<Option<env_logger::Logger>>,
+
+    /// Level set at runtime, if any.
+    level: RwLock<Option<LevelFilter>>,
+}
+
+impl DynamicLogger {
+    fn level(&self) -> Option<LevelFilter> {
+        *self.level.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn with_logger(&self, metadata: &Metadata, f: impl FnOnce(&env_logger::Logger)) {
+        let logger = match self.level() {
+            Some(level) if metadata.level() <= level => &self.all,
+            Some(_) => return,
+            None => &self.env,
+        };
+
+        if let Some(logger) = &*logger.read().unwrap_or_else(PoisonError::into_inner) {
+            f(logger);
+        }
+    }
+}
+
+impl Log for DynamicLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let mut enabled = false;
+        self.with_logger(metadata, |logger| enabled = logger.enabled(metadata));
+        enabled
+    }
+
+    fn log(&self, record: &Record) {
+        self.with_logger(record.metadata(), |logger| logger.log(record));
+    }
+
+    fn flush(&self) {
+        for logger in [&self.env, &self.all] {
+            if let Some(logger) = &*logger.read().unwrap_or_else(PoisonError::into_inner) {
+                logger.flush();
+            }
+        }
+    }
+}
+
+/// Initialize logging from `RUST_LOG`, allowing the level to be changed later
+/// with `set_log_level()`.
+pub fn init() -> Result<()> {
+    let env = env_logger::Builder::from_default_env().build();
+    let all = env_logger::Builder::new()
+        .filter_level(LevelFilter::Trace)
+        .build();
+
+    log::set_max_level(env.filter());
+    *LOGGER.env.write().unwrap_or_else(PoisonError::into_inner) = Some(env);
+    *LOGGER.all.write().unwrap_or_else(PoisonError::into_inner) = Some(all);
+
+    log::set_logger(&LOGGER)?;
+
+    Ok(())
+}
+
+/// Change the log level of the agent, replacing the `RUST_LOG` filter.
+///
+/// The change is logged at the old level, so that it is recorded even when
+/// the new level is less verbose.
+pub fn set_log_level(level: &str) -> Result<()> {
+    let level = Level::from_str(level)
+        .map_err(|_| format_err!("invalid log level: {}", level))?
+        .to_level_filter();
+
+    let old = log::max_level();
+    if let Some(old) = old.to_level() {
+        log::log!(old, "changing log level from {} to {}", old, level);
+    }
+
+    *LOGGER.level.write().unwrap_or_else(PoisonError::into_inner) = Some(level);
+    log::set_max_level(level);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_log_level_invalid() {
+        assert!(set_log_level("verbose").is_err());
+        assert!(set_log_level("").is_err());
+    }
+}
@@ -12,9 +12,16 @@ extern crate onefuzz_telemetry;
 extern crate onefuzz;
 
 use crate::{
-    config::StaticConfig, coordinator::StateUpdateEvent, heartbeat::init_agent_heartbeat,
-    panic::set_panic_handler, work::WorkSet, worker::WorkerEvent,
+    checkpoint::Checkpoint,
+    config::StaticConfig,
+    coordinator::StateUpdateEvent,
+    heartbeat::init_agent_heartbeat,
+    panic::set_panic_handler,
+    reboot::RebootContext,
+    work::{TaskId, WorkSet},
+    worker::WorkerEvent,
 };
+use std::collections::HashMap;
 use std::fs::OpenOptions;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
@@ -29,16 +36,22 @@ use uuid::Uuid;
 
 pub mod agent;
 pub mod buffer;
+pub mod checkpoint;
 pub mod commands;
 pub mod config;
 pub mod coordinator;
 pub mod debug;
+pub mod debugger;
 pub mod done;
+pub mod event_filter;
 pub mod failure;
 pub mod heartbeat;
+pub mod log_level;
 pub mod log_uploader;
 pub mod panic;
+pub mod preflight;
 pub mod reboot;
+pub mod resources;
 pub mod scheduler;
 pub mod setup;
 pub mod validations;
@@ -77,7 +90,7 @@ struct RunOpt {
 }
 
 fn main() -> Result<()> {
-    env_logger::init();
+    log_level::init()?;
 
     let opt = Opt::parse();
 
@@ -248,6 +261,53 @@ async fn load_config(opt: RunOpt) -> Result<StaticConfig> {
     Ok(config)
 }
 
+// If the agent stopped while workers were running, restart the work set they were
+// part of, in the same working directories, instead of failing it. The restarted
+// workers are reported as resumed, with how long they had been running.
+async fn resume_from_checkpoint(
+    coordinator: &mut coordinator::Coordinator,
+) -> Result<Option<(RebootContext, HashMap<TaskId, PathBuf>)>> {
+    let machine_id = coordinator.get_machine_id();
+    let path = Checkpoint::path(machine_id)?;
+
+    let checkpoint = match Checkpoint::load(&path) {
+        Ok(Some(checkpoint)) => checkpoint,
+        Ok(None) => return Ok(None),
+        Err(err) => {
+            warn!("ignoring unreadable checkpoint: {:?}", err);
+            Checkpoint::remove(&path)?;
+            return Ok(None);
+        }
+    };
+
+    // The resumed workers are checkpointed again once they are running.
+    Checkpoint::remove(&path)?;
+
+    let work_set = match WorkSet::load_from_fs_context(machine_id).await? {
+        Some(work_set) => work_set,
+        None => {
+            info!("ignoring checkpoint without a saved work set");
+            return Ok(None);
+        }
+    };
+
+    let events = checkpoint.resumed_events(&work_set);
+    if events.is_empty() {
+        return Ok(None);
+    }
+
+    warn!(
+        "onefuzz-agent stopped unexpectedly, resuming {} workers from checkpoint",
+        events.len()
+    );
+    for event in events {
+        coordinator.emit_event(event.into()).await?;
+    }
+
+    let working_dirs = checkpoint.working_dirs(&work_set);
+    Ok(Some((RebootContext::new(work_set), working_dirs)))
+}
+
 async fn check_existing_worksets(coordinator: &mut coordinator::Coordinator) -> Result<()> {
     // Having existing worksets at this point means the supervisor crashed. If
     // that is the case, mark each of the work units within the workset as
@@ -276,6 +336,7 @@ async fn check_existing_worksets(coordinator: &mut coordinator::Coordinator) ->
                     signal: None,
                     success: false,
                 },
+                tags: unit.tags.clone(),
             };
             coordinator.emit_event(event.into()).await?;
         }
@@ -325,15 +386,26 @@ async fn run_agent(config: StaticConfig, reset_node: bool) -> Result<()> {
     debug!("initialized coordinator");
 
     let reboot = reboot::Reboot::new(config.machine_identity.machine_id);
-    let reboot_context = reboot.load_context().await?;
+    let mut reboot_context = reboot.load_context().await?;
     if reset_node {
         WorkSet::remove_context(config.machine_identity.machine_id).await?;
+        Checkpoint::remove(&Checkpoint::path(config.machine_identity.machine_id)?)?;
+    }
+
+    let mut working_dirs = HashMap::new();
+    if reboot_context.is_none() {
+        if let Some((context, dirs)) = resume_from_checkpoint(&mut coordinator).await? {
+            reboot_context = Some(context);
+            working_dirs = dirs;
+        }
     }
 
     if reboot_context.is_none() {
         check_existing_worksets(&mut coordinator).await?;
     }
-    let scheduler = scheduler::Scheduler::new(reboot_context);
+    let scheduler = scheduler::Scheduler::new(reboot_context)
+        .with_working_dirs(working_dirs)
+        .with_resource_limits(config.resource_limits.clone());
     debug!("loaded scheduler: {}", scheduler);
 
     let work_queue = work::WorkQueue::new(registration.clone())?;
@@ -361,6 +433,7 @@ async fn run_agent(config: StaticConfig, reset_node: bool) -> Result<()> {
         agent_heartbeat,
         config.managed,
         config.machine_identity.machine_id,
+        event_filter::WorkerEventFilter::new(config.max_worker_event_rate.clone()),
     );
 
     info!("running agent");
@@ -0,0 +1,243 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use crate::work::{SyncedDirs, WorkSet, WorkUnit};
+
+pub const INVALID_CONFIG: &str = "invalid_config";
+pub const TARGET_MISSING: &str = "target_missing";
+pub const TARGET_NOT_EXECUTABLE: &str = "target_not_executable";
+pub const EMPTY_INPUTS: &str = "empty_inputs";
+pub const WORLD_WRITABLE_SETUP: &str = "world_writable_setup";
+
+/// A likely problem with a work set's configuration, found before its workers
+/// are started. Warnings are logged, but do not stop the work set from running.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PreflightWarning {
+    /// Kind of problem, which can be listed in the `suppress_warnings` field of
+    /// a task config to skip the warning.
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl PreflightWarning {
+    fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for PreflightWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)
+    }
+}
+
+/// The parts of a task config that are checked.
+#[derive(Debug, Default, Deserialize)]
+struct TaskConfig {
+    target_exe: Option<PathBuf>,
+    inputs: Option<SyncedDirs>,
+    readonly_inputs: Option<SyncedDirs>,
+    #[serde(default)]
+    suppress_warnings: Vec<String>,
+}
+
+/// Check the configuration of each work unit of a work set, and the setup
+/// directory they share.
+///
+/// Warnings about the setup directory are skipped if any work unit suppresses
+/// them.
+pub fn check_work_set(
+    work_set: &WorkSet,
+    setup_dir: &Path,
+    machine_id: Uuid,
+) -> Vec<PreflightWarning> {
+    let mut warnings = vec![];
+    let mut suppressed = vec![];
+
+    for work in &work_set.work_units {
+        let config: TaskConfig = match serde_json::from_str(work.config.expose_ref().as_str()) {
+            Ok(config) => config,
+            Err(err) => {
+                warnings.push(PreflightWarning::new(
+                    INVALID_CONFIG,
+                    format!("unable to parse config of task {}: {}", work.task_id, err),
+                ));
+                continue;
+            }
+        };
+
+        warnings.extend(
+            check_work_unit(work, &config, setup_dir, machine_id)
+                .into_iter()
+                .filter(|warning| !config.suppress_warnings.iter().any(|c| c == warning.code)),
+        );
+        suppressed.extend(config.suppress_warnings);
+    }
+
+    warnings.extend(
+        check_setup_dir(setup_dir)
+            .into_iter()
+            .filter(|warning| !suppressed.iter().any(|c| c == warning.code)),
+    );
+
+    warnings
+}
+
+fn check_work_unit(
+    work: &WorkUnit,
+    config: &TaskConfig,
+    setup_dir: &Path,
+    machine_id: Uuid,
+) -> Vec<PreflightWarning> {
+    let mut warnings = vec![];
+
+    if let Some(target_exe) = &config.target_exe {
+        match resolve_target_exe(setup_dir, target_exe) {
+            Some(path) if !is_executable(&path) => warnings.push(PreflightWarning::new(
+                TARGET_NOT_EXECUTABLE,
+                format!(
+                    "target of task {} is not executable: {}",
+                    work.task_id,
+                    path.display()
+                ),
+            )),
+            Some(_) => {}
+            None => warnings.push(PreflightWarning::new(
+                TARGET_MISSING,
+                format!(
+                    "target of task {} not found in setup dir: {}",
+                    work.task_id,
+                    target_exe.display()
+                ),
+            )),
+        }
+    }
+
+    // Inputs are synced by the task once it starts, so only directories that
+    // are already on the node can be checked: local containers, and corpora
+    // left by an earlier run.
+    let output_dir = work.output_dir(machine_id).ok();
+    let inputs = config
+        .inputs
+        .iter()
+        .chain(config.readonly_inputs.iter())
+        .flat_map(SyncedDirs::iter);
+    for dir in inputs {
+        let path = match (
+            dir.remote_path.as_ref().and_then(|url| url.as_file_path()),
+            &output_dir,
+        ) {
+            (Some(path), _) => path,
+            (None, Some(output_dir)) => output_dir.join(&dir.local_path),
+            (None, None) => continue,
+        };
+
+        if is_empty_dir(&path) {
+            warnings.push(PreflightWarning::new(
+                EMPTY_INPUTS,
+                format!(
+                    "input corpus of task {} is empty: {}",
+                    work.task_id,
+                    path.display()
+                ),
+            ));
+        }
+    }
+
+    warnings
+}
+
+// Target paths are relative to the setup dir, and may use a `{setup_dir}`
+// placeholder or a legacy `setup/` prefix.
+fn resolve_target_exe(setup_dir: &Path, target_exe: &Path) -> Option<PathBuf> {
+    let target_exe = target_exe.to_string_lossy();
+    let expanded = target_exe.replace("{setup_dir}", &setup_dir.to_string_lossy());
+
+    let mut candidates = vec![setup_dir.join(&expanded)];
+    if let Some(legacy) = expanded.strip_prefix("setup/") {
+        candidates.push(setup_dir.join(legacy));
+    }
+
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+#[cfg(target_family = "unix")]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path)
+        .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(target_family = "windows")]
+fn is_executable(_path: &Path) -> bool {
+    // executability is decided by the file extension, which is set by the user
+    true
+}
+
+// A missing directory is not empty, since it is created when inputs are synced.
+fn is_empty_dir(path: &Path) -> bool {
+    match fs::read_dir(path) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn check_setup_dir(setup_dir: &Path) -> Vec<PreflightWarning> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut world_writable = vec![];
+    let mut dirs = vec![setup_dir.to_owned()];
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            // don't follow symlinks out of the setup dir
+            let metadata = match fs::symlink_metadata(entry.path()) {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else if metadata.is_file() && metadata.permissions().mode() & 0o002 != 0 {
+                world_writable.push(entry.path());
+            }
+        }
+    }
+
+    match world_writable.first() {
+        Some(first) => vec![PreflightWarning::new(
+            WORLD_WRITABLE_SETUP,
+            format!(
+                "setup dir has {} world-writable files, including: {}",
+                world_writable.len(),
+                first.display()
+            ),
+        )],
+        None => vec![],
+    }
+}
+
+#[cfg(target_family = "windows")]
+fn check_setup_dir(_setup_dir: &Path) -> Vec<PreflightWarning> {
+    // files don't have a world-writable mode bit
+    vec![]
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,127 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use serde_json::json;
+
+use super::*;
+use crate::work::fixture::{work_set, work_unit};
+
+struct Fixture {
+    dir: PathBuf,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("preflight-{}", Uuid::new_v4()));
+        fs::create_dir_all(dir.join("setup")).unwrap();
+        Self { dir }
+    }
+
+    fn setup_dir(&self) -> PathBuf {
+        self.dir.join("setup")
+    }
+
+    fn work_set(&self, config: serde_json::Value) -> WorkSet {
+        let work_unit = WorkUnit {
+            config: config.to_string().into(),
+            output_dir: Some(self.dir.clone()),
+            ..work_unit()
+        };
+
+        WorkSet {
+            work_units: vec![work_unit],
+            ..work_set()
+        }
+    }
+
+    fn check(&self, config: serde_json::Value) -> Vec<&'static str> {
+        check_work_set(&self.work_set(config), &self.setup_dir(), Uuid::new_v4())
+            .into_iter()
+            .map(|warning| warning.code)
+            .collect()
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[test]
+fn test_preflight_invalid_config() {
+    let fixture = Fixture::new();
+    assert_eq!(fixture.check(json!([])), vec![INVALID_CONFIG]);
+}
+
+#[test]
+fn test_preflight_target_missing() {
+    let fixture = Fixture::new();
+    assert_eq!(
+        fixture.check(json!({ "target_exe": "fuzz.exe" })),
+        vec![TARGET_MISSING]
+    );
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn test_preflight_target_not_executable() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = Fixture::new();
+    let target = fixture.setup_dir().join("fuzz.exe");
+    fs::write(&target, "").unwrap();
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o644)).unwrap();
+
+    assert_eq!(
+        fixture.check(json!({ "target_exe": "fuzz.exe" })),
+        vec![TARGET_NOT_EXECUTABLE]
+    );
+
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o755)).unwrap();
+    assert!(fixture
+        .check(json!({ "target_exe": "fuzz.exe" }))
+        .is_empty());
+    assert!(fixture
+        .check(json!({ "target_exe": "{setup_dir}/fuzz.exe" }))
+        .is_empty());
+}
+
+#[test]
+fn test_preflight_empty_inputs() {
+    let fixture = Fixture::new();
+    fs::create_dir(fixture.dir.join("inputs")).unwrap();
+
+    let config = json!({ "inputs": { "path": "inputs", "url": null } });
+    assert_eq!(fixture.check(config), vec![EMPTY_INPUTS]);
+
+    // inputs that have not been synced yet can't be checked
+    let config = json!({ "readonly_inputs": [{ "path": "readonly_inputs", "url": null }] });
+    assert!(fixture.check(config).is_empty());
+
+    let config = json!({
+        "inputs": { "path": "inputs", "url": null },
+        "suppress_warnings": [EMPTY_INPUTS],
+    });
+    assert!(fixture.check(config).is_empty());
+
+    fs::write(fixture.dir.join("inputs").join("seed"), "hello").unwrap();
+    let config = json!({ "inputs": { "path": "inputs", "url": null } });
+    assert!(fixture.check(config).is_empty());
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn test_preflight_world_writable_setup() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = Fixture::new();
+    let script = fixture.setup_dir().join("setup.sh");
+    fs::write(&script, "").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o666)).unwrap();
+
+    assert_eq!(fixture.check(json!({})), vec![WORLD_WRITABLE_SETUP]);
+
+    let config = json!({ "suppress_warnings": [WORLD_WRITABLE_SETUP] });
+    assert!(fixture.check(config).is_empty());
+}
@@ -3,6 +3,7 @@
 
 use std::path::PathBuf;
 use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
 use downcast_rs::Downcast;
@@ -17,7 +18,12 @@ pub trait IReboot: Downcast {
 
     async fn load_context(&self) -> Result<Option<RebootContext>>;
 
-    fn invoke(&self) -> Result<()>;
+    /// Reboot the machine. On success, this does not return. Returns an error
+    /// if the machine has not rebooted within `timeout`.
+    ///
+    /// Only test doubles return `Ok`, to simulate a reboot that the agent
+    /// survives.
+    async fn invoke(&self, timeout: Duration) -> Result<()>;
 }
 
 impl_downcast!(IReboot);
@@ -32,8 +38,8 @@ impl IReboot for Reboot {
         self.load_context().await
     }
 
-    fn invoke(&self) -> Result<()> {
-        self.invoke()
+    async fn invoke(&self, timeout: Duration) -> Result<()> {
+        self.invoke(timeout).await
     }
 }
 
@@ -62,10 +68,6 @@ impl Reboot {
     }
 
     pub async fn load_context(&self) -> Result<Option<RebootContext>> {
-        #[derive(Deserialize)]
-        struct _RebootContext {
-            pub work_set: WorkSet,
-        }
         use std::io::ErrorKind;
         let path = reboot_context_path(self.machine_id)?;
 
@@ -82,20 +84,26 @@ impl Reboot {
         }
 
         let data = data?;
-        let ctx: _RebootContext = serde_json::from_slice(&data)?;
+        let ctx = match RebootContext::version_of(&data) {
+            Ok(1) => RebootContext::migrate_v1_to_v2(&data),
+            _ => RebootContext::from_slice(&data),
+        }
+        .with_context(|| format!("unable to load reboot context: {}", path.display()))?;
+
+        if let Some(elapsed) = ctx.time_since_saved() {
+            info!("machine rebooted {:?} after saving reboot context", elapsed);
+        }
 
         fs::remove_file(&path)
             .await
             .with_context(|| format!("unable to remove reboot context: {}", path.display()))?;
 
         info!("loaded reboot context");
-        Ok(Some(RebootContext {
-            work_set: ctx.work_set,
-        }))
+        Ok(Some(ctx))
     }
 
     #[cfg(target_family = "unix")]
-    pub fn invoke(&self) -> Result<()> {
+    pub async fn invoke(&self, timeout: Duration) -> Result<()> {
         match std::path::Path::new("/.dockerenv").try_exists() {
             Ok(true) => {
                 info!("running inside docker, exiting instead of rebooting");
@@ -104,13 +112,13 @@ impl Reboot {
             _ => {
                 info!("invoking local reboot command");
                 Command::new("reboot").arg("-f").status()?;
-                self.wait_for_reboot()
+                wait_for_reboot(timeout).await
             }
         }
     }
 
     #[cfg(target_family = "windows")]
-    pub fn invoke(&self) -> Result<()> {
+    pub async fn invoke(&self, timeout: Duration) -> Result<()> {
         info!("invoking local reboot command");
         Command::new("powershell.exe")
             .arg("-Command")
@@ -118,30 +126,123 @@ impl Reboot {
             .arg("-Force")
             .status()?;
 
-        self.wait_for_reboot()
+        wait_for_reboot(timeout).await
     }
+}
 
-    fn wait_for_reboot(&self) -> Result<()> {
-        use std::{thread, time};
-
-        debug!("waiting for reboot");
+/// The agent process does not survive a real reboot, so this only returns if
+/// the machine has not rebooted within `timeout`.
+async fn wait_for_reboot(timeout: Duration) -> Result<()> {
+    debug!("waiting for reboot");
 
-        // 10 minutes.
-        let d = time::Duration::from_secs(60 * 10);
-        thread::sleep(d);
+    tokio::time::sleep(timeout).await;
 
-        anyhow::bail!("Failed to reboot in 10 minutes")
-    }
+    bail!("Failed to reboot in {:?}", timeout)
 }
 
+/// Version of the `RebootContext` schema written by this agent.
+///
+/// Bump this whenever the serialized form of `RebootContext` changes, so that an
+/// upgraded agent can detect contexts saved by a previous release.
+///
+/// Version 2 added `saved_at`.
+pub const REBOOT_CONTEXT_VERSION: u32 = 2;
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct RebootContext {
+    /// Schema version. Contexts saved before versioning was introduced are version 1.
+    #[serde(default = "default_reboot_context_version")]
+    pub version: u32,
+
     pub work_set: WorkSet,
+
+    /// Seconds since the Unix epoch at which the context was created, or `None`
+    /// if it was migrated from version 1, which did not record it.
+    pub saved_at: Option<u64>,
+}
+
+/// The schema of version 1 reboot contexts, see `RebootContext::migrate_v1_to_v2`.
+#[derive(Deserialize)]
+struct RebootContextV1 {
+    work_set: WorkSet,
+}
+
+fn default_reboot_context_version() -> u32 {
+    1
 }
 
 impl RebootContext {
     pub fn new(work_set: WorkSet) -> Self {
-        Self { work_set }
+        let saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|since_epoch| since_epoch.as_secs());
+
+        Self {
+            version: REBOOT_CONTEXT_VERSION,
+            work_set,
+            saved_at,
+        }
+    }
+
+    /// Time elapsed since the context was created, if it is known.
+    pub fn time_since_saved(&self) -> Option<Duration> {
+        let saved_at = UNIX_EPOCH + Duration::from_secs(self.saved_at?);
+        SystemTime::now().duration_since(saved_at).ok()
+    }
+
+    /// Schema version of a saved reboot context, without deserializing the rest of it.
+    pub fn version_of(data: &[u8]) -> Result<u32> {
+        #[derive(Deserialize)]
+        struct Versioned {
+            #[serde(default = "default_reboot_context_version")]
+            version: u32,
+        }
+
+        let Versioned { version } = serde_json::from_slice(data)?;
+        Ok(version)
+    }
+
+    /// Upgrade a serialized version 1 reboot context to version 2.
+    ///
+    /// Version 1 did not record when the context was saved, so `saved_at` is
+    /// left unset.
+    pub fn migrate_v1_to_v2(data: &[u8]) -> Result<Self> {
+        let version = Self::version_of(data)?;
+        if version != 1 {
+            bail!(
+                "expected version 1 reboot context, found version {}",
+                version
+            );
+        }
+
+        let RebootContextV1 { work_set } = serde_json::from_slice(data)?;
+        let ctx = Self {
+            version: 2,
+            work_set,
+            saved_at: None,
+        };
+
+        info!("migrated reboot context from version 1 to 2");
+        Ok(ctx)
+    }
+
+    /// Deserialize a saved reboot context, checking its schema version first so that
+    /// an unsupported context produces a clear error instead of a field mismatch.
+    ///
+    /// Older versions must be migrated first, see `RebootContext::migrate_v1_to_v2`.
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let version = Self::version_of(data)?;
+
+        if version != REBOOT_CONTEXT_VERSION {
+            bail!(
+                "unsupported reboot context version: {}, expected {}",
+                version,
+                REBOOT_CONTEXT_VERSION
+            );
+        }
+
+        Ok(serde_json::from_slice(data)?)
     }
 }
 
@@ -151,3 +252,6 @@ fn reboot_context_path(machine_id: Uuid) -> Result<PathBuf> {
 
 #[cfg(test)]
 pub mod double;
+
+#[cfg(test)]
+mod tests;
@@ -38,7 +38,7 @@ impl IReboot for RebootDouble {
         Ok(saved.pop())
     }
 
-    fn invoke(&self) -> Result<()> {
+    async fn invoke(&self, _timeout: Duration) -> Result<()> {
         self.invoked.swap(true, atomic::Ordering::SeqCst);
         Ok(())
     }
@@ -0,0 +1,59 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::*;
+use crate::work::fixture::work_set;
+
+#[test]
+fn test_reboot_context_roundtrip() {
+    let ctx = RebootContext::new(work_set());
+    assert_eq!(ctx.version, REBOOT_CONTEXT_VERSION);
+    assert!(ctx.saved_at.is_some());
+
+    let data = serde_json::to_vec(&ctx).unwrap();
+    let loaded = RebootContext::from_slice(&data).unwrap();
+
+    assert_eq!(loaded, ctx);
+}
+
+#[test]
+fn test_reboot_context_unversioned() {
+    // Contexts saved before the `version` field existed only contain the work set.
+    let data = serde_json::to_vec(&serde_json::json!({ "work_set": work_set() })).unwrap();
+    assert_eq!(RebootContext::version_of(&data).unwrap(), 1);
+
+    // They must be migrated before they can be loaded.
+    assert!(RebootContext::from_slice(&data).is_err());
+}
+
+#[test]
+fn test_reboot_context_migrate_v1_to_v2() {
+    let work_set = work_set();
+    let data = serde_json::to_vec(&serde_json::json!({ "version": 1, "work_set": &work_set }));
+
+    let migrated = RebootContext::migrate_v1_to_v2(&data.unwrap()).unwrap();
+    assert_eq!(migrated.version, 2);
+    assert_eq!(migrated.work_set, work_set);
+    assert_eq!(migrated.saved_at, None);
+}
+
+#[test]
+fn test_reboot_context_migrate_wrong_version() {
+    let data = serde_json::to_vec(&RebootContext::new(work_set())).unwrap();
+
+    assert!(RebootContext::migrate_v1_to_v2(&data).is_err());
+}
+
+#[test]
+fn test_reboot_context_unsupported_version() {
+    let data = serde_json::to_vec(&serde_json::json!({
+        "version": REBOOT_CONTEXT_VERSION + 1,
+        "work_set": "not a work set",
+    }))
+    .unwrap();
+    let err = RebootContext::from_slice(&data).unwrap_err();
+
+    assert!(err
+        .to_string()
+        .contains("unsupported reboot context version"));
+}
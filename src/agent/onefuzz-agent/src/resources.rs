@@ -0,0 +1,201 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::work::WorkSet;
+
+/// Limits on the work a node accepts, checked by `State<Free>::offers()`
+/// before a work set is claimed. Unset limits are not checked.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct ResourceLimits {
+    /// Maximum number of workers, one per work unit, that a work set may run.
+    pub max_workers: Option<usize>,
+
+    /// Minimum memory that must be available, in bytes.
+    pub min_available_memory: Option<u64>,
+
+    /// Minimum free disk space under the OneFuzz root directory, in bytes.
+    pub min_available_disk: Option<u64>,
+}
+
+/// Memory and disk space available to new work, in bytes. Resources that could
+/// not be measured are `None`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Available {
+    pub memory: Option<u64>,
+    pub disk: Option<u64>,
+}
+
+impl Available {
+    /// Measure the resources needed by `limits`. Failures are logged, and the
+    /// resource is left unmeasured.
+    pub fn measure(limits: &ResourceLimits) -> Self {
+        let memory = limits.min_available_memory.and_then(|_| {
+            onefuzz::memory::available_bytes()
+                .map_err(|err| warn!("unable to measure available memory: {:?}", err))
+                .ok()
+        });
+
+        let disk = limits.min_available_disk.and_then(|_| {
+            onefuzz::fs::onefuzz_root()
+                .and_then(|root| available_disk_bytes(&root))
+                .map_err(|err| warn!("unable to measure available disk space: {:?}", err))
+                .ok()
+        });
+
+        Self { memory, disk }
+    }
+}
+
+impl ResourceLimits {
+    /// Reasons `work_set` exceeds the limits, given the `available` resources.
+    /// Empty if the node can run it.
+    ///
+    /// Unmeasured resources are not held against the work set.
+    pub fn shortfalls(&self, work_set: &WorkSet, available: &Available) -> Vec<String> {
+        let mut shortfalls = vec![];
+
+        if let Some(max_workers) = self.max_workers {
+            let workers = work_set.work_units.len();
+            if workers > max_workers {
+                shortfalls.push(format!(
+                    "{workers} workers requested, at most {max_workers} allowed"
+                ));
+            }
+        }
+
+        if let (Some(min), Some(memory)) = (self.min_available_memory, available.memory) {
+            if memory < min {
+                shortfalls.push(format!(
+                    "{memory} bytes of memory available, at least {min} required"
+                ));
+            }
+        }
+
+        if let (Some(min), Some(disk)) = (self.min_available_disk, available.disk) {
+            if disk < min {
+                shortfalls.push(format!(
+                    "{disk} bytes of disk space available, at least {min} required"
+                ));
+            }
+        }
+
+        shortfalls
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn available_disk_bytes(path: &Path) -> Result<u64> {
+    use anyhow::Context;
+
+    let stat = nix::sys::statvfs::statvfs(path)
+        .with_context(|| format!("unable to query file system of {}", path.display()))?;
+
+    #[allow(clippy::unnecessary_cast)]
+    let bytes = stat.blocks_available() as u64 * stat.fragment_size() as u64;
+
+    Ok(bytes)
+}
+
+#[cfg(target_family = "windows")]
+fn available_disk_bytes(path: &Path) -> Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    use winapi::shared::minwindef::FALSE;
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::fileapi::GetDiskFreeSpaceExW;
+    use winapi::um::winnt::ULARGE_INTEGER;
+
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = ULARGE_INTEGER::default();
+
+    let success = unsafe {
+        GetDiskFreeSpaceExW(
+            path.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+
+    if success == FALSE {
+        let code = unsafe { GetLastError() };
+        bail!("error querying free disk space: {:x}", code);
+    }
+
+    Ok(unsafe { *available.QuadPart() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::work::fixture;
+
+    fn work_set(workers: usize) -> WorkSet {
+        WorkSet {
+            work_units: vec![fixture::work_unit(); workers],
+            ..fixture::work_set()
+        }
+    }
+
+    #[test]
+    fn test_shortfalls_unlimited() {
+        let available = Available {
+            memory: Some(0),
+            disk: Some(0),
+        };
+        let shortfalls = ResourceLimits::default().shortfalls(&work_set(8), &available);
+        assert!(shortfalls.is_empty());
+    }
+
+    #[test]
+    fn test_shortfalls_max_workers() {
+        let limits = ResourceLimits {
+            max_workers: Some(2),
+            ..Default::default()
+        };
+
+        assert!(limits
+            .shortfalls(&work_set(2), &Available::default())
+            .is_empty());
+        assert_eq!(
+            limits.shortfalls(&work_set(3), &Available::default()),
+            vec!["3 workers requested, at most 2 allowed"]
+        );
+    }
+
+    #[test]
+    fn test_shortfalls_memory_and_disk() {
+        let limits = ResourceLimits {
+            min_available_memory: Some(1024),
+            min_available_disk: Some(4096),
+            ..Default::default()
+        };
+
+        let enough = Available {
+            memory: Some(1024),
+            disk: Some(4096),
+        };
+        assert!(limits.shortfalls(&work_set(1), &enough).is_empty());
+
+        let short = Available {
+            memory: Some(512),
+            disk: Some(2048),
+        };
+        assert_eq!(
+            limits.shortfalls(&work_set(1), &short),
+            vec![
+                "512 bytes of memory available, at least 1024 required",
+                "2048 bytes of disk space available, at least 4096 required",
+            ]
+        );
+
+        // Unmeasured resources are not held against the work set.
+        assert!(limits
+            .shortfalls(&work_set(1), &Available::default())
+            .is_empty());
+    }
+}
@@ -1,18 +1,33 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+use std::collections::HashMap;
 use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::Result;
+use anyhow::{format_err, Result};
 use onefuzz::process::Output;
+use uuid::Uuid;
 
+use crate::checkpoint::{Checkpoint, CheckpointTask};
 use crate::commands::add_ssh_key;
-use crate::coordinator::{NodeCommand, NodeState};
-use crate::reboot::RebootContext;
+use crate::coordinator::{NodeCommand, NodeState, NodeStateDetail, StateUpdateEvent};
+use crate::preflight::{self, PreflightWarning};
+use crate::reboot::{IReboot, RebootContext};
+use crate::resources::{Available, ResourceLimits};
+use crate::setup::durations::SetupDurations;
 use crate::setup::ISetupRunner;
 use crate::work::*;
 use crate::worker::*;
 
+/// How long to wait for the machine to reboot when a work set requests it.
+pub const REBOOT_TIMEOUT: Duration = Duration::from_secs(60 * 10);
+
+/// How long a worker may sleep in the same kernel wait channel before it is
+/// considered blocked.
+pub const DEFAULT_BLOCKED_THRESHOLD: Duration = Duration::from_secs(60 * 5);
+
 #[derive(Debug)]
 pub enum Scheduler {
     Free(State<Free>),
@@ -23,6 +38,18 @@ pub enum Scheduler {
     Done(State<Done>),
 }
 
+/// Compares schedulers by state only.
+///
+/// Two schedulers are equal when they are in the same state (e.g. both
+/// `Scheduler::Ready`), even if their contexts differ, such as holding distinct
+/// work sets or workers. This is intended for asserting state transitions, not
+/// for comparing scheduled work.
+impl PartialEq for Scheduler {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
 impl From<&Scheduler> for NodeState {
     fn from(value: &Scheduler) -> Self {
         match value {
@@ -54,15 +81,166 @@ impl Scheduler {
     pub fn new(ctx: Option<RebootContext>) -> Self {
         if let Some(ctx) = ctx {
             let work_set = ctx.work_set;
-            let ctx = Ready { work_set };
+            let ctx = Ready {
+                work_set,
+                working_dirs: HashMap::new(),
+            };
             let state = State { ctx };
             state.into()
         } else {
-            let state = State { ctx: Free {} };
+            let ctx = Free {
+                waiting_since: Instant::now(),
+                limits: ResourceLimits::default(),
+            };
+            let state = State { ctx };
             state.into()
         }
     }
 
+    /// Run the given tasks in the working directories they used before the
+    /// agent stopped. Only a node that is ready to run a work set uses them,
+    /// so schedulers in other states are unchanged.
+    pub fn with_working_dirs(self, working_dirs: HashMap<TaskId, PathBuf>) -> Self {
+        match self {
+            Scheduler::Ready(mut state) => {
+                state.ctx.working_dirs = working_dirs;
+                state.into()
+            }
+            other => other,
+        }
+    }
+
+    /// Set the limits checked by `State<Free>::offers()`. Only a free node
+    /// accepts work sets, so schedulers in other states are unchanged.
+    pub fn with_resource_limits(self, limits: ResourceLimits) -> Self {
+        match self {
+            Scheduler::Free(mut state) => {
+                state.ctx.limits = limits;
+                state.into()
+            }
+            other => other,
+        }
+    }
+
+    /// Stop the node because the work set cannot be set up.
+    pub fn setup_error(error: impl Into<String>) -> Self {
+        let cause = DoneCause::SetupError {
+            error: error.into(),
+            script_output: None,
+        };
+        let state = State {
+            ctx: Done { cause },
+        };
+        state.into()
+    }
+
+    pub fn as_node_state_with_detail(&self) -> NodeStateDetail {
+        match self {
+            Self::Free(state) => NodeStateDetail::Free {
+                idle_seconds: state.idle_duration().as_secs(),
+            },
+            Self::SettingUp(state) => NodeStateDetail::SettingUp {
+                tasks: state.ctx.work_set.task_ids(),
+            },
+            Self::PendingReboot(state) => NodeStateDetail::Rebooting {
+                tasks: state.ctx.work_set.task_ids(),
+            },
+            Self::Ready(state) => NodeStateDetail::Ready {
+                tasks: state.ctx.work_set.task_ids(),
+            },
+            Self::Busy(state) => {
+                let workers: Vec<&Worker> = state.ctx.workers.iter().flatten().collect();
+                let completed_workers = workers.iter().filter(|w| w.is_done()).count();
+
+                NodeStateDetail::Busy {
+                    tasks: state.task_ids(),
+                    active_workers: workers.len() - completed_workers,
+                    completed_workers,
+                    elapsed_seconds: state.started_at().elapsed().unwrap_or_default().as_secs(),
+                }
+            }
+            Self::Done(..) => NodeStateDetail::Done,
+        }
+    }
+
+    /// The work set being set up or run, if any.
+    pub fn work_set(&self) -> Option<&WorkSet> {
+        match self {
+            Self::SettingUp(state) => Some(&state.ctx.work_set),
+            Self::PendingReboot(state) => Some(&state.ctx.work_set),
+            Self::Ready(state) => Some(&state.ctx.work_set),
+            Self::Busy(state) => Some(&state.ctx.work_set),
+            Self::Free(..) | Self::Done(..) => None,
+        }
+    }
+
+    /// The id of the work set being set up or run, if any.
+    pub fn work_set_id(&self) -> Option<Uuid> {
+        self.work_set().map(|work_set| work_set.id)
+    }
+
+    /// Capture the observable state of the scheduler, without consuming it.
+    pub fn snapshot(&self) -> SchedulerSnapshot {
+        let (tasks, elapsed_seconds, done_cause) = match self {
+            Self::Free(state) => (vec![], Some(state.idle_duration()), None),
+            Self::SettingUp(state) => (state.ctx.work_set.task_ids(), None, None),
+            Self::PendingReboot(state) => (state.ctx.work_set.task_ids(), None, None),
+            Self::Ready(state) => (state.ctx.work_set.task_ids(), None, None),
+            Self::Busy(state) => (
+                state.task_ids(),
+                Some(state.started_at().elapsed().unwrap_or_default()),
+                None,
+            ),
+            Self::Done(state) => (vec![], None, Some(state.cause())),
+        };
+
+        let (worker_pids, any_worker_blocked) = match self {
+            Self::Busy(state) => (state.worker_pids(), state.is_any_worker_blocked()),
+            _ => (vec![], false),
+        };
+
+        SchedulerSnapshot {
+            state: NodeState::from(self),
+            tasks,
+            elapsed_seconds: elapsed_seconds.map(|elapsed| elapsed.as_secs()),
+            done_cause,
+            worker_pids,
+            any_worker_blocked,
+        }
+    }
+
+    /// Collect pending worker events without advancing the scheduler. Only
+    /// `Busy` schedulers have workers, so other states return no events.
+    pub async fn drain_events(&mut self) -> Vec<WorkerEvent> {
+        match self {
+            Scheduler::Busy(state) => state.drain_events().await,
+            _ => vec![],
+        }
+    }
+
+    /// Stop the node from any state, killing the workers of a busy node first.
+    ///
+    /// Errors killing workers are logged rather than returned, so the node is
+    /// always stopped. A node that is already done keeps its original cause.
+    pub async fn force_done(self, cause: DoneCause) -> State<Done> {
+        match self {
+            Scheduler::Busy(state) => {
+                let reason = match &cause {
+                    DoneCause::CancelledByOperator { reason } => reason.clone(),
+                    _ => "node stopped".to_owned(),
+                };
+
+                // Keep the requested cause, which need not be a cancellation.
+                if let Err(err) = state.cancel_all(&reason).await {
+                    error!("error killing workers: {:?}", err);
+                }
+                Done { cause }.into()
+            }
+            Scheduler::Done(state) => state,
+            _ => Done { cause }.into(),
+        }
+    }
+
     pub async fn execute_command(self, cmd: NodeCommand, managed: bool) -> Result<Self> {
         match cmd {
             NodeCommand::AddSshKey(ssh_key_info) => {
@@ -82,33 +260,154 @@ impl Scheduler {
                 }
             }
             NodeCommand::Stop {} => {
-                let cause = DoneCause::Stopped;
-                let state = State {
-                    ctx: Done { cause },
+                let cause = if let Scheduler::Busy(_) = &self {
+                    DoneCause::CancelledByOperator {
+                        reason: "stop command received".to_owned(),
+                    }
+                } else {
+                    DoneCause::Stopped
                 };
-                Ok(state.into())
+                Ok(self.force_done(cause).await.into())
+            }
+            NodeCommand::AttachDebugger { task_id, command } => {
+                if let Scheduler::Busy(state) = &self {
+                    match state.run_debugger_command(task_id, &command).await {
+                        Ok(output) => info!("debugger output for task {}:\n{}", task_id, output),
+                        Err(err) => error!("unable to debug task {}: {:?}", task_id, err),
+                    }
+                } else {
+                    warn!(
+                        "ignoring debugger command for task {}, node is not busy",
+                        task_id
+                    );
+                }
+                Ok(self)
+            }
+            NodeCommand::SetLogLevel { level } => {
+                if let Err(err) = crate::log_level::set_log_level(&level) {
+                    warn!("ignoring log level command: {:?}", err);
+                }
+                Ok(self)
             }
             NodeCommand::StopIfFree {} => {
                 if let Scheduler::Free(_) = self {
-                    let cause = DoneCause::Stopped;
-                    let state = State {
-                        ctx: Done { cause },
-                    };
-                    Ok(state.into())
+                    Ok(self.force_done(DoneCause::Stopped).await.into())
                 } else {
                     Ok(self)
                 }
             }
+            // Answered by the agent, which can emit the `Pong` event.
+            NodeCommand::Ping { .. } => Ok(self),
+            NodeCommand::RequeueTask { task_id, work_unit } => match self {
+                Scheduler::Busy(state) if state.task_ids().contains(&task_id) => {
+                    let state = state.force_requeue(task_id, work_unit).await?;
+                    Ok(state.into())
+                }
+                _ => {
+                    warn!("ignoring request to requeue unknown task: {}", task_id);
+                    Ok(self)
+                }
+            },
+        }
+    }
+}
+
+/// Point-in-time view of a `Scheduler`, for reporting its status.
+#[derive(Clone, Debug, Serialize)]
+pub struct SchedulerSnapshot {
+    pub state: NodeState,
+    pub tasks: Vec<TaskId>,
+
+    /// Time spent idle when `Free`, or running the work set when `Busy`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_seconds: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub done_cause: Option<DoneCause>,
+
+    /// OS process ID of each worker when `Busy`. See `State<Busy>::worker_pids`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub worker_pids: Vec<Option<u32>>,
+
+    /// Whether a worker seems stuck when `Busy`. See `State<Busy>::is_any_worker_blocked`.
+    pub any_worker_blocked: bool,
+}
+
+/// A change of the node's state, as observed by the agent.
+#[derive(Clone, Debug, Serialize)]
+pub struct StateTransition {
+    pub from: NodeState,
+    pub to: NodeState,
+    pub at: SystemTime,
+}
+
+/// Everything known about how the node came to be done, for telemetry.
+#[derive(Clone, Debug, Serialize)]
+pub struct DoneReport {
+    pub cause: DoneCause,
+    pub machine_id: Uuid,
+
+    /// Tasks of the last work set the node set up or ran, if any.
+    pub tasks: Vec<TaskId>,
+
+    pub timestamp: SystemTime,
+    pub transitions: Vec<StateTransition>,
+}
+
+impl DoneReport {
+    pub fn transitions(self, transitions: Vec<StateTransition>) -> Self {
+        Self {
+            transitions,
+            ..self
+        }
+    }
+
+    /// The `Done` state update to send to the service.
+    pub fn state_update_event(&self) -> StateUpdateEvent {
+        let (error, script_output) = match self.cause.clone() {
+            DoneCause::SetupError {
+                error,
+                script_output,
+            } => (Some(error), script_output),
+            DoneCause::NodeError { message } => (Some(format!("node error: {message}")), None),
+            DoneCause::RebootTimeout => (
+                Some(format!("machine did not reboot within {REBOOT_TIMEOUT:?}")),
+                None,
+            ),
+            DoneCause::OutOfRetries {
+                task_id,
+                attempt_count,
+            } => (
+                Some(format!(
+                    "task {task_id} failed after {attempt_count} attempts"
+                )),
+                None,
+            ),
+            DoneCause::CancelledByOperator { .. } | DoneCause::Stopped | DoneCause::WorkersDone => {
+                (None, None)
+            }
+        };
+
+        StateUpdateEvent::Done {
+            error,
+            script_output,
         }
     }
 }
 
 #[derive(Debug)]
-pub struct Free;
+pub struct Free {
+    waiting_since: Instant,
+    limits: ResourceLimits,
+}
 
 #[derive(Debug)]
 pub struct SettingUp {
     work_set: WorkSet,
+    started_at: Instant,
+
+    /// Median duration of previous setups of the work set, if any.
+    estimated_duration: Option<Duration>,
 }
 
 #[derive(Debug)]
@@ -119,19 +418,64 @@ pub struct PendingReboot {
 #[derive(Debug)]
 pub struct Ready {
     work_set: WorkSet,
+
+    /// Working directories to reuse instead of `WorkUnit::working_dir`, for
+    /// tasks resumed from a checkpoint.
+    working_dirs: HashMap<TaskId, PathBuf>,
 }
 
 #[derive(Debug)]
 pub struct Busy {
     workers: Vec<Option<Worker>>,
+    started_at: SystemTime,
+    work_set: WorkSet,
+
+    /// Number of times each task's worker has been started.
+    attempts: HashMap<TaskId, u32>,
+
+    /// Cause of the first task failure, reported once every worker is done.
+    failure: Option<DoneCause>,
+
+    /// Number of calls to `update`, used to tag the worker events they emit.
+    generation: u64,
+
+    /// Wait channel each sleeping worker was last seen in, by task.
+    wait_channels: HashMap<TaskId, WaitChannel>,
+    blocked_threshold: Duration,
 }
 
 #[derive(Debug)]
+struct WaitChannel {
+    name: String,
+
+    /// When the worker was first seen in the channel.
+    since: Instant,
+
+    /// Whether `newly_blocked_workers` has returned the worker.
+    reported: bool,
+}
+
+impl WaitChannel {
+    fn is_blocked(&self, threshold: Duration) -> bool {
+        self.since.elapsed() >= threshold
+    }
+}
+
+/// A worker event, tagged with the generation of the `State<Busy>::update`
+/// call that emitted it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BusyEvent {
+    pub generation: u64,
+    pub event: WorkerEvent,
+}
+
+#[derive(Clone, Debug)]
 pub struct Done {
     cause: DoneCause,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DoneCause {
     SetupError {
         error: String,
@@ -139,6 +483,21 @@ pub enum DoneCause {
     },
     Stopped,
     WorkersDone,
+    /// The machine did not reboot after the work set requested it.
+    RebootTimeout,
+    CancelledByOperator {
+        reason: String,
+    },
+    /// The agent itself failed, independently of any task. The node should be
+    /// reimaged, rather than having its work rescheduled.
+    NodeError {
+        message: String,
+    },
+    /// Every worker exited abnormally more often than its work unit allows.
+    OutOfRetries {
+        task_id: TaskId,
+        attempt_count: u32,
+    },
 }
 
 pub trait Context {}
@@ -150,7 +509,7 @@ impl Context for Ready {}
 impl Context for Busy {}
 impl Context for Done {}
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct State<C: Context> {
     ctx: C,
 }
@@ -179,9 +538,45 @@ impl<C: Context> From<C> for State<C> {
 }
 
 impl State<Free> {
-    pub fn schedule(self, work_set: WorkSet) -> State<SettingUp> {
-        let ctx = SettingUp { work_set };
-        State { ctx }
+    /// How long the node has been free and waiting for work.
+    pub fn idle_duration(&self) -> Duration {
+        self.ctx.waiting_since.elapsed()
+    }
+
+    /// Whether the node has the resources to run `work_set`, within its
+    /// configured limits. Checked before claiming a work set, so that work the
+    /// node cannot run is left for other nodes instead of failing setup.
+    pub fn offers(&self, work_set: &WorkSet) -> bool {
+        let limits = &self.ctx.limits;
+        let shortfalls = limits.shortfalls(work_set, &Available::measure(limits));
+
+        for shortfall in &shortfalls {
+            warn!(
+                "cannot offer to run work set {}: {}",
+                work_set.id, shortfall
+            );
+        }
+
+        shortfalls.is_empty()
+    }
+
+    pub fn schedule(self, work_set: WorkSet) -> Result<State<SettingUp>> {
+        work_set.validate_paths()?;
+
+        let estimated_duration = match SetupDurations::load() {
+            Ok(durations) => durations.median(&SetupDurations::work_set_id(&work_set)),
+            Err(err) => {
+                warn!("unable to load setup durations: {:?}", err);
+                None
+            }
+        };
+
+        let ctx = SettingUp {
+            work_set,
+            started_at: Instant::now(),
+            estimated_duration,
+        };
+        Ok(State { ctx })
     }
 }
 
@@ -191,27 +586,69 @@ pub enum SetupDone {
     Done(State<Done>),
 }
 
+impl From<SetupDone> for Scheduler {
+    fn from(setup_done: SetupDone) -> Self {
+        match setup_done {
+            SetupDone::Ready(state) => state.into(),
+            SetupDone::PendingReboot(state) => state.into(),
+            SetupDone::Done(state) => state.into(),
+        }
+    }
+}
+
 impl State<SettingUp> {
-    pub async fn finish(self, runner: &dyn ISetupRunner) -> Result<SetupDone> {
+    /// Run setup, returning the next state and the output of the setup script,
+    /// if one ran.
+    #[tracing::instrument(
+        skip(self, machine_id, runner),
+        fields(
+            %machine_id,
+            work_set_id = %self.ctx.work_set.id,
+            task_ids = ?self.ctx.work_set.task_ids(),
+            task_count = self.ctx.work_set.work_units.len(),
+        )
+    )]
+    pub async fn finish(
+        self,
+        machine_id: Uuid,
+        runner: &dyn ISetupRunner,
+    ) -> Result<(SetupDone, Option<Output>)> {
+        let estimated_finish_time = self.estimated_finish_time();
+        let started_at = self.ctx.started_at;
         let work_set = self.ctx.work_set;
 
-        let output = runner.run(&work_set).await;
+        let run = runner.run(&work_set);
+        let output = match estimated_finish_time {
+            Some(estimated_finish_time) => {
+                tokio::pin!(run);
+                tokio::select! {
+                    output = &mut run => output,
+                    _ = tokio::time::sleep_until(estimated_finish_time.into()) => {
+                        warn!("setup is taking longer than it has before, it may be hung");
+                        run.await
+                    }
+                }
+            }
+            None => run.await,
+        };
 
-        match output {
+        let script_output = match output {
             Ok(Some(output)) => {
                 if !output.exit_status.success {
                     let error = "error running target setup script".to_owned();
                     warn!("{}", error);
                     let cause = DoneCause::SetupError {
                         error,
-                        script_output: Some(output),
+                        script_output: Some(output.clone()),
                     };
                     let ctx = Done { cause };
-                    return Ok(SetupDone::Done(ctx.into()));
+                    return Ok((SetupDone::Done(ctx.into()), Some(output)));
                 }
+                Some(output)
             }
             Ok(None) => {
                 // No script was executed.
+                None
             }
             Err(err) => {
                 let error = format!("{err:?}");
@@ -221,50 +658,145 @@ impl State<SettingUp> {
                     script_output: None,
                 };
                 let ctx = Done { cause };
-                return Ok(SetupDone::Done(ctx.into()));
+                return Ok((SetupDone::Done(ctx.into()), None));
             }
+        };
+
+        let verified = work_set
+            .setup_dir()
+            .and_then(|setup_dir| work_set.verify_setup_dir(&setup_dir));
+
+        if let Err(err) = verified {
+            let error = format!("{err:?}");
+            warn!("{}", error);
+            let cause = DoneCause::SetupError {
+                error,
+                script_output: script_output.clone(),
+            };
+            let ctx = Done { cause };
+            return Ok((SetupDone::Done(ctx.into()), script_output));
         }
 
+        record_setup_duration(&work_set, started_at.elapsed());
+
         let done = if work_set.reboot {
             let ctx = PendingReboot { work_set };
             SetupDone::PendingReboot(ctx.into())
         } else {
-            let ctx = Ready { work_set };
+            let ctx = Ready {
+                work_set,
+                working_dirs: HashMap::new(),
+            };
             SetupDone::Ready(ctx.into())
         };
 
-        Ok(done)
+        Ok((done, script_output))
     }
 
     pub fn work_set(&self) -> &WorkSet {
         &self.ctx.work_set
     }
+
+    /// When setup is expected to finish, based on previous setups of the same
+    /// work set. `None` if the work set has not been set up before.
+    pub fn estimated_finish_time(&self) -> Option<Instant> {
+        self.ctx
+            .estimated_duration
+            .map(|duration| self.ctx.started_at + duration)
+    }
+}
+
+fn record_setup_duration(work_set: &WorkSet, duration: Duration) {
+    let recorded = SetupDurations::load().and_then(|mut durations| {
+        durations.record(&SetupDurations::work_set_id(work_set), duration);
+        durations.save()
+    });
+
+    if let Err(err) = recorded {
+        warn!("unable to record setup duration: {:?}", err);
+    }
 }
 
 impl State<PendingReboot> {
     pub fn reboot_context(self) -> RebootContext {
         RebootContext::new(self.ctx.work_set)
     }
+
+    /// Save the reboot context and reboot the machine.
+    ///
+    /// After a successful reboot, the restarted agent resumes from the saved
+    /// context in the `Ready` state, so this only returns if the reboot did not
+    /// happen within `timeout`, in which case the node is stopped.
+    pub async fn wait_for_reboot(
+        self,
+        reboot: &dyn IReboot,
+        timeout: Duration,
+    ) -> Result<Scheduler> {
+        reboot.save_context(self.reboot_context()).await?;
+
+        if let Err(err) = reboot.invoke(timeout).await {
+            error!("machine did not reboot: {:?}", err);
+            let cause = DoneCause::RebootTimeout;
+            let state: State<Done> = Done { cause }.into();
+            return Ok(state.into());
+        }
+
+        let ctx = reboot.load_context().await?;
+        Ok(Scheduler::new(ctx))
+    }
 }
 
 impl State<Ready> {
+    /// Check the work set's configuration for likely problems before its workers
+    /// are started, skipping any warnings suppressed by the task configs.
+    pub fn preflight_check(&self, machine_id: Uuid) -> Vec<PreflightWarning> {
+        match self.ctx.work_set.setup_dir() {
+            Ok(setup_dir) => preflight::check_work_set(&self.ctx.work_set, &setup_dir, machine_id),
+            Err(err) => vec![PreflightWarning {
+                code: preflight::INVALID_CONFIG,
+                message: format!("unable to get setup dir: {err}"),
+            }],
+        }
+    }
+
+    #[tracing::instrument(
+        skip(self, machine_id),
+        fields(
+            %machine_id,
+            work_set_id = %self.ctx.work_set.id,
+            task_ids = ?self.ctx.work_set.task_ids(),
+            task_count = self.ctx.work_set.work_units.len(),
+        )
+    )]
     pub async fn run(self, machine_id: uuid::Uuid) -> Result<State<Busy>> {
         let mut workers = vec![];
         let setup_dir = self.ctx.work_set.setup_dir()?;
         let extra_setup_dir = self.ctx.work_set.extra_setup_dir()?;
 
-        for work in self.ctx.work_set.work_units {
-            let work_dir = work.working_dir(machine_id)?;
+        for work in &self.ctx.work_set.work_units {
+            let work_dir = match self.ctx.working_dirs.get(&work.task_id) {
+                Some(work_dir) => work_dir.clone(),
+                None => work.working_dir(machine_id)?,
+            };
             let worker = Some(Worker::new(
                 work_dir,
                 setup_dir.clone(),
                 extra_setup_dir.clone(),
-                work,
+                work.clone(),
             ));
             workers.push(worker);
         }
 
-        let ctx = Busy { workers };
+        let ctx = Busy {
+            workers,
+            started_at: SystemTime::now(),
+            work_set: self.ctx.work_set,
+            attempts: HashMap::new(),
+            failure: None,
+            generation: 0,
+            wait_channels: HashMap::new(),
+            blocked_threshold: DEFAULT_BLOCKED_THRESHOLD,
+        };
         let state = ctx.into();
 
         Ok(state)
@@ -272,21 +804,103 @@ impl State<Ready> {
 }
 
 impl State<Busy> {
+    #[tracing::instrument(
+        skip(self, machine_id, events, runner),
+        fields(
+            %machine_id,
+            work_set_id = %self.ctx.work_set.id,
+            task_ids = ?self.task_ids(),
+            task_count = self.ctx.workers.len(),
+            generation = self.ctx.generation + 1,
+        )
+    )]
     pub async fn update(
         mut self,
-        events: &mut Vec<WorkerEvent>,
+        machine_id: Uuid,
+        events: &mut Vec<BusyEvent>,
         runner: &mut dyn IWorkerRunner,
     ) -> Result<Updated> {
+        let previous = self.ctx.generation;
+        self.ctx.generation += 1;
+        let generation = self.ctx.generation;
+
+        // Events left in the buffer by the previous update were not handled by
+        // the caller, because that update failed, so they are kept. Anything
+        // older is stale and must not be processed again.
+        events.retain(|e| e.generation == previous);
+
+        self.observe_wait_channels();
+
+        let mut worker_events = vec![];
+
         for worker_slot in &mut self.ctx.workers {
-            let worker = worker_slot.take().unwrap().update(events, runner).await?;
+            let worker = worker_slot.take().unwrap();
+            let worker = worker.update(&mut worker_events, runner).await;
+
+            // Tag events as they are emitted, so a later worker's error does
+            // not lose them.
+            events.extend(
+                worker_events
+                    .drain(..)
+                    .map(|event| BusyEvent { generation, event }),
+            );
+            let worker = worker?;
+
+            // Workers that were stopped or cancelled are not retried. A worker
+            // that failed is restarted or removed below, so it is only seen
+            // here once, even if it exited during `drain_events`.
+            if !worker.exited_abnormally() {
+                worker_slot.replace(worker);
+                continue;
+            }
+
+            let task_id = worker.task_id();
+            let attempt_count = self.ctx.attempts.entry(task_id).or_insert(1);
 
-            worker_slot.replace(worker);
+            match self.ctx.work_set.work_unit_by_task_id(task_id) {
+                Some(work) if *attempt_count <= work.max_retries => {
+                    *attempt_count += 1;
+                    warn!(
+                        "worker exited abnormally, restarting task {} (attempt {} of {})",
+                        task_id,
+                        attempt_count,
+                        work.max_retries + 1
+                    );
+
+                    let worker = Worker::new(
+                        worker.working_dir().to_owned(),
+                        self.ctx.work_set.setup_dir()?,
+                        self.ctx.work_set.extra_setup_dir()?,
+                        work.clone(),
+                    );
+                    worker_slot.replace(worker);
+                }
+                _ => {
+                    // Leave the slot empty, so it is removed below.
+                    error!(
+                        "worker exited abnormally, task {} is out of retries after {} attempts",
+                        task_id, attempt_count
+                    );
+                    self.ctx.failure.get_or_insert(DoneCause::OutOfRetries {
+                        task_id,
+                        attempt_count: *attempt_count,
+                    });
+                }
+            }
         }
 
+        self.ctx.workers.retain(Option::is_some);
+
         let updated = if self.all_workers_done() {
-            let done = Done {
-                cause: DoneCause::WorkersDone,
-            };
+            for worker in self.ctx.workers.iter().flatten() {
+                info!(
+                    "worker done, working dir: {}",
+                    worker.working_dir().display()
+                );
+            }
+
+            let cause = self.ctx.failure.take().unwrap_or(DoneCause::WorkersDone);
+            let done = Done { cause };
             Updated::Done(done.into())
         } else {
             Updated::Busy(self)
@@ -295,6 +909,81 @@ impl State<Busy> {
         Ok(updated)
     }
 
+    /// Collect the events of workers whose child processes have exited,
+    /// without starting, retrying or stopping any worker, and without
+    /// transitioning to `Done`.
+    ///
+    /// A worker that cannot be checked is logged and dropped.
+    pub async fn drain_events(&mut self) -> Vec<WorkerEvent> {
+        let mut events = vec![];
+
+        for worker_slot in &mut self.ctx.workers {
+            let worker = worker_slot.take().unwrap();
+            let task_id = worker.task_id();
+
+            match worker.poll(&mut events).await {
+                Ok(worker) => {
+                    worker_slot.replace(worker);
+                }
+                Err(err) => error!("unable to check worker for task {}: {:?}", task_id, err),
+            }
+        }
+
+        self.ctx.workers.retain(Option::is_some);
+
+        events
+    }
+
+    /// Time at which the node started running the work set.
+    pub fn started_at(&self) -> SystemTime {
+        self.ctx.started_at
+    }
+
+    /// Service-provided estimate of how long the work set will run, if any.
+    pub fn estimated_duration(&self) -> Option<Duration> {
+        self.ctx.work_set.estimated_duration
+    }
+
+    /// Save a checkpoint of the running workers to `path`, so that they can be
+    /// resumed if the agent crashes. Each worker's elapsed time is measured
+    /// from the start of the work set.
+    pub fn checkpoint(&self, path: &Path) -> Result<()> {
+        let elapsed = self.ctx.started_at.elapsed().unwrap_or_default();
+        let tasks = self
+            .ctx
+            .workers
+            .iter()
+            .flatten()
+            .filter(|worker| matches!(worker, Worker::Running(..)))
+            .map(|worker| CheckpointTask {
+                task_id: worker.task_id(),
+                working_dir: worker.working_dir().to_owned(),
+                elapsed,
+            })
+            .collect();
+
+        Checkpoint { tasks }.save(path)
+    }
+
+    /// OS process ID of each worker, or `None` for workers that have not been
+    /// spawned or have exited.
+    pub fn worker_pids(&self) -> Vec<Option<u32>> {
+        self.ctx
+            .workers
+            .iter()
+            .map(|worker| worker.as_ref().and_then(Worker::pid))
+            .collect()
+    }
+
+    pub fn task_ids(&self) -> Vec<TaskId> {
+        self.ctx
+            .workers
+            .iter()
+            .flatten()
+            .map(|worker| worker.task_id())
+            .collect()
+    }
+
     fn all_workers_done(&self) -> bool {
         self.ctx
             .workers
@@ -303,25 +992,223 @@ impl State<Busy> {
     }
 
     pub async fn stop(mut self, task_id: TaskId) -> Result<Self> {
+        if self.ctx.work_set.work_unit_by_task_id(task_id).is_none() {
+            warn!("ignoring request to stop unknown task: {}", task_id);
+            return Ok(self);
+        }
+
         self.ctx.workers =
             futures::future::try_join_all(self.ctx.workers.iter_mut().map(|worker| async move {
                 match worker.take() {
-                    Some(worker) => {
-                        let mut new_worker = None;
-                        if let Worker::Running(state) = worker {
-                            if state.work().task_id == task_id {
-                                new_worker = Some(Worker::Done(state.stop().kill().await?));
-                            }
-                        }
-                        Ok::<std::option::Option<Worker>, anyhow::Error>(new_worker)
+                    Some(Worker::Running(state)) if state.work().task_id == task_id => {
+                        let state = state.stop().kill().await?;
+                        Ok::<Option<Worker>, anyhow::Error>(Some(state.into()))
                     }
-                    None => Ok(None),
+                    // Leave the workers of other tasks running.
+                    other => Ok(other),
                 }
             }))
             .await?;
 
         Ok(self)
     }
+
+    /// Replace the work unit of a task's worker without restarting the node. The
+    /// worker's child process is stopped if it is running, and a new worker for
+    /// `new_work` is put in the same slot, to be started by the next `update`.
+    ///
+    /// The new worker runs in the old worker's working directory, and starts with
+    /// a fresh retry count.
+    pub async fn force_requeue(mut self, task_id: TaskId, new_work: WorkUnit) -> Result<Self> {
+        if new_work.task_id != task_id {
+            bail!(
+                "unable to requeue task {} with the work unit of task {}",
+                task_id,
+                new_work.task_id
+            );
+        }
+
+        let setup_dir = self.ctx.work_set.setup_dir()?;
+        let extra_setup_dir = self.ctx.work_set.extra_setup_dir()?;
+
+        let worker_slot = self
+            .ctx
+            .workers
+            .iter_mut()
+            .find(|worker| matches!(worker, Some(worker) if worker.task_id() == task_id))
+            .ok_or_else(|| format_err!("unable to requeue unknown task: {}", task_id))?;
+
+        let worker = worker_slot.take().unwrap();
+        let work_dir = worker.working_dir().to_owned();
+
+        match worker {
+            Worker::Running(state) => {
+                state.stop().kill().await?;
+            }
+            Worker::Stopping(state) => {
+                state.kill().await?;
+            }
+            Worker::Ready(..) | Worker::Done(..) => {}
+        }
+
+        info!(
+            "requeued task {}, working dir: {}",
+            task_id,
+            work_dir.display()
+        );
+
+        worker_slot.replace(Worker::new(
+            work_dir,
+            setup_dir,
+            extra_setup_dir,
+            new_work.clone(),
+        ));
+
+        if let Some(work) = self.ctx.work_set.work_unit_by_task_id_mut(task_id) {
+            *work = new_work;
+        }
+        self.ctx.attempts.remove(&task_id);
+
+        Ok(self)
+    }
+
+    /// Set how long a worker may stay in the same wait channel before
+    /// `is_any_worker_blocked` reports it.
+    #[cfg(test)]
+    pub fn blocked_threshold(mut self, threshold: Duration) -> Self {
+        self.ctx.blocked_threshold = threshold;
+        self
+    }
+
+    /// Whether any running worker has slept in the same kernel wait channel
+    /// for longer than the blocked threshold, which often means its harness is
+    /// stuck on I/O. Wait channels are sampled on each `update`, and are only
+    /// available on Linux.
+    pub fn is_any_worker_blocked(&self) -> bool {
+        let threshold = self.ctx.blocked_threshold;
+        self.ctx
+            .wait_channels
+            .values()
+            .any(|wchan| wchan.is_blocked(threshold))
+    }
+
+    /// Workers that became blocked since the last call, with their wait
+    /// channel and how long they have been in it. A worker is returned again
+    /// only if it leaves the channel and then gets blocked again.
+    pub fn newly_blocked_workers(&mut self) -> Vec<(TaskId, String, Duration)> {
+        let threshold = self.ctx.blocked_threshold;
+        let mut blocked = vec![];
+
+        for (task_id, wchan) in &mut self.ctx.wait_channels {
+            if !wchan.reported && wchan.is_blocked(threshold) {
+                wchan.reported = true;
+                blocked.push((*task_id, wchan.name.clone(), wchan.since.elapsed()));
+            }
+        }
+
+        blocked
+    }
+
+    fn observe_wait_channels(&mut self) {
+        let mut wait_channels = HashMap::new();
+
+        for worker in self.ctx.workers.iter().flatten() {
+            if worker.pid().is_none() {
+                continue;
+            }
+
+            let task_id = worker.task_id();
+            let name = match worker.wait_channel() {
+                Ok(Some(name)) => name,
+                Ok(None) => continue,
+                Err(err) => {
+                    debug!("unable to read wait channel of task {}: {:?}", task_id, err);
+                    continue;
+                }
+            };
+
+            // Keep the previous sample if the worker hasn't left the channel.
+            let wchan = match self.ctx.wait_channels.remove(&task_id) {
+                Some(previous) if previous.name == name => previous,
+                _ => WaitChannel {
+                    name,
+                    since: Instant::now(),
+                    reported: false,
+                },
+            };
+            wait_channels.insert(task_id, wchan);
+        }
+
+        self.ctx.wait_channels = wait_channels;
+    }
+
+    /// Resource usage of each running worker, by task. Workers whose usage
+    /// can't be read are skipped.
+    pub fn resource_usage(&self) -> Vec<(TaskId, ResourceUsage)> {
+        self.ctx
+            .workers
+            .iter()
+            .flatten()
+            .filter(|worker| worker.pid().is_some())
+            .filter_map(|worker| match worker.resource_usage() {
+                Ok(usage) => Some((worker.task_id(), usage)),
+                Err(err) => {
+                    debug!(
+                        "unable to read resource usage of task {}: {:?}",
+                        worker.task_id(),
+                        err
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Run a command in a debugger attached to the running worker of a task,
+    /// then detach, returning the debugger's output.
+    pub async fn run_debugger_command(&self, task_id: TaskId, command: &str) -> Result<String> {
+        let worker = self
+            .ctx
+            .workers
+            .iter()
+            .flatten()
+            .find(|worker| worker.task_id() == task_id && worker.pid().is_some())
+            .ok_or_else(|| format_err!("no running worker for task: {}", task_id))?;
+
+        let mut session = worker.attach_debugger()?;
+        session.send_command(command)?;
+        session.detach().await
+    }
+
+    /// Immediately kill all workers, wait for them to exit, and stop the node.
+    ///
+    /// A worker that fails to be killed does not stop the others from being
+    /// killed. Each failure is logged, and an error is returned once every
+    /// worker has been handled.
+    pub async fn cancel_all(self, reason: &str) -> Result<State<Done>> {
+        let workers: Vec<_> = self.ctx.workers.into_iter().flatten().collect();
+        let total = workers.len();
+
+        let results = futures::future::join_all(workers.into_iter().map(|worker| async move {
+            let task_id = worker.task_id();
+            worker
+                .cancel()
+                .await
+                .map_err(|err| error!("unable to kill worker for task {}: {:?}", task_id, err))
+        }))
+        .await;
+
+        let failed = results.iter().filter(|result| result.is_err()).count();
+        if failed > 0 {
+            bail!("unable to kill {} of {} workers", failed, total);
+        }
+
+        let cause = DoneCause::CancelledByOperator {
+            reason: reason.to_owned(),
+        };
+        let done = Done { cause };
+        Ok(done.into())
+    }
 }
 
 pub enum Updated {
@@ -342,4 +1229,16 @@ impl State<Done> {
     pub fn cause(&self) -> DoneCause {
         self.ctx.cause.clone()
     }
+
+    /// Package the done state with the given context. The report has no
+    /// transitions, see `DoneReport::transitions`.
+    pub fn into_report(self, machine_id: Uuid, work_set: Option<&WorkSet>) -> DoneReport {
+        DoneReport {
+            cause: self.ctx.cause,
+            machine_id,
+            tasks: work_set.map(WorkSet::task_ids).unwrap_or_default(),
+            timestamp: SystemTime::now(),
+            transitions: vec![],
+        }
+    }
 }
@@ -1,30 +1,26 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use std::path::{Path, PathBuf};
-use std::process::Stdio;
-use std::time::Duration;
+use std::path::Path;
 
 use anyhow::{Context, Result};
 use downcast_rs::Downcast;
 use onefuzz::az_copy;
 use onefuzz::process::Output;
+use onefuzz::setup_script::SetupScript;
 use tokio::fs;
-use tokio::process::Command;
 use uuid::Uuid;
 
 use crate::work::*;
 
-// Default to 59 minutes, just under the service's `NODE_EXPIRATION_TIME` of 1 hour.
-const DEFAULT_SETUP_SCRIPT_TIMEOUT: Duration = Duration::from_secs(59 * 60);
-
-const SETUP_PATH_ENV: &str = "ONEFUZZ_TARGET_SETUP_PATH";
-
 pub type SetupOutput = Option<Output>;
 
 #[async_trait]
 pub trait ISetupRunner: Downcast {
     async fn run(&self, work_set: &WorkSet) -> Result<SetupOutput>;
+
+    /// Describe what `run` would do for the work set, without doing it.
+    fn dry_run(&self, work_set: &WorkSet) -> Result<String>;
 }
 
 impl_downcast!(ISetupRunner);
@@ -34,6 +30,10 @@ impl ISetupRunner for SetupRunner {
     async fn run(&self, work_set: &WorkSet) -> Result<SetupOutput> {
         self.run(work_set).await
     }
+
+    fn dry_run(&self, work_set: &WorkSet) -> Result<String> {
+        self.dry_run(work_set)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -101,6 +101,53 @@ impl SetupRunner {
         Self::run_setup_script(setup_dir).await
     }
 
+    /// Describe each step of `run` for the work set, one per line, with all
+    /// paths resolved. Container URLs are printed without their SAS tokens.
+    pub fn dry_run(&self, work_set: &WorkSet) -> Result<String> {
+        let mut steps = vec![];
+
+        if let (Some(extra_setup_container), Some(extra_setup_dir)) =
+            (&work_set.extra_setup_url, work_set.extra_setup_dir()?)
+        {
+            steps.push(format!(
+                "sync extra setup container {} to {}",
+                extra_setup_container,
+                extra_setup_dir.display()
+            ));
+        }
+
+        steps.push(format!(
+            "save work set context to {}",
+            WorkSet::context_path(self.machine_id)?.display()
+        ));
+
+        let setup_dir = work_set.setup_dir()?;
+        steps.push(format!(
+            "sync setup container {} to {}",
+            work_set.setup_url,
+            setup_dir.display()
+        ));
+        steps.push(format!("make files in {} executable", setup_dir.display()));
+
+        for work_unit in &work_set.work_units {
+            let working_dir = work_unit.working_dir(self.machine_id)?;
+            steps.push(format!(
+                "symlink {} to {}",
+                working_dir.join("setup").display(),
+                setup_dir.display()
+            ));
+        }
+
+        // The script comes from the setup container, so it may not be synced yet.
+        let setup_script = SetupScript::in_dir(setup_dir);
+        steps.push(format!(
+            "run setup script, if the container has one: {}",
+            setup_script.command_line()
+        ));
+
+        Ok(steps.join("\n"))
+    }
+
     pub async fn run_setup_script(
         setup_dir: impl AsRef<Path>,
     ) -> std::result::Result<Option<Output>, anyhow::Error> {
@@ -211,76 +258,7 @@ async fn create_setup_symlink(setup_dir: &Path, working_dir: impl AsRef<Path>) -
     Ok(())
 }
 
-#[cfg(target_family = "windows")]
-const SETUP_SCRIPT: &str = "setup.ps1";
-
-#[cfg(target_family = "unix")]
-const SETUP_SCRIPT: &str = "setup.sh";
-
-pub struct SetupScript {
-    setup_dir: PathBuf,
-    script_path: PathBuf,
-}
-
-impl SetupScript {
-    pub async fn new(setup_dir: impl AsRef<Path>) -> Result<Option<Self>> {
-        let setup_dir = setup_dir.as_ref().to_path_buf();
-        let script_path = setup_dir.join(SETUP_SCRIPT);
-
-        let script = if onefuzz::fs::exists(&script_path).await? {
-            Some(Self {
-                setup_dir,
-                script_path,
-            })
-        } else {
-            None
-        };
-
-        Ok(script)
-    }
-
-    pub fn path(&self) -> &Path {
-        &self.script_path
-    }
-
-    pub async fn invoke(&self, timeout: impl Into<Option<Duration>>) -> Result<Output> {
-        let timeout = timeout.into().unwrap_or(DEFAULT_SETUP_SCRIPT_TIMEOUT);
-
-        let timed = tokio::time::timeout(timeout, self.setup_command().output())
-            .await
-            .context("setup script timed out")?;
-        let output = timed?.into();
-
-        Ok(output)
-    }
-
-    #[cfg(target_family = "windows")]
-    fn setup_command(&self) -> Command {
-        let mut cmd = Command::new("powershell.exe");
-
-        cmd.env(SETUP_PATH_ENV, &self.setup_dir);
-        cmd.arg("-ExecutionPolicy");
-        cmd.arg("Unrestricted");
-        cmd.arg("-File");
-        cmd.arg(&self.script_path);
-        cmd.stderr(Stdio::piped());
-        cmd.stdout(Stdio::piped());
-
-        cmd
-    }
-
-    #[cfg(target_family = "unix")]
-    fn setup_command(&self) -> Command {
-        let mut cmd = Command::new("bash");
-
-        cmd.env(SETUP_PATH_ENV, &self.setup_dir);
-        cmd.arg(&self.script_path);
-        cmd.stderr(Stdio::piped());
-        cmd.stdout(Stdio::piped());
-
-        cmd
-    }
-}
+pub mod durations;
 
 #[cfg(test)]
 pub mod double;
@@ -24,4 +24,8 @@ impl ISetupRunner for SetupRunnerDouble {
         }
         Ok(self.script.clone())
     }
+
+    fn dry_run(&self, work_set: &WorkSet) -> Result<String> {
+        Ok(format!("set up {} work units", work_set.work_units.len()))
+    }
 }
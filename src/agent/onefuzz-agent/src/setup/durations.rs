@@ -0,0 +1,127 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::work::WorkSet;
+
+/// Number of recent setup durations kept for each work set.
+const MAX_SAMPLES: usize = 10;
+
+/// Local cache of how long setup took for previously seen work sets.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SetupDurations {
+    /// Recent setup durations in whole seconds, oldest first, by work set id.
+    durations: HashMap<String, Vec<u64>>,
+}
+
+impl SetupDurations {
+    pub fn path() -> Result<PathBuf> {
+        Ok(onefuzz::fs::onefuzz_root()?.join("setup_durations.json"))
+    }
+
+    /// Load the cache, which is empty if it has not been saved yet.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("unable to read setup durations: {}", path.display()))
+            }
+        };
+
+        serde_json::from_slice(&data)
+            .with_context(|| format!("invalid setup durations: {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        let data = serde_json::to_vec(&self)?;
+
+        std::fs::write(&path, data)
+            .with_context(|| format!("unable to save setup durations: {}", path.display()))
+    }
+
+    /// Setup of a work set depends only on its setup containers, so work sets
+    /// are identified by those.
+    pub fn work_set_id(work_set: &WorkSet) -> String {
+        match &work_set.extra_setup_url {
+            Some(extra_setup_url) => format!("{} {}", work_set.setup_url, extra_setup_url),
+            None => work_set.setup_url.to_string(),
+        }
+    }
+
+    pub fn record(&mut self, work_set_id: &str, duration: Duration) {
+        let samples = self.durations.entry(work_set_id.to_owned()).or_default();
+        samples.push(duration.as_secs());
+
+        if samples.len() > MAX_SAMPLES {
+            samples.drain(..samples.len() - MAX_SAMPLES);
+        }
+    }
+
+    /// Median of the recent setup durations of a work set, if it has been set
+    /// up before.
+    pub fn median(&self, work_set_id: &str) -> Option<Duration> {
+        let mut samples = self.durations.get(work_set_id)?.clone();
+        if samples.is_empty() {
+            return None;
+        }
+
+        samples.sort_unstable();
+        let mid = samples.len() / 2;
+        let median = if samples.len() % 2 == 0 {
+            (samples[mid - 1] + samples[mid]) / 2
+        } else {
+            samples[mid]
+        };
+
+        Some(Duration::from_secs(median))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setup_durations_median() {
+        let mut durations = SetupDurations::default();
+        assert_eq!(durations.median("a"), None);
+
+        durations.record("a", Duration::from_secs(30));
+        assert_eq!(durations.median("a"), Some(Duration::from_secs(30)));
+
+        durations.record("a", Duration::from_secs(10));
+        assert_eq!(durations.median("a"), Some(Duration::from_secs(20)));
+
+        durations.record("a", Duration::from_secs(600));
+        assert_eq!(durations.median("a"), Some(Duration::from_secs(30)));
+
+        assert_eq!(durations.median("b"), None);
+    }
+
+    #[test]
+    fn test_setup_durations_max_samples() {
+        let mut durations = SetupDurations::default();
+
+        for _ in 0..MAX_SAMPLES {
+            durations.record("a", Duration::from_secs(1000));
+        }
+        for _ in 0..MAX_SAMPLES {
+            durations.record("a", Duration::from_secs(5));
+        }
+
+        // Only the most recent samples are kept.
+        assert_eq!(durations.durations["a"].len(), MAX_SAMPLES);
+        assert_eq!(durations.median("a"), Some(Duration::from_secs(5)));
+    }
+}
@@ -1,12 +1,14 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use std::{io::ErrorKind, sync::Arc};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use downcast_rs::Downcast;
-use onefuzz::{auth::Secret, blob::BlobContainerUrl, http::is_auth_error};
+use onefuzz::{auth::Secret, blob::BlobContainerUrl, http::is_auth_error, syncdir::SyncedDir};
 use storage_queue::{Message as QueueMessage, QueueClient};
 use tokio::fs;
 use tokio::sync::RwLock;
@@ -20,18 +22,125 @@ pub type TaskId = Uuid;
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct WorkSet {
+    /// Identifies the work set in logs. Work sets from services that don't
+    /// send an id are given a random one.
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
+
     pub reboot: bool,
     pub setup_url: BlobContainerUrl,
     pub extra_setup_url: Option<BlobContainerUrl>,
     pub script: bool,
     pub work_units: Vec<WorkUnit>,
+
+    /// Expected running time of the work set, estimated by the service from
+    /// historical data. Serialized as whole seconds.
+    #[serde(default, with = "optional_duration_secs")]
+    pub estimated_duration: Option<Duration>,
 }
 
 impl WorkSet {
+    /// Build a work set with a single work unit from the environment variables
+    /// set on nodes by the service.
+    ///
+    /// `ONEFUZZ_JOB_ID`, `ONEFUZZ_TASK_ID`, `ONEFUZZ_SETUP_URL` and
+    /// `ONEFUZZ_TASK_CONFIG` (a path to the JSON task config) are required.
+    /// `ONEFUZZ_EXTRA_SETUP_URL` is optional, and `ONEFUZZ_REBOOT` and
+    /// `ONEFUZZ_SCRIPT` are set to request a reboot or setup script. The setup
+    /// directory is derived from the setup URL, as for service work sets.
+    pub fn from_env() -> Result<Self> {
+        fn var(name: &str) -> Result<String> {
+            std::env::var(name).with_context(|| format!("unable to read {name}"))
+        }
+
+        let job_id = Uuid::parse_str(&var("ONEFUZZ_JOB_ID")?).context("invalid ONEFUZZ_JOB_ID")?;
+        let task_id =
+            Uuid::parse_str(&var("ONEFUZZ_TASK_ID")?).context("invalid ONEFUZZ_TASK_ID")?;
+        let setup_url = BlobContainerUrl::parse(var("ONEFUZZ_SETUP_URL")?)
+            .context("invalid ONEFUZZ_SETUP_URL")?;
+        let extra_setup_url = std::env::var("ONEFUZZ_EXTRA_SETUP_URL")
+            .ok()
+            .map(BlobContainerUrl::parse)
+            .transpose()
+            .context("invalid ONEFUZZ_EXTRA_SETUP_URL")?;
+        let reboot = std::env::var("ONEFUZZ_REBOOT").is_ok();
+        let script = std::env::var("ONEFUZZ_SCRIPT").is_ok();
+
+        let config_path = var("ONEFUZZ_TASK_CONFIG")?;
+        let config = std::fs::read_to_string(&config_path)
+            .with_context(|| format!("unable to read task config: {config_path}"))?;
+
+        let work_unit = WorkUnit {
+            job_id,
+            task_id,
+            config: config.into(),
+            tags: HashMap::new(),
+            expected_files: vec![],
+            max_retries: 0,
+            output_dir: None,
+            stdin_file: None,
+            max_corpus_size: None,
+        };
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            reboot,
+            setup_url,
+            extra_setup_url,
+            script,
+            work_units: vec![work_unit],
+            estimated_duration: None,
+        })
+    }
+
+    /// The environment variables read by [`WorkSet::from_env`], describing the
+    /// work set's first work unit.
+    ///
+    /// `ONEFUZZ_TASK_CONFIG` is not set, since it is the path to a task config
+    /// file rather than the config itself. `ONEFUZZ_REBOOT` and `ONEFUZZ_SCRIPT`
+    /// are only set if the work set requests a reboot or setup script.
+    #[cfg(test)]
+    pub fn to_env_vars(&self) -> HashMap<String, String> {
+        let mut vars = HashMap::new();
+
+        if let Some(work_unit) = self.work_units.first() {
+            vars.insert("ONEFUZZ_JOB_ID".to_owned(), work_unit.job_id.to_string());
+            vars.insert("ONEFUZZ_TASK_ID".to_owned(), work_unit.task_id.to_string());
+        }
+
+        // only relative paths can't be converted to a URL, and parsing a URL never
+        // produces one
+        if let Ok(url) = self.setup_url.url() {
+            vars.insert("ONEFUZZ_SETUP_URL".to_owned(), url.to_string());
+        }
+
+        if let Some(Ok(url)) = self.extra_setup_url.as_ref().map(BlobContainerUrl::url) {
+            vars.insert("ONEFUZZ_EXTRA_SETUP_URL".to_owned(), url.to_string());
+        }
+
+        if self.reboot {
+            vars.insert("ONEFUZZ_REBOOT".to_owned(), "1".to_owned());
+        }
+
+        if self.script {
+            vars.insert("ONEFUZZ_SCRIPT".to_owned(), "1".to_owned());
+        }
+
+        vars
+    }
+
     pub fn task_ids(&self) -> Vec<TaskId> {
         self.work_units.iter().map(|w| w.task_id).collect()
     }
 
+    pub fn work_unit_by_task_id(&self, task_id: TaskId) -> Option<&WorkUnit> {
+        self.work_units.iter().find(|w| w.task_id == task_id)
+    }
+
+    pub fn work_unit_by_task_id_mut(&mut self, task_id: TaskId) -> Option<&mut WorkUnit> {
+        self.work_units.iter_mut().find(|w| w.task_id == task_id)
+    }
+
     pub fn context_path(machine_id: Uuid) -> Result<PathBuf> {
         Ok(onefuzz::fs::onefuzz_root()?.join(format!("workset_context-{machine_id}.json")))
     }
@@ -100,6 +209,62 @@ impl WorkSet {
             .map(|url| url.as_path(root))
             .transpose()
     }
+
+    /// Check that the setup directories can be used, before any setup is done.
+    ///
+    /// The directories are created during setup, so they need not exist yet,
+    /// but an existing path or its nearest existing ancestor must be a
+    /// directory we can read.
+    pub fn validate_paths(&self) -> Result<()> {
+        validate_dir(&self.setup_dir()?).context("invalid setup directory")?;
+
+        if let Some(extra_setup_dir) = self.extra_setup_dir()? {
+            validate_dir(&extra_setup_dir).context("invalid extra setup directory")?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that the setup directory contains the files expected by each work
+    /// unit, failing with the list of any missing files.
+    pub fn verify_setup_dir(&self, setup_dir: &Path) -> Result<()> {
+        let missing: BTreeSet<&Path> = self
+            .work_units
+            .iter()
+            .flat_map(|unit| &unit.expected_files)
+            .map(PathBuf::as_path)
+            .filter(|path| !setup_dir.join(path).exists())
+            .collect();
+
+        if !missing.is_empty() {
+            let missing: Vec<String> = missing
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            bail!(
+                "setup directory {} is missing expected files: {}",
+                setup_dir.display(),
+                missing.join(", ")
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_dir(dir: &Path) -> Result<()> {
+    for path in dir.ancestors() {
+        match std::fs::metadata(path) {
+            Ok(metadata) if metadata.is_dir() => return Ok(()),
+            Ok(_) => bail!("{} is not a directory", path.display()),
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("unable to access {}", path.display()))
+            }
+        }
+    }
+
+    bail!("no part of {} exists", dir.display())
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -112,6 +277,32 @@ pub struct WorkUnit {
 
     /// JSON-serialized task config.
     pub config: Secret<String>,
+
+    /// Arbitrary metadata attached by the service, included in worker events.
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+
+    /// Files, relative to the setup directory, that must exist after setup.
+    #[serde(default)]
+    pub expected_files: Vec<PathBuf>,
+
+    /// Number of times to restart the worker after it exits abnormally.
+    #[serde(default)]
+    pub max_retries: u32,
+
+    /// Directory to place worker output in, instead of the working directory.
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
+
+    /// File to redirect to the worker's stdin, for targets that read their
+    /// input from stdin instead of an `{input}` argument.
+    #[serde(default)]
+    pub stdin_file: Option<PathBuf>,
+
+    /// Max number of entries to keep in the worker's corpus directories. When
+    /// exceeded, the oldest files are deleted.
+    #[serde(default)]
+    pub max_corpus_size: Option<usize>,
 }
 
 impl WorkUnit {
@@ -121,9 +312,100 @@ impl WorkUnit {
             .join(self.task_id.to_string()))
     }
 
+    /// Directory the worker writes its output to: `output_dir` if set, else the
+    /// working directory.
+    pub fn output_dir(&self, machine_id: Uuid) -> Result<PathBuf> {
+        match &self.output_dir {
+            Some(output_dir) => Ok(output_dir.clone()),
+            None => self.working_dir(machine_id),
+        }
+    }
+
     pub fn config_path(&self, machine_id: Uuid) -> Result<PathBuf> {
         Ok(self.working_dir(machine_id)?.join("config.json"))
     }
+
+    /// Local directories of the `inputs` corpus in the task config, relative
+    /// to `output_dir`. Corpora synced from a local container are left out,
+    /// since they are not owned by the worker.
+    pub fn corpus_dirs(&self, output_dir: &Path) -> Result<Vec<PathBuf>> {
+        #[derive(Deserialize)]
+        struct CorpusConfig {
+            inputs: Option<SyncedDirs>,
+        }
+
+        let config: CorpusConfig = serde_json::from_str(self.config.expose_ref())
+            .with_context(|| format!("unable to parse config of task {}", self.task_id))?;
+
+        let dirs = config
+            .inputs
+            .iter()
+            .flat_map(SyncedDirs::iter)
+            .filter(|dir| {
+                dir.remote_path
+                    .as_ref()
+                    .and_then(|url| url.as_file_path())
+                    .is_none()
+            })
+            .map(|dir| output_dir.join(&dir.local_path))
+            .collect();
+
+        Ok(dirs)
+    }
+}
+
+/// A task config field that holds one synced directory, or a list of them.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum SyncedDirs {
+    Single(SyncedDir),
+    Multiple(Vec<SyncedDir>),
+}
+
+impl SyncedDirs {
+    pub fn iter(&self) -> impl Iterator<Item = &SyncedDir> {
+        match self {
+            Self::Single(dir) => std::slice::from_ref(dir).iter(),
+            Self::Multiple(dirs) => dirs.iter(),
+        }
+    }
+}
+
+/// (De)serialize a `Duration` as a number of whole seconds.
+pub mod duration_secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(value.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let secs = u64::deserialize(deserializer)?;
+        Ok(Duration::from_secs(secs))
+    }
+}
+
+/// (De)serialize an `Option<Duration>` as an optional number of whole seconds.
+pub mod optional_duration_secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.map(|d| d.as_secs()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        let secs = Option::<u64>::deserialize(deserializer)?;
+        Ok(secs.map(Duration::from_secs))
+    }
 }
 
 #[async_trait]
@@ -244,3 +526,9 @@ impl WorkQueue {
 
 #[cfg(test)]
 pub mod double;
+
+#[cfg(test)]
+pub mod fixture;
+
+#[cfg(test)]
+mod tests;
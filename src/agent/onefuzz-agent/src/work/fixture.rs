@@ -0,0 +1,34 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::*;
+
+pub fn work_set_id() -> Uuid {
+    Uuid::from_u128(1)
+}
+
+pub fn work_unit() -> WorkUnit {
+    WorkUnit {
+        job_id: "83267e88-efdd-4b1d-92c0-6b80d01887f8".parse().unwrap(),
+        task_id: "eb8ee6b8-6f2d-43b1-aec2-022e9813e86b".parse().unwrap(),
+        config: r#"{ "hello": "world" }"#.to_owned().into(),
+        tags: Default::default(),
+        expected_files: vec![],
+        max_retries: 0,
+        output_dir: None,
+        stdin_file: None,
+        max_corpus_size: None,
+    }
+}
+
+pub fn work_set() -> WorkSet {
+    WorkSet {
+        id: work_set_id(),
+        reboot: false,
+        setup_url: BlobContainerUrl::parse("https://contoso.com/my-setup-container").unwrap(),
+        extra_setup_url: None,
+        script: false,
+        work_units: vec![work_unit()],
+        estimated_duration: None,
+    }
+}
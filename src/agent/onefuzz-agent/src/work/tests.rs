@@ -0,0 +1,50 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::fixture::work_set;
+use super::*;
+
+#[test]
+fn test_to_env_vars() {
+    let vars = work_set().to_env_vars();
+
+    assert_eq!(
+        vars,
+        HashMap::from([
+            (
+                "ONEFUZZ_JOB_ID".to_owned(),
+                "83267e88-efdd-4b1d-92c0-6b80d01887f8".to_owned()
+            ),
+            (
+                "ONEFUZZ_TASK_ID".to_owned(),
+                "eb8ee6b8-6f2d-43b1-aec2-022e9813e86b".to_owned()
+            ),
+            (
+                "ONEFUZZ_SETUP_URL".to_owned(),
+                "https://contoso.com/my-setup-container".to_owned()
+            ),
+        ])
+    );
+}
+
+#[test]
+fn test_to_env_vars_optional() {
+    let mut work_set = work_set();
+    work_set.reboot = true;
+    work_set.script = true;
+    work_set.extra_setup_url =
+        Some(BlobContainerUrl::parse("https://contoso.com/my-extra-setup-container").unwrap());
+
+    let vars = work_set.to_env_vars();
+
+    assert!(vars.contains_key("ONEFUZZ_REBOOT"));
+    assert!(vars.contains_key("ONEFUZZ_SCRIPT"));
+    assert_eq!(
+        vars["ONEFUZZ_EXTRA_SETUP_URL"],
+        "https://contoso.com/my-extra-setup-container"
+    );
+
+    // the setup URL round trips through the variable
+    let setup_url = BlobContainerUrl::parse(&vars["ONEFUZZ_SETUP_URL"]).unwrap();
+    assert_eq!(setup_url, work_set.setup_url);
+}
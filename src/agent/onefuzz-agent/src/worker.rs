@@ -4,8 +4,9 @@ use std::{
     collections::HashMap,
     path::{Path, PathBuf},
     process::{Child, ChildStderr, ChildStdout, Command, Stdio},
+    sync::{mpsc, Arc, Mutex},
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::{format_err, Context as AnyhowContext, Result};
@@ -13,17 +14,24 @@ use downcast_rs::Downcast;
 use ipc_channel::ipc::{IpcOneShotServer, IpcReceiver, IpcSender};
 use onefuzz::{
     ipc::IpcMessageKind,
+    libfuzzer::LibFuzzerStats,
     machine_id::MachineIdentity,
     process::{ExitStatus, Output},
 };
+#[cfg(test)]
+use regex::Regex;
 use tokio::{
-    fs, task,
+    fs,
+    sync::broadcast,
+    task,
     time::{error::Elapsed, timeout},
 };
 use url::Url;
 use uuid::Uuid;
 
+use crate::debugger::DebuggerSession;
 use crate::work::*;
+use crate::worker::corpus::Pruned;
 use crate::{buffer::TailBuffer, log_uploader::Uploader};
 
 use serde_json::Value;
@@ -31,18 +39,221 @@ use serde_json::Value;
 // Max length of captured output streams from worker child processes.
 const MAX_TAIL_LEN: usize = 40960;
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+// Min time between prunes of a running worker's corpus directories.
+const CORPUS_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+// Time to wait for a killed worker child process to exit.
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(90);
+
+// Max number of stdout lines buffered for `Worker::wait_for_output()`, while
+// it is waiting. Lines are truncated to `MAX_STDOUT_LINE_LEN` bytes, so the
+// buffer holds at most 1 MiB.
+const MAX_STDOUT_LINES: usize = 256;
+const MAX_STDOUT_LINE_LEN: usize = 4096;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum WorkerEvent {
     Running {
         task_id: TaskId,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        tags: HashMap<String, String>,
     },
     Done {
         task_id: TaskId,
         exit_status: ExitStatus,
         stderr: String,
         stdout: String,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        tags: HashMap<String, String>,
+    },
+    Metric {
+        task_id: TaskId,
+        name: String,
+        value: f64,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        tags: HashMap<String, String>,
+    },
+    Stats {
+        task_id: TaskId,
+        stats: LibFuzzerStats,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        tags: HashMap<String, String>,
     },
+    /// Output of the setup script, sent whenever one runs, whether it succeeds
+    /// or not.
+    SetupOutput { script_output: Output },
+    /// Old entries were deleted from the worker's corpus, to keep it under the
+    /// work unit's `max_corpus_size`.
+    CorpusPruned {
+        task_id: TaskId,
+        removed: usize,
+        kept: usize,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        tags: HashMap<String, String>,
+    },
+    /// The worker was restarted after the agent crashed, from a checkpoint
+    /// saved while it was running.
+    Resumed {
+        task_id: TaskId,
+        /// How long the worker had been running when the checkpoint was saved.
+        #[serde(with = "duration_secs")]
+        previous_elapsed: Duration,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        tags: HashMap<String, String>,
+    },
+    /// A message logged by a worker on its stderr, as a line of the form
+    /// `[ONEFUZZ:LEVEL:message]`.
+    Log {
+        task_id: TaskId,
+        level: LogLevel,
+        message: String,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        tags: HashMap<String, String>,
+    },
+    /// Reply to a `Ping` node command, echoing its token.
+    Pong { token: String },
+}
+
+impl WorkerEvent {
+    pub fn kind(&self) -> WorkerEventKind {
+        match self {
+            Self::Running { .. } => WorkerEventKind::Running,
+            Self::Done { .. } => WorkerEventKind::Done,
+            Self::Metric { .. } => WorkerEventKind::Metric,
+            Self::Stats { .. } => WorkerEventKind::Stats,
+            Self::SetupOutput { .. } => WorkerEventKind::SetupOutput,
+            Self::CorpusPruned { .. } => WorkerEventKind::CorpusPruned,
+            Self::Resumed { .. } => WorkerEventKind::Resumed,
+            Self::Log { .. } => WorkerEventKind::Log,
+            Self::Pong { .. } => WorkerEventKind::Pong,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerEventKind {
+    Running,
+    Done,
+    Metric,
+    Stats,
+    SetupOutput,
+    CorpusPruned,
+    Resumed,
+    Log,
+    Pong,
+}
+
+/// Prefix of worker stderr lines that report a metric, as
+/// `METRIC:name=value[,tag=value]*`.
+const METRIC_PREFIX: &str = "METRIC:";
+
+/// A metric reported by a worker on its stderr.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkerMetric {
+    pub name: String,
+    pub value: f64,
+    pub tags: HashMap<String, String>,
+}
+
+impl WorkerMetric {
+    /// Parse a stderr line, returning `None` if it does not report a metric or
+    /// is malformed.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end().strip_prefix(METRIC_PREFIX)?;
+        let mut pairs = line.split(',');
+
+        let (name, value) = pairs.next()?.split_once('=')?;
+        if name.is_empty() {
+            return None;
+        }
+        let value = value.parse().ok()?;
+
+        let tags = pairs
+            .map(|pair| {
+                pair.split_once('=')
+                    .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            name: name.to_owned(),
+            value,
+            tags,
+        })
+    }
+}
+
+/// Prefix of worker stderr lines that log a message, as
+/// `[ONEFUZZ:LEVEL:message]`.
+const LOG_PREFIX: &str = "[ONEFUZZ:";
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// Parse a level name, ignoring case.
+    pub fn parse(level: &str) -> Option<Self> {
+        let level = match level.to_ascii_lowercase().as_str() {
+            "error" => Self::Error,
+            "warn" => Self::Warn,
+            "info" => Self::Info,
+            "debug" => Self::Debug,
+            "trace" => Self::Trace,
+            _ => return None,
+        };
+
+        Some(level)
+    }
+}
+
+/// A message logged by a worker on its stderr.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkerLog {
+    pub level: LogLevel,
+    pub message: String,
+}
+
+impl WorkerLog {
+    /// Parse a stderr line, returning `None` if it does not log a message or
+    /// has an unknown level. The message may contain `:` and `]`.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line
+            .trim_end()
+            .strip_prefix(LOG_PREFIX)?
+            .strip_suffix(']')?;
+        let (level, message) = line.split_once(':')?;
+
+        Some(Self {
+            level: LogLevel::parse(level)?,
+            message: message.to_owned(),
+        })
+    }
+}
+
+/// A report parsed from a line of worker stderr.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StderrEvent {
+    Metric(WorkerMetric),
+    Stats(LibFuzzerStats),
+    Log(WorkerLog),
+}
+
+impl StderrEvent {
+    pub fn parse(line: &str) -> Option<Self> {
+        WorkerMetric::parse(line)
+            .map(Self::Metric)
+            .or_else(|| WorkerLog::parse(line).map(Self::Log))
+            .or_else(|| LibFuzzerStats::parse(line).map(Self::Stats))
+    }
 }
 
 #[derive(Debug)]
@@ -64,16 +275,180 @@ impl Worker {
             work_dir,
             setup_dir,
             extra_setup_dir,
+            env: HashMap::new(),
         };
 
         let state = State { ctx, work };
         state.into()
     }
 
+    /// Set an environment variable of the worker's child process, for values that
+    /// are not known until after the worker is constructed.
+    ///
+    /// Fails if the child process has already been spawned.
+    #[cfg(test)]
+    pub fn set_env(&mut self, key: &str, value: &str) -> Result<()> {
+        match self {
+            Worker::Ready(state) => {
+                state.ctx.env.insert(key.to_owned(), value.to_owned());
+                Ok(())
+            }
+            _ => bail!("cannot set env on running worker"),
+        }
+    }
+
     pub fn is_done(&self) -> bool {
         matches!(self, Worker::Done(..))
     }
 
+    /// Exit status of the worker's child process, once it is done.
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        match self {
+            Worker::Done(state) => Some(state.ctx.output.exit_status),
+            _ => None,
+        }
+    }
+
+    /// Whether the worker's child process exited with a failure on its own,
+    /// rather than after being stopped or cancelled.
+    pub fn exited_abnormally(&self) -> bool {
+        let stopped = matches!(self, Worker::Done(state) if state.ctx.stopped);
+        !stopped && matches!(self.exit_status(), Some(s) if !s.success)
+    }
+
+    pub fn task_id(&self) -> TaskId {
+        match self {
+            Worker::Ready(state) => state.work.task_id,
+            Worker::Running(state) => state.work.task_id,
+            Worker::Stopping(state) => state.work.task_id,
+            Worker::Done(state) => state.work.task_id,
+        }
+    }
+
+    /// OS process ID of the worker's child process, if it has been spawned and
+    /// has not exited.
+    pub fn pid(&self) -> Option<u32> {
+        match self {
+            Worker::Running(state) => state.ctx.child.pid(),
+            Worker::Stopping(state) => state.ctx.child.pid(),
+            Worker::Ready(..) | Worker::Done(..) => None,
+        }
+    }
+
+    /// Current CPU and memory usage of the worker's child process.
+    pub fn resource_usage(&self) -> Result<ResourceUsage> {
+        let pid = self
+            .pid()
+            .ok_or_else(|| format_err!("worker for task {} is not running", self.task_id()))?;
+
+        usage::resource_usage(pid)
+    }
+
+    /// Kernel wait channel the worker's child process is sleeping in, if any.
+    pub fn wait_channel(&self) -> Result<Option<String>> {
+        let pid = self
+            .pid()
+            .ok_or_else(|| format_err!("worker for task {} is not running", self.task_id()))?;
+
+        usage::wait_channel(pid)
+    }
+
+    /// Attach a debugger to the worker's running child process.
+    pub fn attach_debugger(&self) -> Result<DebuggerSession> {
+        let pid = self
+            .pid()
+            .ok_or_else(|| format_err!("worker for task {} is not running", self.task_id()))?;
+
+        DebuggerSession::attach(pid)
+    }
+
+    /// Make the worker's child process crash, by writing a breakpoint
+    /// instruction at `addr` in its memory. It crashes when it next executes
+    /// the instruction there.
+    ///
+    /// For testing how the agent handles workers that crash, such as retrying
+    /// the work unit until it is out of retries.
+    #[cfg(test)]
+    pub fn inject_fault(&self, addr: u64) -> Result<()> {
+        let pid = self
+            .pid()
+            .ok_or_else(|| format_err!("worker for task {} is not running", self.task_id()))?;
+
+        fault::inject_fault(pid, addr)
+    }
+
+    /// Wait until the worker's child process prints a stdout line containing
+    /// `pattern`, and return the line.
+    ///
+    /// Only lines printed after the first call are searched, since stdout lines
+    /// are not buffered until something waits for them. Fails if the child
+    /// exits or `timeout` elapses first.
+    #[cfg(test)]
+    pub async fn wait_for_output(&mut self, pattern: &str, timeout: Duration) -> Result<String> {
+        let description = format!("`{}`", pattern);
+        self.wait_for_stdout_line(&description, |line| line.contains(pattern), timeout)
+            .await
+    }
+
+    /// Like [`Worker::wait_for_output`], but waits for a line matching a regex.
+    #[cfg(test)]
+    pub async fn wait_for_output_matching(
+        &mut self,
+        pattern: &Regex,
+        timeout: Duration,
+    ) -> Result<String> {
+        let description = format!("a match of `{}`", pattern);
+        self.wait_for_stdout_line(&description, |line| pattern.is_match(line), timeout)
+            .await
+    }
+
+    #[cfg(test)]
+    async fn wait_for_stdout_line(
+        &mut self,
+        description: &str,
+        matches: impl Fn(&str) -> bool,
+        duration: Duration,
+    ) -> Result<String> {
+        let task_id = self.task_id();
+        let child = match self {
+            Worker::Running(state) => &mut state.ctx.child,
+            _ => bail!("worker for task {} is not running", task_id),
+        };
+
+        let search = async {
+            while let Some(line) = child.next_stdout_line().await {
+                if matches(&line) {
+                    return Some(line);
+                }
+            }
+            None
+        };
+
+        match timeout(duration, search).await {
+            Ok(Some(line)) => Ok(line),
+            Ok(None) => bail!(
+                "worker for task {} exited before printing {}",
+                task_id,
+                description
+            ),
+            Err(_) => bail!(
+                "timed out waiting for worker for task {} to print {}",
+                task_id,
+                description
+            ),
+        }
+    }
+
+    /// Directory the worker's task runs in, which holds its artifacts and logs.
+    pub fn working_dir(&self) -> &Path {
+        match self {
+            Worker::Ready(state) => &state.ctx.work_dir,
+            Worker::Running(state) => &state.ctx.work_dir,
+            Worker::Stopping(state) => &state.ctx.work_dir,
+            Worker::Done(state) => &state.ctx.work_dir,
+        }
+    }
+
     pub async fn update(
         self,
         events: &mut Vec<WorkerEvent>,
@@ -84,24 +459,12 @@ impl Worker {
                 let state = state.run(runner).await?;
                 let event = WorkerEvent::Running {
                     task_id: state.work.task_id,
+                    tags: state.work.tags.clone(),
                 };
                 events.push(event);
                 state.into()
             }
-            Worker::Running(state) => match state.wait().await? {
-                Waited::Done(state) => {
-                    let output = state.output();
-                    let event = WorkerEvent::Done {
-                        exit_status: output.exit_status,
-                        stderr: output.stderr,
-                        stdout: output.stdout,
-                        task_id: state.work.task_id,
-                    };
-                    events.push(event);
-                    state.into()
-                }
-                Waited::Running(state) => state.into(),
-            },
+            worker @ Worker::Running(..) => worker.poll(events).await?,
             Worker::Stopping(state) => {
                 let state = state.kill().await?;
                 state.into()
@@ -114,6 +477,113 @@ impl Worker {
 
         Ok(worker)
     }
+
+    /// Check whether a running worker's child process has exited, without
+    /// starting or stopping any worker. Workers in other states are unchanged.
+    pub async fn poll(self, events: &mut Vec<WorkerEvent>) -> Result<Self> {
+        let mut state = match self {
+            Worker::Running(state) => state,
+            worker => return Ok(worker),
+        };
+
+        for event in state.ctx.child.stderr_events() {
+            let event = match event {
+                StderrEvent::Metric(metric) => {
+                    let mut tags = state.work.tags.clone();
+                    tags.extend(metric.tags);
+                    WorkerEvent::Metric {
+                        task_id: state.work.task_id,
+                        name: metric.name,
+                        value: metric.value,
+                        tags,
+                    }
+                }
+                StderrEvent::Stats(stats) => WorkerEvent::Stats {
+                    task_id: state.work.task_id,
+                    stats,
+                    tags: state.work.tags.clone(),
+                },
+                StderrEvent::Log(log) => WorkerEvent::Log {
+                    task_id: state.work.task_id,
+                    level: log.level,
+                    message: log.message,
+                    tags: state.work.tags.clone(),
+                },
+            };
+            events.push(event);
+        }
+
+        let prune_due = state
+            .ctx
+            .pruned_at
+            .map_or(true, |at| at.elapsed() >= CORPUS_PRUNE_INTERVAL);
+
+        if let Some(max_size) = state.work.max_corpus_size.filter(|_| prune_due) {
+            state.ctx.pruned_at = Some(Instant::now());
+
+            match prune_corpus(&state.work, &state.ctx.work_dir, max_size).await {
+                Ok(pruned) if pruned.removed > 0 => events.push(WorkerEvent::CorpusPruned {
+                    task_id: state.work.task_id,
+                    removed: pruned.removed,
+                    kept: pruned.kept,
+                    tags: state.work.tags.clone(),
+                }),
+                Ok(_) => {}
+                Err(err) => warn!(
+                    "unable to prune corpus of task {}: {:?}",
+                    state.work.task_id, err
+                ),
+            }
+        }
+
+        let worker = match state.wait().await? {
+            Waited::Done(state) => {
+                let output = state.output();
+                let event = WorkerEvent::Done {
+                    exit_status: output.exit_status,
+                    stderr: output.stderr,
+                    stdout: output.stdout,
+                    task_id: state.work.task_id,
+                    tags: state.work.tags.clone(),
+                };
+                events.push(event);
+                state.into()
+            }
+            Waited::Running(state) => state.into(),
+        };
+
+        Ok(worker)
+    }
+
+    /// Forcefully kill the worker's child process, if it has one, and wait
+    /// for it to exit.
+    pub async fn cancel(self) -> Result<Self> {
+        let worker = match self {
+            Worker::Running(state) => state.stop().cancel().await?.into(),
+            Worker::Stopping(state) => state.cancel().await?.into(),
+            worker => worker,
+        };
+
+        Ok(worker)
+    }
+}
+
+// Prune each corpus directory of a work unit down to `max_size` entries.
+async fn prune_corpus(work: &WorkUnit, work_dir: &Path, max_size: usize) -> Result<Pruned> {
+    let output_dir = work
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| work_dir.to_owned());
+    let dirs = work.corpus_dirs(&output_dir)?;
+
+    let mut total = Pruned::default();
+    for dir in dirs {
+        let pruned = task::spawn_blocking(move || corpus::prune(&dir, max_size)).await??;
+        total.removed += pruned.removed;
+        total.kept += pruned.kept;
+    }
+
+    Ok(total)
 }
 
 #[derive(Debug)]
@@ -121,24 +591,36 @@ pub struct Ready {
     work_dir: PathBuf,
     setup_dir: PathBuf,
     extra_setup_dir: Option<PathBuf>,
+
+    /// Environment variables set on the child process, in addition to the agent's own.
+    env: HashMap<String, String>,
 }
 
 #[derive(Debug)]
 pub struct Running {
     child: Box<dyn IWorkerChild>,
+    work_dir: PathBuf,
     _from_agent_to_task: IpcSender<IpcMessageKind>,
     from_task_to_agent: IpcReceiver<IpcMessageKind>,
     log_uploader: Option<Uploader>,
+
+    /// When the corpus was last pruned, if it has been.
+    pruned_at: Option<Instant>,
 }
 
 #[derive(Debug)]
 pub struct Stopping {
     child: Box<dyn IWorkerChild>,
+    work_dir: PathBuf,
 }
 
 #[derive(Debug)]
 pub struct Done {
     output: Output,
+    work_dir: PathBuf,
+
+    /// Whether the child process was stopped by the agent.
+    stopped: bool,
 }
 
 pub trait Context {}
@@ -177,6 +659,7 @@ impl State<Ready> {
                 &self.ctx.setup_dir,
                 self.ctx.extra_setup_dir,
                 &self.work,
+                &self.ctx.env,
                 from_agent_to_task_endpoint,
                 from_task_to_agent_endpoint,
             )
@@ -277,9 +760,11 @@ impl State<Ready> {
         let state = State {
             ctx: Running {
                 child,
+                work_dir: self.ctx.work_dir,
                 _from_agent_to_task: from_agent_to_task,
                 from_task_to_agent,
                 log_uploader,
+                pruned_at: None,
             },
             work: self.work,
         };
@@ -297,7 +782,13 @@ impl State<Running> {
         let waited = self.ctx.child.try_wait()?;
 
         if let Some(output) = waited {
-            let ctx = Done { output };
+            // `Running` implements `Drop`, so its fields can't be moved out.
+            let work_dir = std::mem::take(&mut self.ctx.work_dir);
+            let ctx = Done {
+                output,
+                work_dir,
+                stopped: false,
+            };
             let state = State {
                 ctx,
                 work: self.work,
@@ -317,8 +808,10 @@ impl State<Running> {
     pub fn stop(mut self) -> State<Stopping> {
         let c = std::mem::replace(&mut self.ctx.child, Box::new(NoopChild {}));
 
+        let work_dir = std::mem::take(&mut self.ctx.work_dir);
+
         State {
-            ctx: Stopping { child: c },
+            ctx: Stopping { child: c, work_dir },
             work: self.work,
         }
     }
@@ -354,7 +847,11 @@ impl State<Stopping> {
         .await
         {
             Ok(Ok(output)) => {
-                let ctx = Done { output };
+                let ctx = Done {
+                    output,
+                    work_dir: self.ctx.work_dir,
+                    stopped: true,
+                };
                 Ok(State {
                     ctx,
                     work: self.work,
@@ -374,6 +871,53 @@ impl State<Stopping> {
             }
         }
     }
+
+    /// Kill the child process without waiting for a graceful shutdown.
+    ///
+    /// If the child has not exited within `CANCEL_TIMEOUT` of being killed, it is
+    /// left behind and reported as done, with an unknown exit status.
+    pub async fn cancel(mut self) -> Result<State<Done>> {
+        self.ctx.child.kill()?;
+
+        let wait = async {
+            loop {
+                if let Some(output) = self.ctx.child.try_wait()? {
+                    return Ok::<_, anyhow::Error>(output);
+                }
+
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+
+        let output = match timeout(CANCEL_TIMEOUT, wait).await {
+            Ok(output) => output?,
+            Err(_) => {
+                error!(
+                    "killed task did not exit within {:?}, giving up on it",
+                    CANCEL_TIMEOUT
+                );
+                Output {
+                    exit_status: ExitStatus {
+                        code: None,
+                        signal: None,
+                        success: false,
+                    },
+                    stderr: String::new(),
+                    stdout: String::new(),
+                }
+            }
+        };
+
+        let ctx = Done {
+            output,
+            work_dir: self.ctx.work_dir,
+            stopped: true,
+        };
+        Ok(State {
+            ctx,
+            work: self.work,
+        })
+    }
 }
 
 pub enum Waited {
@@ -404,11 +948,17 @@ impl_from_state_for_worker!(Done);
 
 #[async_trait]
 pub trait IWorkerRunner: Downcast {
+    /// Environment variables the runner sets on the child process of a work
+    /// unit. Variables set on the worker with `Worker::set_env()` take
+    /// precedence.
+    fn prepare_environment(&self, work: &WorkUnit) -> Result<HashMap<String, String>>;
+
     async fn run(
         &self,
         setup_dir: &Path,
         extra_setup_dir: Option<PathBuf>,
         work: &WorkUnit,
+        env: &HashMap<String, String>,
         from_agent_to_task_endpoint: String,
         from_task_to_agent_endpoint: String,
     ) -> Result<Box<dyn IWorkerChild>>;
@@ -416,10 +966,20 @@ pub trait IWorkerRunner: Downcast {
 
 impl_downcast!(IWorkerRunner);
 
+#[async_trait]
 pub trait IWorkerChild: Downcast + std::fmt::Debug {
+    /// OS process ID of the child, if it has one.
+    fn pid(&self) -> Option<u32>;
+
     fn try_wait(&mut self) -> Result<Option<Output>>;
 
     fn kill(&mut self) -> Result<()>;
+
+    /// Metrics and libFuzzer stats reported on stderr since the last call.
+    fn stderr_events(&mut self) -> Vec<StderrEvent>;
+
+    /// Next line printed on stdout, or `None` once stdout is closed.
+    async fn next_stdout_line(&mut self) -> Option<String>;
 }
 
 impl_downcast!(IWorkerChild);
@@ -436,11 +996,19 @@ impl WorkerRunner {
 
 #[async_trait]
 impl IWorkerRunner for WorkerRunner {
+    fn prepare_environment(&self, work: &WorkUnit) -> Result<HashMap<String, String>> {
+        let mut env = HashMap::new();
+        env.insert("ONEFUZZ_JOB_ID".to_owned(), work.job_id.to_string());
+        env.insert("ONEFUZZ_TASK_ID".to_owned(), work.task_id.to_string());
+        Ok(env)
+    }
+
     async fn run(
         &self,
         setup_dir: &Path,
         extra_setup_dir: Option<PathBuf>,
         work: &WorkUnit,
+        env: &HashMap<String, String>,
         from_agent_to_task_endpoint: String,
         from_task_to_agent_endpoint: String,
     ) -> Result<Box<dyn IWorkerChild>> {
@@ -457,6 +1025,18 @@ impl IWorkerRunner for WorkerRunner {
 
         debug!("created worker working dir: {}", working_dir.display());
 
+        let output_dir = work.output_dir(self.machine_identity.machine_id)?;
+        if output_dir != working_dir {
+            fs::create_dir_all(&output_dir).await.with_context(|| {
+                format!(
+                    "unable to create worker output directory: {}",
+                    output_dir.display()
+                )
+            })?;
+
+            debug!("created worker output dir: {}", output_dir.display());
+        }
+
         // inject the machine_identity in the config file
         let work_config = work.config.expose_ref();
         let mut config: HashMap<&str, Value> = serde_json::from_str(work_config.as_str())?;
@@ -476,6 +1056,13 @@ impl IWorkerRunner for WorkerRunner {
             from_task_to_agent_endpoint.into(),
         );
 
+        if work.stdin_file.is_some() && uses_input_argument(&config) {
+            bail!(
+                "task {} sets a stdin file, but its target options also pass `{{input}}`",
+                work.task_id
+            );
+        }
+
         let config_path = work.config_path(self.machine_identity.machine_id)?;
 
         fs::write(&config_path, serde_json::to_string(&config)?.as_bytes())
@@ -489,13 +1076,18 @@ impl IWorkerRunner for WorkerRunner {
 
         info!(
             "spawning `onefuzz-task`; cwd = {}, job_id = {}, task_id = {}",
-            working_dir.display(),
+            output_dir.display(),
             work.job_id,
             work.task_id,
         );
 
+        let mut envs = self.prepare_environment(work)?;
+        envs.extend(env.iter().map(|(k, v)| (k.clone(), v.clone())));
+
         let mut cmd = Command::new("onefuzz-task");
-        cmd.current_dir(&working_dir);
+        // Task outputs are relative to the current directory.
+        cmd.current_dir(&output_dir);
+        cmd.envs(envs);
         cmd.arg("managed");
         cmd.arg(config_path);
         cmd.arg(setup_dir);
@@ -504,6 +1096,12 @@ impl IWorkerRunner for WorkerRunner {
             cmd.arg(extra_setup_dir);
         }
 
+        if let Some(stdin_file) = &work.stdin_file {
+            let stdin = std::fs::File::open(stdin_file)
+                .with_context(|| format!("unable to open stdin file: {}", stdin_file.display()))?;
+            cmd.stdin(stdin);
+        }
+
         cmd.stderr(Stdio::piped());
         cmd.stdout(Stdio::piped());
 
@@ -511,6 +1109,21 @@ impl IWorkerRunner for WorkerRunner {
     }
 }
 
+/// Whether a task config passes the input to the target as an argument, via
+/// `{input}` in its `target_options`.
+fn uses_input_argument(config: &HashMap<&str, Value>) -> bool {
+    config
+        .get("target_options")
+        .and_then(Value::as_array)
+        .map(|options| {
+            options
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|option| option.contains("{input}"))
+        })
+        .unwrap_or(false)
+}
+
 trait SuspendableChild {
     fn suspend(&self) -> Result<()>;
 }
@@ -548,6 +1161,25 @@ struct RedirectedChild {
 
     /// Worker threads which continuously read from the redirected streams.
     streams: Option<StreamReaderThreads>,
+
+    /// Events parsed from stderr by the stream reader.
+    stderr_events: mpsc::Receiver<StderrEvent>,
+
+    /// Where the stdout reader sends lines, once `next_stdout_line()` is called.
+    stdout_subscriber: Arc<Mutex<StdoutSubscriber>>,
+
+    /// Lines of stdout not yet returned by `next_stdout_line()`. Older lines
+    /// are dropped when it is full.
+    stdout_lines: Option<broadcast::Receiver<String>>,
+}
+
+/// Destination of the stdout lines of a `RedirectedChild`.
+#[derive(Debug, Default)]
+struct StdoutSubscriber {
+    lines: Option<broadcast::Sender<String>>,
+
+    /// Whether stdout was closed.
+    closed: bool,
 }
 
 impl RedirectedChild {
@@ -561,16 +1193,34 @@ impl RedirectedChild {
         // Guaranteed by the above.
         let stderr = child.stderr.take().unwrap();
         let stdout = child.stdout.take().unwrap();
-        let streams = Some(StreamReaderThreads::new(stderr, stdout));
-
-        Ok(Self { child, streams })
+        let (stderr_events_tx, stderr_events) = mpsc::channel();
+        let stdout_subscriber = Arc::new(Mutex::new(StdoutSubscriber::default()));
+        let streams = Some(StreamReaderThreads::new(
+            stderr,
+            stdout,
+            stderr_events_tx,
+            stdout_subscriber.clone(),
+        ));
+
+        Ok(Self {
+            child,
+            streams,
+            stderr_events,
+            stdout_subscriber,
+            stdout_lines: None,
+        })
     }
 }
 
 #[derive(Debug)]
 struct NoopChild {}
 
+#[async_trait]
 impl IWorkerChild for NoopChild {
+    fn pid(&self) -> Option<u32> {
+        None
+    }
+
     fn try_wait(&mut self) -> Result<Option<Output>> {
         Ok(None)
     }
@@ -578,6 +1228,14 @@ impl IWorkerChild for NoopChild {
     fn kill(&mut self) -> Result<()> {
         Ok(())
     }
+
+    fn stderr_events(&mut self) -> Vec<StderrEvent> {
+        vec![]
+    }
+
+    async fn next_stdout_line(&mut self) -> Option<String> {
+        None
+    }
 }
 
 /// Worker threads that tail the redirected output streams of a running child process.
@@ -593,17 +1251,24 @@ struct CapturedStreams {
 }
 
 impl StreamReaderThreads {
-    pub fn new(mut stderr: ChildStderr, mut stdout: ChildStdout) -> Self {
+    pub fn new(
+        mut stderr: ChildStderr,
+        mut stdout: ChildStdout,
+        stderr_events: mpsc::Sender<StderrEvent>,
+        stdout_subscriber: Arc<Mutex<StdoutSubscriber>>,
+    ) -> Self {
         use std::io::Read;
 
         let stderr = thread::spawn(move || {
             let mut buf = TailBuffer::new(MAX_TAIL_LEN);
             let mut tmp = [0u8; MAX_TAIL_LEN];
+            let mut lines = StderrLines::new(stderr_events);
 
             while let Ok(count) = stderr.read(&mut tmp) {
                 if count == 0 {
                     break;
                 }
+                lines.push(&tmp[..count]);
                 if let Err(err) = std::io::copy(&mut &tmp[..count], &mut buf) {
                     log::error!("error copying to circular buffer: {}", err);
                     break;
@@ -616,11 +1281,13 @@ impl StreamReaderThreads {
         let stdout = thread::spawn(move || {
             let mut buf = TailBuffer::new(MAX_TAIL_LEN);
             let mut tmp = [0u8; MAX_TAIL_LEN];
+            let mut lines = StdoutLines::new(stdout_subscriber);
 
             while let Ok(count) = stdout.read(&mut tmp) {
                 if count == 0 {
                     break;
                 }
+                lines.push(&tmp[..count]);
 
                 if let Err(err) = std::io::copy(&mut &tmp[..count], &mut buf) {
                     log::error!("error copying to circular buffer: {}", err);
@@ -650,7 +1317,84 @@ impl StreamReaderThreads {
     }
 }
 
+/// Splits a stream into lines and sends the events they report.
+struct StderrLines {
+    line: Vec<u8>,
+    events: mpsc::Sender<StderrEvent>,
+}
+
+impl StderrLines {
+    fn new(events: mpsc::Sender<StderrEvent>) -> Self {
+        Self {
+            line: vec![],
+            events,
+        }
+    }
+
+    fn push(&mut self, data: &[u8]) {
+        for &byte in data {
+            if byte == b'\n' {
+                let line = String::from_utf8_lossy(&self.line);
+                if let Some(event) = StderrEvent::parse(&line) {
+                    // The receiver is only dropped with the child.
+                    let _ = self.events.send(event);
+                }
+                self.line.clear();
+            } else if self.line.len() < MAX_TAIL_LEN {
+                // Overlong lines are truncated, and then fail to parse.
+                self.line.push(byte);
+            }
+        }
+    }
+}
+
+/// Splits a stream into lines and sends them to be searched by `wait_for_output()`.
+///
+/// Lines are dropped while nothing is subscribed.
+struct StdoutLines {
+    line: Vec<u8>,
+    subscriber: Arc<Mutex<StdoutSubscriber>>,
+}
+
+impl StdoutLines {
+    fn new(subscriber: Arc<Mutex<StdoutSubscriber>>) -> Self {
+        Self {
+            line: vec![],
+            subscriber,
+        }
+    }
+
+    fn push(&mut self, data: &[u8]) {
+        for &byte in data {
+            if byte == b'\n' {
+                if let Some(lines) = &self.subscriber.lock().unwrap().lines {
+                    let line = String::from_utf8_lossy(&self.line).into_owned();
+                    // The receiver is only dropped with the child.
+                    let _ = lines.send(line);
+                }
+                self.line.clear();
+            } else if self.line.len() < MAX_STDOUT_LINE_LEN {
+                self.line.push(byte);
+            }
+        }
+    }
+}
+
+impl Drop for StdoutLines {
+    fn drop(&mut self) {
+        // Close the channel, so the subscriber sees the end of stdout.
+        let mut subscriber = self.subscriber.lock().unwrap();
+        subscriber.lines = None;
+        subscriber.closed = true;
+    }
+}
+
+#[async_trait]
 impl IWorkerChild for RedirectedChild {
+    fn pid(&self) -> Option<u32> {
+        Some(self.child.id())
+    }
+
     fn try_wait(&mut self) -> Result<Option<Output>> {
         let output = if let Some(exit_status) = self.child.try_wait()? {
             let exit_status = exit_status.into();
@@ -691,8 +1435,41 @@ impl IWorkerChild for RedirectedChild {
 
         Ok(())
     }
+
+    fn stderr_events(&mut self) -> Vec<StderrEvent> {
+        self.stderr_events.try_iter().collect()
+    }
+
+    async fn next_stdout_line(&mut self) -> Option<String> {
+        if self.stdout_lines.is_none() {
+            let mut subscriber = self.stdout_subscriber.lock().unwrap();
+            if subscriber.closed {
+                return None;
+            }
+
+            let (lines_tx, lines) = broadcast::channel(MAX_STDOUT_LINES);
+            subscriber.lines = Some(lines_tx);
+            self.stdout_lines = Some(lines);
+        }
+
+        let stdout_lines = self.stdout_lines.as_mut()?;
+        loop {
+            match stdout_lines.recv().await {
+                Ok(line) => return Some(line),
+                // Lines dropped when the buffer was full are skipped.
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
 }
 
+mod corpus;
+#[cfg(test)]
+mod fault;
+mod usage;
+pub use usage::ResourceUsage;
+
 #[cfg(test)]
 pub mod double;
 
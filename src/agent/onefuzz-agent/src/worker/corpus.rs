@@ -0,0 +1,161 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+
+/// Number of corpus files deleted and kept by `prune()`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Pruned {
+    pub removed: usize,
+    pub kept: usize,
+}
+
+/// Delete the oldest files in `dir`, by modification time, until at most
+/// `max_size` remain. Subdirectories are left alone, and not counted.
+///
+/// A missing directory has nothing to prune.
+pub fn prune(dir: &Path, max_size: usize) -> Result<Pruned> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Pruned::default()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("unable to read corpus {}", dir.display()))
+        }
+    };
+
+    let mut files = vec![];
+    for entry in entries {
+        let entry = entry?;
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            // Removed since it was listed, e.g. by the fuzzer's own merging.
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        if metadata.is_file() {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((modified, entry.path()));
+        }
+    }
+
+    if files.len() <= max_size {
+        return Ok(Pruned {
+            removed: 0,
+            kept: files.len(),
+        });
+    }
+
+    files.sort();
+    let excess = files.len() - max_size;
+
+    let mut removed = 0;
+    for (_, path) in &files[..excess] {
+        match fs::remove_file(path) {
+            Ok(()) => removed += 1,
+            Err(err) if err.kind() == ErrorKind::NotFound => removed += 1,
+            Err(err) => {
+                return Err(err).with_context(|| format!("unable to remove {}", path.display()))
+            }
+        }
+    }
+
+    Ok(Pruned {
+        removed,
+        kept: max_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    struct Fixture {
+        dir: PathBuf,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("corpus-{}", Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self { dir }
+        }
+
+        fn path(&self) -> &Path {
+            &self.dir
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn write_with_mtime(dir: &Path, name: &str, secs: u64) {
+        let path = dir.join(name);
+        fs::write(&path, name).unwrap();
+
+        let mtime = filetime::FileTime::from_system_time(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+        );
+        filetime::set_file_mtime(&path, mtime).unwrap();
+    }
+
+    #[test]
+    fn test_prune_removes_oldest() {
+        let dir = Fixture::new();
+        write_with_mtime(dir.path(), "c", 3000);
+        write_with_mtime(dir.path(), "a", 1000);
+        write_with_mtime(dir.path(), "b", 2000);
+        fs::create_dir(dir.path().join("subdir")).unwrap();
+
+        let pruned = prune(dir.path(), 2).unwrap();
+        assert_eq!(
+            pruned,
+            Pruned {
+                removed: 1,
+                kept: 2
+            }
+        );
+
+        assert!(!dir.path().join("a").exists());
+        assert!(dir.path().join("b").exists());
+        assert!(dir.path().join("c").exists());
+        assert!(dir.path().join("subdir").exists());
+    }
+
+    #[test]
+    fn test_prune_under_limit() {
+        let dir = Fixture::new();
+        write_with_mtime(dir.path(), "a", 1000);
+
+        let pruned = prune(dir.path(), 2).unwrap();
+        assert_eq!(
+            pruned,
+            Pruned {
+                removed: 0,
+                kept: 1
+            }
+        );
+        assert!(dir.path().join("a").exists());
+    }
+
+    #[test]
+    fn test_prune_missing_dir() {
+        let dir = Fixture::new();
+
+        let pruned = prune(&dir.path().join("missing"), 0).unwrap();
+        assert_eq!(pruned, Pruned::default());
+    }
+}
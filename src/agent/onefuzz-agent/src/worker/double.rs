@@ -1,6 +1,8 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+use std::convert::TryFrom;
+
 use ipc_channel::ipc;
 
 use super::*;
@@ -8,15 +10,21 @@ use super::*;
 #[derive(Clone, Debug, Default)]
 pub struct WorkerRunnerDouble {
     pub child: ChildDouble,
+    pub env: HashMap<String, String>,
 }
 
 #[async_trait]
 impl IWorkerRunner for WorkerRunnerDouble {
+    fn prepare_environment(&self, _work: &WorkUnit) -> Result<HashMap<String, String>> {
+        Ok(self.env.clone())
+    }
+
     async fn run(
         &self,
         _setup_dir: &Path,
         _extra_setup_dir: Option<PathBuf>,
         _work: &WorkUnit,
+        _env: &HashMap<String, String>,
         from_agent_to_task_endpoint: String,
         from_task_to_agent_endpoint: String,
     ) -> Result<Box<dyn IWorkerChild>> {
@@ -44,16 +52,23 @@ impl IWorkerRunner for WorkerRunnerDouble {
     }
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct ChildDouble {
     pub id: u64,
     pub exit_status: Option<ExitStatus>,
     pub stderr: String,
     pub stdout: String,
     pub killed: bool,
+    pub stderr_events: Vec<StderrEvent>,
+    pub stdout_lines: Vec<String>,
 }
 
+#[async_trait]
 impl IWorkerChild for ChildDouble {
+    fn pid(&self) -> Option<u32> {
+        u32::try_from(self.id).ok()
+    }
+
     fn try_wait(&mut self) -> Result<Option<Output>> {
         let output = if let Some(exit_status) = self.exit_status {
             Some(Output {
@@ -72,4 +87,21 @@ impl IWorkerChild for ChildDouble {
         self.killed = true;
         Ok(())
     }
+
+    fn stderr_events(&mut self) -> Vec<StderrEvent> {
+        std::mem::take(&mut self.stderr_events)
+    }
+
+    async fn next_stdout_line(&mut self) -> Option<String> {
+        if !self.stdout_lines.is_empty() {
+            return Some(self.stdout_lines.remove(0));
+        }
+
+        if self.exit_status.is_none() {
+            // A running child may print more later.
+            futures::future::pending::<()>().await;
+        }
+
+        None
+    }
 }
@@ -0,0 +1,113 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use anyhow::Result;
+
+/// Breakpoint instruction written by `inject_fault()`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const BREAKPOINT: &[u8] = &[0xcc]; // int3
+
+#[cfg(target_arch = "aarch64")]
+const BREAKPOINT: &[u8] = &0xd420_0000u32.to_le_bytes(); // brk #0
+
+/// Write a breakpoint instruction at `addr` in the memory of a running
+/// process, so that it crashes when it next executes the instruction there.
+///
+/// The process is not being debugged, so the breakpoint is not handled, and
+/// the process is terminated by it.
+#[cfg(target_os = "linux")]
+pub fn inject_fault(pid: u32, addr: u64) -> Result<()> {
+    use std::ffi::c_void;
+
+    use anyhow::Context;
+    use nix::libc::c_long;
+    use nix::sys::ptrace;
+    use nix::sys::wait::waitpid;
+    use nix::unistd::Pid;
+
+    let pid = Pid::from_raw(pid as _);
+    let addr = addr as ptrace::AddressType;
+
+    ptrace::attach(pid).with_context(|| format!("unable to attach to process {pid}"))?;
+
+    let write = || -> Result<()> {
+        // Wait for the process to stop, after the attach.
+        waitpid(pid, None)?;
+
+        let word = ptrace::read(pid, addr)?;
+        let mut bytes = word.to_ne_bytes();
+        bytes[..BREAKPOINT.len()].copy_from_slice(BREAKPOINT);
+        let word = c_long::from_ne_bytes(bytes);
+
+        // `ptrace::write()` takes the word to write as a pointer.
+        unsafe { ptrace::write(pid, addr, word as *mut c_void)? };
+
+        Ok(())
+    };
+    let result = write().with_context(|| format!("unable to write to process {pid} at {addr:?}"));
+
+    // Resume the process even if the write failed.
+    ptrace::detach(pid, None).with_context(|| format!("unable to detach from process {pid}"))?;
+
+    result
+}
+
+#[cfg(target_os = "windows")]
+pub fn inject_fault(pid: u32, addr: u64) -> Result<()> {
+    use winapi::shared::minwindef::{DWORD, FALSE, LPCVOID, LPVOID};
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::memoryapi::{VirtualProtectEx, WriteProcessMemory};
+    use winapi::um::processthreadsapi::{FlushInstructionCache, OpenProcess};
+    use winapi::um::winnt::{PAGE_EXECUTE_READWRITE, PROCESS_VM_OPERATION, PROCESS_VM_WRITE};
+
+    let process = unsafe { OpenProcess(PROCESS_VM_OPERATION | PROCESS_VM_WRITE, FALSE, pid) };
+    if process.is_null() {
+        let code = unsafe { GetLastError() };
+        bail!("unable to open process {}: {:x}", pid, code);
+    }
+
+    let addr = addr as LPVOID;
+    let len = BREAKPOINT.len();
+
+    // Code pages are not writable, so make the page writable for the write,
+    // and restore its protection after.
+    let result = unsafe {
+        let mut protection: DWORD = 0;
+        let mut written = 0;
+
+        if VirtualProtectEx(process, addr, len, PAGE_EXECUTE_READWRITE, &mut protection) == FALSE {
+            Err(GetLastError())
+        } else {
+            let success = WriteProcessMemory(
+                process,
+                addr,
+                BREAKPOINT.as_ptr() as LPCVOID,
+                len,
+                &mut written,
+            );
+            let code = GetLastError();
+
+            VirtualProtectEx(process, addr, len, protection, &mut protection);
+            FlushInstructionCache(process, addr, len);
+
+            if success == FALSE {
+                Err(code)
+            } else {
+                Ok(())
+            }
+        }
+    };
+    unsafe { CloseHandle(process) };
+
+    if let Err(code) = result {
+        bail!(
+            "unable to write to process {} at {:x}: {:x}",
+            pid,
+            addr as usize,
+            code
+        );
+    }
+
+    Ok(())
+}
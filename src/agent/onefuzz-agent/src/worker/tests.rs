@@ -20,9 +20,19 @@ impl Fixture {
             job_id,
             task_id,
             config,
+            tags: self.tags(),
+            expected_files: vec![],
+            max_retries: 0,
+            output_dir: None,
+            stdin_file: None,
+            max_corpus_size: None,
         }
     }
 
+    fn tags(&self) -> HashMap<String, String> {
+        HashMap::from([("commit".to_owned(), "3f8e2a1".to_owned())])
+    }
+
     fn child_running(&self) -> ChildDouble {
         ChildDouble {
             id: 123,
@@ -57,11 +67,16 @@ struct RunnerDouble {
 
 #[async_trait]
 impl IWorkerRunner for RunnerDouble {
+    fn prepare_environment(&self, _work: &WorkUnit) -> Result<HashMap<String, String>> {
+        Ok(HashMap::new())
+    }
+
     async fn run(
         &self,
         _setup_dir: &Path,
         _extra_setup_dir: Option<PathBuf>,
         _work: &WorkUnit,
+        _env: &HashMap<String, String>,
         from_agent_to_task_endpoint: String,
         from_task_to_agent_endpoint: String,
     ) -> Result<Box<dyn IWorkerChild>> {
@@ -93,9 +108,10 @@ async fn test_ready_run() {
     let mut runner = Fixture.runner(Fixture.child_running());
     let state = State {
         ctx: Ready {
-            work_dir: PathBuf::default(),
+            work_dir: PathBuf::from("work-dir"),
             setup_dir: PathBuf::default(),
             extra_setup_dir: None,
+            env: HashMap::new(),
         },
         work: Fixture.work(),
     };
@@ -109,6 +125,82 @@ async fn test_ready_run() {
         .cloned()
         .unwrap();
     assert_eq!(child, Fixture.child_running());
+
+    let worker = Worker::from(state);
+    assert_eq!(worker.working_dir(), Path::new("work-dir"));
+    assert_eq!(worker.pid(), Some(123));
+}
+
+#[test]
+fn test_worker_runner_prepare_environment() {
+    let runner = WorkerRunner::new(MachineIdentity {
+        machine_id: Uuid::new_v4(),
+        machine_name: "test-machine".into(),
+        scaleset_name: None,
+    });
+    let work = Fixture.work();
+
+    let env = runner.prepare_environment(&work).unwrap();
+
+    assert_eq!(env.get("ONEFUZZ_JOB_ID"), Some(&work.job_id.to_string()));
+    assert_eq!(env.get("ONEFUZZ_TASK_ID"), Some(&work.task_id.to_string()));
+}
+
+#[test]
+fn test_attach_debugger_not_running() {
+    let worker = Worker::new(
+        PathBuf::from("work-dir"),
+        PathBuf::default(),
+        None,
+        Fixture.work(),
+    );
+
+    assert!(worker.attach_debugger().is_err());
+}
+
+#[test]
+fn test_inject_fault_not_running() {
+    let worker = Worker::new(
+        PathBuf::from("work-dir"),
+        PathBuf::default(),
+        None,
+        Fixture.work(),
+    );
+
+    let err = worker.inject_fault(0x1000).unwrap_err();
+    assert!(err.to_string().contains("is not running"));
+}
+
+#[tokio::test]
+async fn test_set_env() {
+    let mut worker = Worker::new(
+        PathBuf::from("work-dir"),
+        PathBuf::default(),
+        None,
+        Fixture.work(),
+    );
+
+    worker
+        .set_env("COVERAGE_SOCKET", "/tmp/coverage.sock")
+        .unwrap();
+
+    match &worker {
+        Worker::Ready(state) => assert_eq!(
+            state.ctx.env.get("COVERAGE_SOCKET").map(String::as_str),
+            Some("/tmp/coverage.sock")
+        ),
+        _ => panic!("unexpected worker state: {:?}", worker),
+    }
+
+    let mut runner = Fixture.runner(Fixture.child_running());
+    let mut events = vec![];
+    let mut worker = worker.update(&mut events, &mut runner).await.unwrap();
+    assert!(matches!(worker, Worker::Running(..)));
+
+    let err = worker
+        .set_env("COVERAGE_SOCKET", "/tmp/other.sock")
+        .unwrap_err();
+    assert_eq!(err.to_string(), "cannot set env on running worker");
 }
 
 #[tokio::test]
@@ -118,9 +210,11 @@ async fn test_running_kill() {
     let mut state = State {
         ctx: Running {
             child,
+            work_dir: PathBuf::default(),
             _from_agent_to_task: connections.agent_connections.0,
             from_task_to_agent: connections.agent_connections.1,
             log_uploader: None,
+            pruned_at: None,
         },
         work: Fixture.work(),
     };
@@ -136,6 +230,23 @@ async fn test_running_kill() {
     assert!(child.killed);
 }
 
+#[tokio::test]
+async fn test_stopping_cancel() {
+    let exit_status = Fixture.exit_status_ok();
+    let child = Box::new(Fixture.child_exited(exit_status));
+    let state = State {
+        ctx: Stopping {
+            child,
+            work_dir: PathBuf::default(),
+        },
+        work: Fixture.work(),
+    };
+
+    let done = state.cancel().await.unwrap();
+
+    assert_eq!(done.output().exit_status, exit_status);
+}
+
 #[tokio::test]
 async fn test_running_wait_running() {
     let connections = bootstrap_ipc().await.unwrap();
@@ -143,9 +254,11 @@ async fn test_running_wait_running() {
     let state = State {
         ctx: Running {
             child,
+            work_dir: PathBuf::default(),
             _from_agent_to_task: connections.agent_connections.0,
             from_task_to_agent: connections.agent_connections.1,
             log_uploader: None,
+            pruned_at: None,
         },
         work: Fixture.work(),
     };
@@ -173,9 +286,11 @@ async fn test_running_wait_done() {
     let state = State {
         ctx: Running {
             child,
+            work_dir: PathBuf::default(),
             _from_agent_to_task: connections.agent_connections.0,
             from_task_to_agent: connections.agent_connections.1,
             log_uploader: None,
+            pruned_at: None,
         },
         work: Fixture.work(),
     };
@@ -198,6 +313,7 @@ async fn test_worker_ready_update() {
             work_dir: PathBuf::default(),
             setup_dir: PathBuf::default(),
             extra_setup_dir: None,
+            env: HashMap::new(),
         },
         work: Fixture.work(),
     };
@@ -207,7 +323,32 @@ async fn test_worker_ready_update() {
     let worker = worker.update(&mut events, &mut runner).await.unwrap();
 
     assert!(matches!(worker, Worker::Running(..)));
-    assert_eq!(events, vec![WorkerEvent::Running { task_id }]);
+    assert_eq!(
+        events,
+        vec![WorkerEvent::Running {
+            task_id,
+            tags: Fixture.tags(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_worker_ready_poll() {
+    let state = State {
+        ctx: Ready {
+            work_dir: PathBuf::default(),
+            setup_dir: PathBuf::default(),
+            extra_setup_dir: None,
+            env: HashMap::new(),
+        },
+        work: Fixture.work(),
+    };
+    let worker = Worker::Ready(state);
+    let mut events = vec![];
+    let worker = worker.poll(&mut events).await.unwrap();
+
+    assert!(matches!(worker, Worker::Ready(..)));
+    assert_eq!(events, vec![]);
 }
 
 #[tokio::test]
@@ -218,9 +359,11 @@ async fn test_worker_running_update_running() {
     let state = State {
         ctx: Running {
             child,
+            work_dir: PathBuf::default(),
             _from_agent_to_task: connections.agent_connections.0,
             from_task_to_agent: connections.agent_connections.1,
             log_uploader: None,
+            pruned_at: None,
         },
         work: Fixture.work(),
     };
@@ -241,9 +384,11 @@ async fn test_worker_running_update_done() {
     let state = State {
         ctx: Running {
             child,
+            work_dir: PathBuf::default(),
             _from_agent_to_task: connections.agent_connections.0,
             from_task_to_agent: connections.agent_connections.1,
             log_uploader: None,
+            pruned_at: None,
         },
         work: Fixture.work(),
     };
@@ -261,6 +406,7 @@ async fn test_worker_running_update_done() {
             exit_status,
             stderr: "stderr".into(),
             stdout: "stdout".into(),
+            tags: Fixture.tags(),
         }]
     );
 }
@@ -277,7 +423,11 @@ async fn test_worker_done() {
         stdout: "stdout".into(),
     };
     let state = State {
-        ctx: Done { output },
+        ctx: Done {
+            output,
+            work_dir: PathBuf::default(),
+            stopped: false,
+        },
         work: Fixture.work(),
     };
     let worker = Worker::Done(state);
@@ -289,6 +439,336 @@ async fn test_worker_done() {
     assert_eq!(events, vec![]);
 }
 
+#[test]
+fn test_worker_metric_parse() {
+    assert_eq!(
+        WorkerMetric::parse("METRIC:execs_sec=1234.5,fuzzer=libfuzzer,worker=0\n"),
+        Some(WorkerMetric {
+            name: "execs_sec".into(),
+            value: 1234.5,
+            tags: HashMap::from([
+                ("fuzzer".to_owned(), "libfuzzer".to_owned()),
+                ("worker".to_owned(), "0".to_owned()),
+            ]),
+        })
+    );
+    assert_eq!(
+        WorkerMetric::parse("METRIC:paths=7"),
+        Some(WorkerMetric {
+            name: "paths".into(),
+            value: 7.0,
+            tags: HashMap::new(),
+        })
+    );
+
+    assert_eq!(WorkerMetric::parse("INFO: paths=7"), None);
+    assert_eq!(WorkerMetric::parse("METRIC:paths"), None);
+    assert_eq!(WorkerMetric::parse("METRIC:=7"), None);
+    assert_eq!(WorkerMetric::parse("METRIC:paths=seven"), None);
+    assert_eq!(WorkerMetric::parse("METRIC:paths=7,fuzzer"), None);
+}
+
+#[test]
+fn test_worker_log_parse() {
+    assert_eq!(
+        WorkerLog::parse("[ONEFUZZ:INFO:starting target]\n"),
+        Some(WorkerLog {
+            level: LogLevel::Info,
+            message: "starting target".into(),
+        })
+    );
+    assert_eq!(
+        WorkerLog::parse("[ONEFUZZ:warn:retrying: [attempt 2]]"),
+        Some(WorkerLog {
+            level: LogLevel::Warn,
+            message: "retrying: [attempt 2]".into(),
+        })
+    );
+    assert_eq!(
+        WorkerLog::parse("[ONEFUZZ:ERROR:]"),
+        Some(WorkerLog {
+            level: LogLevel::Error,
+            message: "".into(),
+        })
+    );
+
+    assert_eq!(WorkerLog::parse("INFO: starting target"), None);
+    assert_eq!(WorkerLog::parse("[ONEFUZZ:INFO:starting target"), None);
+    assert_eq!(WorkerLog::parse("[ONEFUZZ:INFO]"), None);
+    assert_eq!(WorkerLog::parse("[ONEFUZZ:VERBOSE:starting target]"), None);
+}
+
+#[tokio::test]
+async fn test_worker_running_poll_logs() {
+    let child = ChildDouble {
+        stderr_events: vec![StderrEvent::Log(WorkerLog {
+            level: LogLevel::Debug,
+            message: "starting target".into(),
+        })],
+        ..Fixture.child_running()
+    };
+    let worker = running_worker(child).await;
+    let mut events = vec![];
+    worker.poll(&mut events).await.unwrap();
+
+    assert_eq!(
+        events,
+        vec![WorkerEvent::Log {
+            task_id: Fixture.work().task_id,
+            level: LogLevel::Debug,
+            message: "starting target".into(),
+            tags: Fixture.tags(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_worker_running_poll_metrics() {
+    let connections = bootstrap_ipc().await.unwrap();
+    let child = Box::new(ChildDouble {
+        stderr_events: vec![StderrEvent::Metric(WorkerMetric {
+            name: "execs_sec".into(),
+            value: 100.0,
+            tags: HashMap::from([("worker".to_owned(), "0".to_owned())]),
+        })],
+        ..Fixture.child_running()
+    });
+    let state = State {
+        ctx: Running {
+            child,
+            work_dir: PathBuf::default(),
+            _from_agent_to_task: connections.agent_connections.0,
+            from_task_to_agent: connections.agent_connections.1,
+            log_uploader: None,
+            pruned_at: None,
+        },
+        work: Fixture.work(),
+    };
+    let worker = Worker::Running(state);
+    let mut events = vec![];
+    let worker = worker.poll(&mut events).await.unwrap();
+
+    let mut tags = Fixture.tags();
+    tags.insert("worker".to_owned(), "0".to_owned());
+    assert_eq!(
+        events,
+        vec![WorkerEvent::Metric {
+            task_id: Fixture.work().task_id,
+            name: "execs_sec".into(),
+            value: 100.0,
+            tags,
+        }]
+    );
+
+    // Metrics are only reported once.
+    let mut events = vec![];
+    worker.poll(&mut events).await.unwrap();
+    assert_eq!(events, vec![]);
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn test_redirected_child_metrics() {
+    use std::process::Command;
+
+    let script = "import sys;\
+sys.stderr.write('starting\\nMETRIC:execs_sec=');\
+sys.stderr.flush();\
+sys.stderr.write('42\\nMETRIC:bad\\n')";
+
+    let mut cmd = Command::new("python3");
+    cmd.args(["-c", script]);
+
+    let mut redirected = RedirectedChild::spawn(cmd).unwrap();
+    redirected.child.wait().unwrap();
+    redirected.streams.take().unwrap().join().unwrap();
+
+    assert_eq!(
+        redirected.stderr_events(),
+        vec![StderrEvent::Metric(WorkerMetric {
+            name: "execs_sec".into(),
+            value: 42.0,
+            tags: HashMap::new(),
+        })]
+    );
+}
+
+#[cfg(target_family = "unix")]
+#[tokio::test]
+async fn test_redirected_child_stdout_lines() {
+    use std::process::Command;
+
+    // Lines are only kept once something waits for them, so give the test
+    // time to start waiting.
+    let mut cmd = Command::new("python3");
+    cmd.args([
+        "-c",
+        "import time; time.sleep(1); print('starting'); print('ready', end='')",
+    ]);
+
+    let mut redirected = RedirectedChild::spawn(cmd).unwrap();
+
+    assert_eq!(redirected.next_stdout_line().await, Some("starting".into()));
+    // The unterminated last line is not a line yet.
+    assert_eq!(redirected.next_stdout_line().await, None);
+}
+
+#[tokio::test]
+async fn test_worker_running_poll_stats() {
+    let connections = bootstrap_ipc().await.unwrap();
+    let stats = LibFuzzerStats {
+        iterations: 4096,
+        coverage: 11,
+        features: 12,
+        corpus_size: 6,
+        exec_per_sec: 2048.0,
+        rss_mb: 30,
+    };
+    let child = Box::new(ChildDouble {
+        stderr_events: vec![StderrEvent::Stats(stats.clone())],
+        ..Fixture.child_running()
+    });
+    let state = State {
+        ctx: Running {
+            child,
+            work_dir: PathBuf::default(),
+            _from_agent_to_task: connections.agent_connections.0,
+            from_task_to_agent: connections.agent_connections.1,
+            log_uploader: None,
+            pruned_at: None,
+        },
+        work: Fixture.work(),
+    };
+    let worker = Worker::Running(state);
+    let mut events = vec![];
+    worker.poll(&mut events).await.unwrap();
+
+    assert_eq!(
+        events,
+        vec![WorkerEvent::Stats {
+            task_id: Fixture.work().task_id,
+            stats,
+            tags: Fixture.tags(),
+        }]
+    );
+}
+
+async fn running_worker(child: ChildDouble) -> Worker {
+    let connections = bootstrap_ipc().await.unwrap();
+    let state = State {
+        ctx: Running {
+            child: Box::new(child),
+            work_dir: PathBuf::default(),
+            _from_agent_to_task: connections.agent_connections.0,
+            from_task_to_agent: connections.agent_connections.1,
+            log_uploader: None,
+            pruned_at: None,
+        },
+        work: Fixture.work(),
+    };
+    Worker::Running(state)
+}
+
+#[tokio::test]
+async fn test_worker_wait_for_output() {
+    let mut worker = running_worker(ChildDouble {
+        stdout_lines: vec!["starting".into(), "INFO: listening on port 4141".into()],
+        ..Fixture.child_running()
+    })
+    .await;
+
+    let line = worker
+        .wait_for_output("listening", Duration::from_secs(1))
+        .await
+        .unwrap();
+    assert_eq!(line, "INFO: listening on port 4141");
+}
+
+#[tokio::test]
+async fn test_worker_wait_for_output_matching() {
+    let mut worker = running_worker(ChildDouble {
+        stdout_lines: vec!["port 0".into(), "listening on port 4141".into()],
+        ..Fixture.child_running()
+    })
+    .await;
+
+    let pattern = Regex::new(r"port [1-9]\d*").unwrap();
+    let line = worker
+        .wait_for_output_matching(&pattern, Duration::from_secs(1))
+        .await
+        .unwrap();
+    assert_eq!(line, "listening on port 4141");
+}
+
+#[tokio::test]
+async fn test_worker_wait_for_output_timeout() {
+    let mut worker = running_worker(ChildDouble {
+        stdout_lines: vec!["starting".into()],
+        ..Fixture.child_running()
+    })
+    .await;
+
+    let err = worker
+        .wait_for_output("listening", Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("timed out"));
+}
+
+#[tokio::test]
+async fn test_worker_wait_for_output_exited() {
+    let exit_status = Fixture.exit_status_ok();
+    let mut worker = running_worker(Fixture.child_exited(exit_status)).await;
+
+    let err = worker
+        .wait_for_output("listening", Duration::from_secs(1))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("exited before printing"));
+}
+
+#[tokio::test]
+async fn test_worker_wait_for_output_not_running() {
+    let mut worker = Worker::new(PathBuf::default(), PathBuf::default(), None, Fixture.work());
+
+    let err = worker
+        .wait_for_output("listening", Duration::from_secs(1))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("is not running"));
+}
+
+#[test]
+fn test_uses_input_argument() {
+    let config = |json: &'static str| -> HashMap<&'static str, Value> {
+        serde_json::from_str(json).unwrap()
+    };
+
+    assert!(uses_input_argument(&config(
+        r#"{"target_options": ["-runs=1", "{input}"]}"#
+    )));
+    assert!(uses_input_argument(&config(
+        r#"{"target_options": ["--file={input}"]}"#
+    )));
+    assert!(!uses_input_argument(&config(
+        r#"{"target_options": ["-runs=1"]}"#
+    )));
+    assert!(!uses_input_argument(&config(r#"{"task_id": "x"}"#)));
+}
+
+#[test]
+fn test_stderr_event_parse() {
+    assert!(matches!(
+        StderrEvent::parse("METRIC:paths=7"),
+        Some(StderrEvent::Metric(_))
+    ));
+    assert!(matches!(
+        StderrEvent::parse("#4096\tpulse  cov: 11 ft: 12 corp: 6/21b exec/s: 2048 rss: 30Mb"),
+        Some(StderrEvent::Stats(_))
+    ));
+    assert_eq!(StderrEvent::parse("INFO: Seed: 1"), None);
+}
+
 #[cfg(target_family = "unix")]
 #[test]
 fn test_redirected_child() {
@@ -397,3 +877,60 @@ struct IpcConnections {
     pub agent_connections: (IpcSender<IpcMessageKind>, IpcReceiver<IpcMessageKind>),
     pub _task_connections: (IpcSender<IpcMessageKind>, IpcReceiver<IpcMessageKind>),
 }
+
+#[tokio::test]
+async fn test_worker_running_poll_prunes_corpus() {
+    let work_dir = std::env::temp_dir().join(format!("worker-{}", Uuid::new_v4()));
+    let inputs = work_dir.join("inputs");
+    std::fs::create_dir_all(&inputs).unwrap();
+    for name in ["a", "b", "c"] {
+        std::fs::write(inputs.join(name), name).unwrap();
+    }
+
+    let config = serde_json::json!({
+        "task_id": "ed1eeec9-2f39-442d-9e70-563454b866c0",
+        "instance_id": "5220ff9b-2ab2-4cf8-b9ad-b948c3b94f08",
+        "inputs": { "path": "inputs", "url": null },
+    });
+    let work = WorkUnit {
+        config: config.to_string().into(),
+        max_corpus_size: Some(2),
+        ..Fixture.work()
+    };
+
+    let connections = bootstrap_ipc().await.unwrap();
+    let state = State {
+        ctx: Running {
+            child: Box::new(Fixture.child_running()),
+            work_dir: work_dir.clone(),
+            _from_agent_to_task: connections.agent_connections.0,
+            from_task_to_agent: connections.agent_connections.1,
+            log_uploader: None,
+            pruned_at: None,
+        },
+        work,
+    };
+    let worker = Worker::Running(state);
+    let mut events = vec![];
+    let worker = worker.poll(&mut events).await.unwrap();
+
+    assert_eq!(
+        events,
+        vec![WorkerEvent::CorpusPruned {
+            task_id: Fixture.work().task_id,
+            removed: 1,
+            kept: 2,
+            tags: Fixture.tags(),
+        }]
+    );
+    assert_eq!(std::fs::read_dir(&inputs).unwrap().count(), 2);
+
+    // The corpus is not pruned again until the interval has passed.
+    std::fs::write(inputs.join("d"), "d").unwrap();
+    let mut events = vec![];
+    worker.poll(&mut events).await.unwrap();
+    assert_eq!(events, vec![]);
+    assert_eq!(std::fs::read_dir(&inputs).unwrap().count(), 3);
+
+    let _ = std::fs::remove_dir_all(&work_dir);
+}
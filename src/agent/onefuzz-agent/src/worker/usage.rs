@@ -0,0 +1,251 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use anyhow::Result;
+
+/// CPU and memory usage of a worker's child process.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct ResourceUsage {
+    /// Average CPU usage since the process started, as a percentage of one
+    /// core. May exceed 100 for multi-threaded processes.
+    pub cpu_percent: f64,
+
+    /// Resident set size (working set, on Windows).
+    pub rss_bytes: u64,
+
+    /// Virtual memory size (committed memory, on Windows).
+    pub vsz_bytes: u64,
+}
+
+#[cfg(target_os = "linux")]
+pub fn resource_usage(pid: u32) -> Result<ResourceUsage> {
+    use anyhow::Context;
+    use nix::unistd::{sysconf, SysconfVar};
+
+    const BYTES_PER_KB: u64 = 1024;
+
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .with_context(|| format!("unable to read stat of process {pid}"))?;
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status"))
+        .with_context(|| format!("unable to read status of process {pid}"))?;
+    let uptime = std::fs::read_to_string("/proc/uptime").context("unable to read uptime")?;
+
+    let stat = parse_stat(&stat)?;
+    let rss_kb = parse_vm_rss_kb(&status)?;
+    let uptime_secs: f64 = uptime
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| format_err!("empty /proc/uptime"))?
+        .parse()?;
+
+    let ticks_per_sec = sysconf(SysconfVar::CLK_TCK)?
+        .ok_or_else(|| format_err!("clock ticks per second unavailable"))?
+        as f64;
+
+    let cpu_secs = (stat.utime + stat.stime) as f64 / ticks_per_sec;
+    let elapsed_secs = uptime_secs - stat.starttime as f64 / ticks_per_sec;
+
+    Ok(ResourceUsage {
+        cpu_percent: cpu_percent(cpu_secs, elapsed_secs),
+        rss_bytes: rss_kb * BYTES_PER_KB,
+        vsz_bytes: stat.vsize,
+    })
+}
+
+#[cfg(target_os = "windows")]
+pub fn resource_usage(pid: u32) -> Result<ResourceUsage> {
+    use std::convert::TryFrom;
+
+    use winapi::shared::minwindef::{FALSE, FILETIME};
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::processthreadsapi::{GetProcessTimes, OpenProcess};
+    use winapi::um::psapi::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use winapi::um::sysinfoapi::GetSystemTimeAsFileTime;
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    // FILETIME values are in 100ns intervals.
+    fn secs(time: FILETIME) -> f64 {
+        let intervals = (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime);
+        intervals as f64 / 10_000_000.0
+    }
+
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid) };
+    if process.is_null() {
+        let code = unsafe { GetLastError() };
+        bail!("unable to open process {}: {:x}", pid, code);
+    }
+
+    let mut creation = FILETIME::default();
+    let mut exit = FILETIME::default();
+    let mut kernel = FILETIME::default();
+    let mut user = FILETIME::default();
+    let mut now = FILETIME::default();
+    let mut counters = PROCESS_MEMORY_COUNTERS::default();
+
+    let (times_success, memory_success) = unsafe {
+        let times_success =
+            GetProcessTimes(process, &mut creation, &mut exit, &mut kernel, &mut user);
+        GetSystemTimeAsFileTime(&mut now);
+
+        // Will always fit in a `u32`.
+        let size = u32::try_from(std::mem::size_of::<PROCESS_MEMORY_COUNTERS>())?;
+        let memory_success = GetProcessMemoryInfo(process, &mut counters, size);
+
+        (times_success, memory_success)
+    };
+    let code = unsafe { GetLastError() };
+    unsafe { CloseHandle(process) };
+
+    if times_success == FALSE || memory_success == FALSE {
+        bail!("error querying usage of process {}: {:x}", pid, code);
+    }
+
+    Ok(ResourceUsage {
+        cpu_percent: cpu_percent(secs(kernel) + secs(user), secs(now) - secs(creation)),
+        rss_bytes: u64::try_from(counters.WorkingSetSize)?,
+        vsz_bytes: u64::try_from(counters.PagefileUsage)?,
+    })
+}
+
+/// Kernel function a process is sleeping in, or `None` if it is running.
+///
+/// A process that stays in the same wait channel for a long time is likely
+/// blocked on I/O.
+#[cfg(target_os = "linux")]
+pub fn wait_channel(pid: u32) -> Result<Option<String>> {
+    use anyhow::Context;
+
+    let wchan = std::fs::read_to_string(format!("/proc/{pid}/wchan"))
+        .with_context(|| format!("unable to read wchan of process {pid}"))?;
+
+    Ok(parse_wchan(&wchan))
+}
+
+#[cfg(target_os = "windows")]
+pub fn wait_channel(pid: u32) -> Result<Option<String>> {
+    // Wait reasons are per-thread on Windows, and aren't exposed by any API
+    // the agent uses.
+    bail!("wait channel of process {} unavailable on Windows", pid)
+}
+
+fn cpu_percent(cpu_secs: f64, elapsed_secs: f64) -> f64 {
+    if elapsed_secs > 0.0 {
+        100.0 * cpu_secs / elapsed_secs
+    } else {
+        0.0
+    }
+}
+
+/// Fields of `/proc/<pid>/stat` used to compute usage.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Eq, PartialEq)]
+struct ProcStat {
+    /// User mode time, in clock ticks.
+    utime: u64,
+
+    /// Kernel mode time, in clock ticks.
+    stime: u64,
+
+    /// Time the process started after boot, in clock ticks.
+    starttime: u64,
+
+    /// Virtual memory size, in bytes.
+    vsize: u64,
+}
+
+#[cfg(target_os = "linux")]
+fn parse_stat(stat: &str) -> Result<ProcStat> {
+    // The command name is parenthesized and may contain spaces, so only split
+    // the fields after it. The first of these is field 3, the process state.
+    let (_, fields) = stat
+        .rsplit_once(')')
+        .ok_or_else(|| format_err!("malformed /proc/<pid>/stat: {}", stat))?;
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+
+    let field = |number: usize| -> Result<u64> {
+        let value = fields
+            .get(number - 3)
+            .ok_or_else(|| format_err!("missing field {} in /proc/<pid>/stat", number))?;
+        Ok(value.parse()?)
+    };
+
+    Ok(ProcStat {
+        utime: field(14)?,
+        stime: field(15)?,
+        starttime: field(22)?,
+        vsize: field(23)?,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn parse_vm_rss_kb(status: &str) -> Result<u64> {
+    let line = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .ok_or_else(|| format_err!("`VmRSS` not found in /proc/<pid>/status"))?;
+
+    let kb = line.trim().trim_end_matches("kB").trim().parse()?;
+
+    Ok(kb)
+}
+
+// `0` means the process is running, or that the kernel hides symbol names.
+#[cfg(target_os = "linux")]
+fn parse_wchan(wchan: &str) -> Option<String> {
+    match wchan.trim() {
+        "" | "0" => None,
+        wchan => Some(wchan.to_owned()),
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stat() -> Result<()> {
+        let stat = "1234 (my (fuzz) target) S 1 1234 1234 0 -1 4194560 1500 0 0 0 \
+                    250 50 0 0 20 0 4 0 10000 123456789 2048 18446744073709551615";
+
+        let stat = parse_stat(stat)?;
+        assert_eq!(
+            stat,
+            ProcStat {
+                utime: 250,
+                stime: 50,
+                starttime: 10000,
+                vsize: 123456789,
+            }
+        );
+
+        assert!(parse_stat("1234 (truncated) S 1").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_vm_rss_kb() -> Result<()> {
+        let status = "Name:\tfuzz\nVmPeak:\t  10000 kB\nVmRSS:\t    5120 kB\nThreads:\t4\n";
+        assert_eq!(parse_vm_rss_kb(status)?, 5120);
+
+        assert!(parse_vm_rss_kb("Name:\tkthreadd\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_wchan() {
+        assert_eq!(parse_wchan("pipe_read"), Some("pipe_read".to_owned()));
+        assert_eq!(parse_wchan("0"), None);
+        assert_eq!(parse_wchan(""), None);
+    }
+
+    #[test]
+    fn test_resource_usage_self() -> Result<()> {
+        let usage = resource_usage(std::process::id())?;
+        assert!(usage.rss_bytes > 0);
+        assert!(usage.vsz_bytes >= usage.rss_bytes);
+        assert!(usage.cpu_percent >= 0.0);
+        Ok(())
+    }
+}
@@ -4,9 +4,11 @@
 #[cfg(any(target_os = "linux", target_os = "windows"))]
 use crate::local::coverage;
 use crate::local::{
-    common::add_common_config, generic_analysis, generic_crash_report, generic_generator,
-    libfuzzer, libfuzzer_crash_report, libfuzzer_fuzz, libfuzzer_merge, libfuzzer_regression,
-    libfuzzer_test_input, radamsa, test_input, tui::TerminalUi,
+    common::add_common_config, coverage_diff, fuzz_with_grammar, generic_analysis,
+    generic_crash_report, generic_generator, libfuzzer, libfuzzer_coverage, libfuzzer_crash_report,
+    libfuzzer_fuzz, libfuzzer_merge, libfuzzer_regression, libfuzzer_test_input, minimize, radamsa,
+    reproduce_all, sanitizer_coverage, symbolize_stack, test_input, tui::TerminalUi, unique_inputs,
+    verify_setup,
 };
 use anyhow::{Context, Result};
 use clap::{Arg, ArgAction, Command};
@@ -27,11 +29,20 @@ enum Commands {
     LibfuzzerCrashReport,
     LibfuzzerTestInput,
     LibfuzzerRegression,
+    LibfuzzerCoverage,
     Libfuzzer,
     CrashReport,
     Generator,
     Analysis,
     TestInput,
+    SanitizerCoverage,
+    UniqueInputs,
+    VerifySetup,
+    Minimize,
+    CoverageDiff,
+    SymbolizeStack,
+    FuzzWithGrammar,
+    ReproduceAll,
 }
 
 const TIMEOUT: &str = "timeout";
@@ -77,11 +88,20 @@ pub async fn run(args: clap::ArgMatches) -> Result<()> {
             Commands::LibfuzzerRegression => {
                 libfuzzer_regression::run(&sub_args, event_sender).await
             }
+            Commands::LibfuzzerCoverage => libfuzzer_coverage::run(&sub_args, event_sender).await,
             Commands::Libfuzzer => libfuzzer::run(&sub_args, event_sender).await,
             Commands::CrashReport => generic_crash_report::run(&sub_args, event_sender).await,
             Commands::Generator => generic_generator::run(&sub_args, event_sender).await,
             Commands::Analysis => generic_analysis::run(&sub_args, event_sender).await,
             Commands::TestInput => test_input::run(&sub_args, event_sender).await,
+            Commands::SanitizerCoverage => sanitizer_coverage::run(&sub_args, event_sender).await,
+            Commands::UniqueInputs => unique_inputs::run(&sub_args, event_sender).await,
+            Commands::VerifySetup => verify_setup::run(&sub_args, event_sender).await,
+            Commands::Minimize => minimize::run(&sub_args, event_sender).await,
+            Commands::CoverageDiff => coverage_diff::run(&sub_args, event_sender).await,
+            Commands::SymbolizeStack => symbolize_stack::run(&sub_args, event_sender).await,
+            Commands::FuzzWithGrammar => fuzz_with_grammar::run(&sub_args, event_sender).await,
+            Commands::ReproduceAll => reproduce_all::run(&sub_args, event_sender).await,
         }
     });
 
@@ -135,11 +155,20 @@ pub fn args(name: &'static str) -> Command {
             Commands::LibfuzzerMerge => libfuzzer_merge::args(subcommand.into()),
             Commands::LibfuzzerTestInput => libfuzzer_test_input::args(subcommand.into()),
             Commands::LibfuzzerRegression => libfuzzer_regression::args(subcommand.into()),
+            Commands::LibfuzzerCoverage => libfuzzer_coverage::args(subcommand.into()),
             Commands::Libfuzzer => libfuzzer::args(subcommand.into()),
             Commands::CrashReport => generic_crash_report::args(subcommand.into()),
             Commands::Generator => generic_generator::args(subcommand.into()),
             Commands::Analysis => generic_analysis::args(subcommand.into()),
             Commands::TestInput => test_input::args(subcommand.into()),
+            Commands::SanitizerCoverage => sanitizer_coverage::args(subcommand.into()),
+            Commands::UniqueInputs => unique_inputs::args(subcommand.into()),
+            Commands::VerifySetup => verify_setup::args(subcommand.into()),
+            Commands::Minimize => minimize::args(subcommand.into()),
+            Commands::CoverageDiff => coverage_diff::args(subcommand.into()),
+            Commands::SymbolizeStack => symbolize_stack::args(subcommand.into()),
+            Commands::FuzzWithGrammar => fuzz_with_grammar::args(subcommand.into()),
+            Commands::ReproduceAll => reproduce_all::args(subcommand.into()),
         };
         cmd = cmd.subcommand(add_common_config(app));
     }
@@ -0,0 +1,153 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::local::common::UiEvent;
+use anyhow::{Context, Result};
+use clap::{Arg, Command};
+use flume::Sender;
+use srcview::{FunctionCoverage, ModOff, PdbCache, Report, SrcLine, SrcView};
+use std::{
+    collections::BTreeSet,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+const BEFORE: &str = "before";
+const AFTER: &str = "after";
+const PDB: &str = "pdb";
+const MODULE_NAME: &str = "module_name";
+const OUTPUT: &str = "output";
+
+pub async fn run(args: &clap::ArgMatches, _event_sender: Option<Sender<UiEvent>>) -> Result<()> {
+    let before = args.get_one::<PathBuf>(BEFORE).expect("is marked required");
+    let after = args.get_one::<PathBuf>(AFTER).expect("is marked required");
+    let pdb = args.get_one::<PathBuf>(PDB).expect("is marked required");
+    let module_name = args.get_one::<String>(MODULE_NAME);
+    let output = args.get_one::<PathBuf>(OUTPUT).expect("has default value");
+
+    let srcview = load_srcview(pdb, module_name.map(String::as_str))?;
+
+    let before = read_coverage(&srcview, before).await?;
+    let after = read_coverage(&srcview, after).await?;
+
+    let mut diff =
+        Report::new(&after, &srcview, None)?.subtract(&Report::new(&before, &srcview, None)?);
+    diff.filter_zero_coverage();
+
+    let file = std::fs::File::create(output)
+        .with_context(|| format!("unable to create output: {}", output.display()))?;
+    let mut writer = BufWriter::new(file);
+    diff.cobertura(None, None, &mut writer)?;
+    writer.flush()?;
+    info!("wrote cobertura report: {}", output.display());
+
+    let new_lines: Vec<SrcLine> = diff.covered_lines().collect();
+    println!("{} newly covered lines", new_lines.len());
+
+    let new_functions = newly_covered_functions(
+        srcview.function_coverage_summary(&before),
+        srcview.function_coverage_summary(&after),
+    );
+    println!("{} newly covered functions", new_functions.len());
+    for function in new_functions {
+        println!(
+            "  {} ({}): {}/{} lines",
+            function.name,
+            function.file.display(),
+            function.covered_lines,
+            function.total_lines
+        );
+    }
+
+    Ok(())
+}
+
+// The modoff module names aren't known in advance, so unless a module name is given, map
+// the pdb to its stem with common module extensions, as `srcview` does.
+pub(crate) fn load_srcview(pdb: &Path, module_name: Option<&str>) -> Result<SrcView> {
+    let cache =
+        PdbCache::new(pdb).with_context(|| format!("unable to load pdb: {}", pdb.display()))?;
+
+    let mut srcview = SrcView::new();
+    match module_name {
+        Some(module_name) => {
+            srcview.insert_cache(module_name, cache);
+        }
+        None => {
+            let stem = pdb
+                .file_stem()
+                .ok_or_else(|| format_err!("unable to identify pdb stem: {}", pdb.display()))?
+                .to_string_lossy();
+
+            for ext in ["sys", "exe", "dll"] {
+                srcview.insert_cache(&format!("{stem}.{ext}"), cache.clone());
+            }
+            srcview.insert_cache(&stem, cache);
+        }
+    }
+
+    Ok(srcview)
+}
+
+async fn read_coverage(srcview: &SrcView, modoff_path: &Path) -> Result<Vec<SrcLine>> {
+    let modoff_data = tokio::fs::read_to_string(modoff_path)
+        .await
+        .with_context(|| format!("unable to read modoff file: {}", modoff_path.display()))?;
+    let modoffs = ModOff::parse(&modoff_data)
+        .with_context(|| format!("invalid modoff file: {}", modoff_path.display()))?;
+
+    Ok(modoffs.iter().filter_map(|m| srcview.modoff(m)).collect())
+}
+
+// Functions without any covered lines before, but with some after.
+fn newly_covered_functions(
+    before: Vec<FunctionCoverage>,
+    after: Vec<FunctionCoverage>,
+) -> Vec<FunctionCoverage> {
+    let covered_before: BTreeSet<(PathBuf, String)> = before
+        .into_iter()
+        .filter(|function| function.covered_lines > 0)
+        .map(|function| (function.file, function.name))
+        .collect();
+
+    after
+        .into_iter()
+        .filter(|function| {
+            function.covered_lines > 0
+                && !covered_before.contains(&(function.file.clone(), function.name.clone()))
+        })
+        .collect()
+}
+
+pub fn build_shared_args() -> Vec<Arg> {
+    vec![
+        Arg::new(BEFORE)
+            .long(BEFORE)
+            .required(true)
+            .value_parser(value_parser!(PathBuf))
+            .help("Modoff coverage of the earlier corpus"),
+        Arg::new(AFTER)
+            .long(AFTER)
+            .required(true)
+            .value_parser(value_parser!(PathBuf))
+            .help("Modoff coverage of the later corpus"),
+        Arg::new(PDB)
+            .long(PDB)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new(MODULE_NAME).long(MODULE_NAME).help(
+            "Module name of the PDB in the modoff files, guessed from the PDB name if not set",
+        ),
+        Arg::new(OUTPUT)
+            .long(OUTPUT)
+            .default_value("coverage-diff.xml")
+            .value_parser(value_parser!(PathBuf))
+            .help("File to write the Cobertura XML report of the newly covered lines to"),
+    ]
+}
+
+pub fn args(name: &'static str) -> Command {
+    Command::new(name)
+        .about("report the source lines and functions covered by one modoff file but not another")
+        .args(&build_shared_args())
+}
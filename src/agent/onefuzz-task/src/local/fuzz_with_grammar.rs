@@ -0,0 +1,359 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::{
+    local::common::{
+        build_local_context, get_cmd_arg, get_cmd_env, CmdType, UiEvent, CHECK_ASAN_LOG,
+        CHECK_RETRY_COUNT, CRASHES_DIR, DISABLE_CHECK_DEBUGGER, TARGET_ENV, TARGET_EXE,
+        TARGET_OPTIONS, TARGET_TIMEOUT,
+    },
+    tasks::report::{
+        crash_report::CrashTestResult,
+        generic::{test_input, TestInputArgs},
+    },
+};
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, Command};
+use flume::Sender;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Deserialize;
+use std::{collections::HashMap, io::Write, path::PathBuf};
+use tempfile::NamedTempFile;
+
+const GRAMMAR_FILE: &str = "grammar_file";
+const ITERATIONS: &str = "iterations";
+const SEED: &str = "seed";
+const MAX_DEPTH: &str = "max_depth";
+
+// Symbols expanded for a single input before the grammar is assumed not to
+// terminate.
+const MAX_EXPANSIONS: usize = 100_000;
+
+/// A source of inputs for `fuzz-with-grammar`.
+pub trait IGrammarFuzzer {
+    fn generate(&mut self) -> Result<Vec<u8>>;
+}
+
+/// A context-free grammar, as JSON.
+///
+/// Each rule maps a nonterminal to its alternatives, and each alternative is a
+/// list of symbols. Symbols that name a rule are expanded, any other symbol is
+/// literal text. For example:
+///
+/// ```json
+/// {
+///     "start": "<expr>",
+///     "rules": {
+///         "<expr>": [["<digit>"], ["(", "<expr>", "+", "<expr>", ")"]],
+///         "<digit>": [["0"], ["1"]]
+///     }
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct Grammar {
+    #[serde(default = "default_start")]
+    start: String,
+    rules: HashMap<String, Vec<Vec<String>>>,
+}
+
+fn default_start() -> String {
+    "<start>".to_owned()
+}
+
+impl Grammar {
+    pub fn parse(data: &str) -> Result<Self> {
+        let grammar: Self = serde_json::from_str(data)?;
+
+        if !grammar.rules.contains_key(&grammar.start) {
+            bail!("grammar has no rule for start symbol: {}", grammar.start);
+        }
+
+        for (name, alternatives) in &grammar.rules {
+            if alternatives.is_empty() {
+                bail!("grammar rule has no alternatives: {}", name);
+            }
+        }
+
+        Ok(grammar)
+    }
+
+    fn is_nonterminal(&self, symbol: &str) -> bool {
+        self.rules.contains_key(symbol)
+    }
+}
+
+/// Generates inputs by randomly expanding a [`Grammar`].
+///
+/// Below `max_depth`, each alternative of a rule is equally likely. Past it, the
+/// alternative with the fewest nonterminals is chosen, so that expansion ends.
+pub struct GrammarFuzzer {
+    grammar: Grammar,
+    max_depth: usize,
+    rng: StdRng,
+}
+
+impl GrammarFuzzer {
+    pub fn new(grammar: Grammar, max_depth: usize, seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        Self {
+            grammar,
+            max_depth,
+            rng,
+        }
+    }
+}
+
+impl IGrammarFuzzer for GrammarFuzzer {
+    fn generate(&mut self) -> Result<Vec<u8>> {
+        let Self {
+            grammar,
+            max_depth,
+            rng,
+        } = self;
+
+        let mut output = vec![];
+        let mut expansions = 0;
+
+        // Symbols still to expand, with their depth, last one first.
+        let mut stack = vec![(grammar.start.as_str(), 0)];
+        while let Some((symbol, depth)) = stack.pop() {
+            let alternatives = match grammar.rules.get(symbol) {
+                Some(alternatives) => alternatives,
+                None => {
+                    output.extend_from_slice(symbol.as_bytes());
+                    continue;
+                }
+            };
+
+            expansions += 1;
+            if expansions > MAX_EXPANSIONS {
+                bail!(
+                    "grammar did not terminate after {} expansions",
+                    MAX_EXPANSIONS
+                );
+            }
+
+            let symbols = if depth < *max_depth {
+                &alternatives[rng.gen_range(0..alternatives.len())]
+            } else {
+                alternatives
+                    .iter()
+                    .min_by_key(|symbols| {
+                        symbols.iter().filter(|s| grammar.is_nonterminal(s)).count()
+                    })
+                    .expect("rules have at least one alternative")
+            };
+            stack.extend(symbols.iter().rev().map(|s| (s.as_str(), depth + 1)));
+        }
+
+        Ok(output)
+    }
+}
+
+pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>) -> Result<()> {
+    let context = build_local_context(args, false, event_sender).await?;
+
+    let target_exe = args
+        .get_one::<PathBuf>(TARGET_EXE)
+        .expect("is marked required");
+    let target_env = get_cmd_env(CmdType::Target, args)?;
+    let target_options = get_cmd_arg(CmdType::Target, args);
+    let target_timeout = args.get_one::<u64>(TARGET_TIMEOUT).copied();
+    let check_retry_count = args
+        .get_one::<u64>(CHECK_RETRY_COUNT)
+        .copied()
+        .expect("has default value");
+    let check_asan_log = args.get_flag(CHECK_ASAN_LOG);
+    let check_debugger = !args.get_flag(DISABLE_CHECK_DEBUGGER);
+    let crashes_dir = args.get_one::<PathBuf>(CRASHES_DIR);
+    let iterations = args.get_one::<u64>(ITERATIONS).copied();
+
+    let grammar_file = args
+        .get_one::<PathBuf>(GRAMMAR_FILE)
+        .expect("is marked required");
+    let grammar_data = tokio::fs::read_to_string(grammar_file)
+        .await
+        .with_context(|| format!("unable to read grammar file: {}", grammar_file.display()))?;
+    let grammar = Grammar::parse(&grammar_data)
+        .with_context(|| format!("invalid grammar file: {}", grammar_file.display()))?;
+    let max_depth = args
+        .get_one::<usize>(MAX_DEPTH)
+        .copied()
+        .expect("has default value");
+    let seed = args.get_one::<u64>(SEED).copied();
+
+    let mut fuzzer: Box<dyn IGrammarFuzzer + Send> =
+        Box::new(GrammarFuzzer::new(grammar, max_depth, seed));
+
+    if let Some(crashes_dir) = crashes_dir {
+        tokio::fs::create_dir_all(crashes_dir)
+            .await
+            .with_context(|| format!("unable to create crashes dir: {}", crashes_dir.display()))?;
+    }
+
+    let mut crashes = 0;
+    let mut count = 0;
+    while iterations.map_or(true, |iterations| count < iterations) {
+        count += 1;
+
+        let data = fuzzer.generate()?;
+
+        // Keep the temporary file alive until the test is done, it is deleted on drop.
+        let mut input = NamedTempFile::new().context("unable to create temporary input file")?;
+        input
+            .write_all(&data)
+            .and_then(|_| input.flush())
+            .context("unable to write generated input")?;
+
+        let config = TestInputArgs {
+            target_exe: target_exe.as_path(),
+            target_env: &target_env,
+            target_options: &target_options,
+            input_url: None,
+            input: input.path(),
+            job_id: context.common_config.job_id,
+            task_id: context.common_config.task_id,
+            target_timeout,
+            check_retry_count,
+            setup_dir: &context.common_config.setup_dir,
+            extra_setup_dir: context.common_config.extra_setup_dir.as_deref(),
+            minimized_stack_depth: None,
+            check_asan_log,
+            check_debugger,
+            machine_identity: context.common_config.machine_identity.clone(),
+        };
+
+        let report = match test_input(config).await? {
+            CrashTestResult::CrashReport(report) => report,
+            CrashTestResult::NoRepro(_) => continue,
+        };
+        crashes += 1;
+
+        if let Some(crashes_dir) = crashes_dir {
+            let path = crashes_dir.join(&report.input_sha256);
+            tokio::fs::write(&path, &data)
+                .await
+                .with_context(|| format!("unable to save crashing input: {}", path.display()))?;
+            info!("saved crashing input: {}", path.display());
+        }
+
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+
+    info!(
+        "tested {} generated inputs, found {} crashes",
+        count, crashes
+    );
+    Ok(())
+}
+
+pub fn build_shared_args() -> Vec<Arg> {
+    vec![
+        Arg::new(TARGET_EXE)
+            .long(TARGET_EXE)
+            .value_parser(value_parser!(PathBuf))
+            .required(true),
+        Arg::new(GRAMMAR_FILE)
+            .long(GRAMMAR_FILE)
+            .value_parser(value_parser!(PathBuf))
+            .required(true)
+            .help("JSON grammar to generate inputs from"),
+        Arg::new(TARGET_ENV).long(TARGET_ENV).num_args(0..),
+        Arg::new(TARGET_OPTIONS)
+            .default_value("{input}")
+            .long(TARGET_OPTIONS)
+            .value_delimiter(' ')
+            .help("Use a quoted string with space separation to denote multiple arguments"),
+        Arg::new(TARGET_TIMEOUT)
+            .long(TARGET_TIMEOUT)
+            .value_parser(value_parser!(u64)),
+        Arg::new(CHECK_RETRY_COUNT)
+            .long(CHECK_RETRY_COUNT)
+            .value_parser(value_parser!(u64))
+            .default_value("0"),
+        Arg::new(CHECK_ASAN_LOG)
+            .action(ArgAction::SetTrue)
+            .long(CHECK_ASAN_LOG),
+        Arg::new(DISABLE_CHECK_DEBUGGER)
+            .action(ArgAction::SetTrue)
+            .long("disable_check_debugger"),
+        Arg::new(CRASHES_DIR)
+            .long(CRASHES_DIR)
+            .value_parser(value_parser!(PathBuf))
+            .help("Directory to save crashing inputs to, named by their SHA-256"),
+        Arg::new(ITERATIONS)
+            .long(ITERATIONS)
+            .value_parser(value_parser!(u64))
+            .help("Number of inputs to test, instead of running until stopped"),
+        Arg::new(MAX_DEPTH)
+            .long(MAX_DEPTH)
+            .value_parser(value_parser!(usize))
+            .default_value("16")
+            .help("Depth past which rules are expanded to end the input as soon as possible"),
+        Arg::new(SEED)
+            .long(SEED)
+            .value_parser(value_parser!(u64))
+            .help("Seed for the input generator, to repeat a run"),
+    ]
+}
+
+pub fn args(name: &'static str) -> Command {
+    Command::new(name)
+        .about("test an application with inputs generated from a grammar")
+        .args(&build_shared_args())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAMMAR: &str = r#"{
+        "start": "<expr>",
+        "rules": {
+            "<expr>": [["<digit>"], ["(", "<expr>", "+", "<expr>", ")"]],
+            "<digit>": [["0"], ["1"]]
+        }
+    }"#;
+
+    #[test]
+    fn test_grammar_parse_errors() {
+        assert!(Grammar::parse(r#"{"rules": {"<expr>": [["1"]]}}"#).is_err());
+        assert!(Grammar::parse(r#"{"rules": {"<start>": []}}"#).is_err());
+        assert!(Grammar::parse(r#"{"rules": {"<start>": [["1"]]}}"#).is_ok());
+    }
+
+    #[test]
+    fn test_grammar_fuzzer_generate() -> Result<()> {
+        let mut fuzzer = GrammarFuzzer::new(Grammar::parse(GRAMMAR)?, 4, Some(1));
+
+        for _ in 0..100 {
+            let input = String::from_utf8(fuzzer.generate()?)?;
+            assert!(!input.is_empty());
+            assert!(input.chars().all(|c| "01()+".contains(c)), "{input}");
+            assert_eq!(input.matches('(').count(), input.matches(')').count());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_grammar_fuzzer_max_depth() -> Result<()> {
+        // past the max depth, only the shortest expansion is used
+        let mut fuzzer = GrammarFuzzer::new(Grammar::parse(GRAMMAR)?, 0, None);
+        let input = fuzzer.generate()?;
+        assert!(input == b"0" || input == b"1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_grammar_fuzzer_no_termination() -> Result<()> {
+        let grammar = Grammar::parse(r#"{"rules": {"<start>": [["a", "<start>"]]}}"#)?;
+        let mut fuzzer = GrammarFuzzer::new(grammar, 4, Some(1));
+        assert!(fuzzer.generate().is_err());
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::{
     local::common::{
@@ -12,7 +12,10 @@ use crate::{
     },
     tasks::{
         config::CommonConfig,
-        report::generic::{Config, ReportTask},
+        report::{
+            crash_report::CrashTestResult,
+            generic::{test_input, Config, ReportTask, TestInputArgs},
+        },
     },
 };
 use anyhow::Result;
@@ -20,6 +23,8 @@ use clap::{Arg, ArgAction, Command};
 use flume::Sender;
 use storage_queue::QueueClient;
 
+const CRASH_INPUT: &str = "crash_input";
+
 pub fn build_report_config(
     args: &clap::ArgMatches,
     input_queue: Option<QueueClient>,
@@ -85,11 +90,61 @@ pub fn build_report_config(
 }
 
 pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>) -> Result<()> {
+    if let Some(crash_input) = args.get_one::<PathBuf>(CRASH_INPUT) {
+        return report_crash_input(args, crash_input, event_sender).await;
+    }
+
     let context = build_local_context(args, true, event_sender.clone()).await?;
     let config = build_report_config(args, None, context.common_config.clone(), event_sender)?;
     ReportTask::new(config).managed_run().await
 }
 
+// Print the report for a single, already existing crash input, as it would be
+// saved by the report task.
+async fn report_crash_input(
+    args: &clap::ArgMatches,
+    crash_input: &Path,
+    event_sender: Option<Sender<UiEvent>>,
+) -> Result<()> {
+    let context = build_local_context(args, false, event_sender).await?;
+
+    let target_exe: PathBuf = get_cmd_exe(CmdType::Target, args)?.into();
+    let target_env = get_cmd_env(CmdType::Target, args)?;
+    let target_options = get_cmd_arg(CmdType::Target, args);
+    let target_timeout = args.get_one::<u64>(TARGET_TIMEOUT).copied();
+    let check_retry_count = args
+        .get_one::<u64>(CHECK_RETRY_COUNT)
+        .copied()
+        .expect("has a default");
+    let check_asan_log = args.get_flag(CHECK_ASAN_LOG);
+    let check_debugger = !args.get_flag(DISABLE_CHECK_DEBUGGER);
+
+    let config = TestInputArgs {
+        target_exe: &target_exe,
+        target_env: &target_env,
+        target_options: &target_options,
+        input_url: None,
+        input: crash_input,
+        job_id: context.common_config.job_id,
+        task_id: context.common_config.task_id,
+        target_timeout,
+        check_retry_count,
+        setup_dir: &context.common_config.setup_dir,
+        extra_setup_dir: context.common_config.extra_setup_dir.as_deref(),
+        minimized_stack_depth: None,
+        check_asan_log,
+        check_debugger,
+        machine_identity: context.common_config.machine_identity.clone(),
+    };
+
+    let report = match test_input(config).await? {
+        CrashTestResult::CrashReport(report) => serde_json::to_string_pretty(&report)?,
+        CrashTestResult::NoRepro(no_repro) => serde_json::to_string_pretty(&no_repro)?,
+    };
+    println!("{report}");
+    Ok(())
+}
+
 pub fn build_shared_args() -> Vec<Arg> {
     vec![
         Arg::new(TARGET_EXE).long(TARGET_EXE).required(true),
@@ -101,8 +156,13 @@ pub fn build_shared_args() -> Vec<Arg> {
             .help("Use a quoted string with space separation to denote multiple arguments"),
         Arg::new(CRASHES_DIR)
             .long(CRASHES_DIR)
-            .required(true)
+            .required_unless_present(CRASH_INPUT)
             .value_parser(value_parser!(PathBuf)),
+        Arg::new(CRASH_INPUT)
+            .long(CRASH_INPUT)
+            .value_parser(value_parser!(PathBuf))
+            .conflicts_with(CRASHES_DIR)
+            .help("Print the report for a single crash input, instead of monitoring a directory"),
         Arg::new(REPORTS_DIR)
             .long(REPORTS_DIR)
             .required(false)
@@ -114,7 +174,7 @@ pub fn build_shared_args() -> Vec<Arg> {
         Arg::new(UNIQUE_REPORTS_DIR)
             .long(UNIQUE_REPORTS_DIR)
             .value_parser(value_parser!(PathBuf))
-            .required(true),
+            .required_unless_present(CRASH_INPUT),
         Arg::new(TARGET_TIMEOUT)
             .long(TARGET_TIMEOUT)
             .value_parser(value_parser!(u64))
@@ -0,0 +1,171 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::local::common::{
+    build_local_context, get_cmd_arg, get_cmd_env, CmdType, UiEvent, INPUTS_DIR, TARGET_ENV,
+    TARGET_EXE, TARGET_OPTIONS, TARGET_TIMEOUT,
+};
+use anyhow::{Context, Result};
+use clap::{Arg, Command};
+use flume::Sender;
+use onefuzz::{libfuzzer::LibFuzzer, sancov};
+use std::path::{Path, PathBuf};
+use tempfile::{tempdir, NamedTempFile};
+
+const OUTPUT: &str = "output";
+const COBERTURA: &str = "cobertura";
+const PDB: &str = "pdb";
+const SRCVIEW_EXE: &str = "srcview_exe";
+
+pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>) -> Result<()> {
+    let context = build_local_context(args, false, event_sender).await?;
+
+    let target_exe = args
+        .get_one::<PathBuf>(TARGET_EXE)
+        .expect("is marked required");
+    let mut target_env = get_cmd_env(CmdType::Target, args)?;
+    let mut target_options = get_cmd_arg(CmdType::Target, args);
+    let inputs_dir = args
+        .get_one::<PathBuf>(INPUTS_DIR)
+        .expect("is marked required");
+
+    if let Some(timeout) = args.get_one::<u64>(TARGET_TIMEOUT) {
+        target_options.push(format!("-timeout={timeout}"));
+    }
+
+    let coverage_dir = tempdir().context("unable to create sancov dir")?;
+    sancov::add_sancov_env(&mut target_env, coverage_dir.path());
+
+    let fuzzer = LibFuzzer::new(
+        target_exe.clone(),
+        target_options,
+        target_env,
+        context.common_config.setup_dir.clone(),
+        context.common_config.extra_setup_dir.clone(),
+        context
+            .common_config
+            .extra_output
+            .as_ref()
+            .map(|x| x.local_path.clone()),
+        context.common_config.machine_identity.clone(),
+    );
+
+    if let Some(cov) = fuzzer.run_corpus(inputs_dir).await? {
+        info!("libFuzzer reported {cov} coverage points");
+    }
+
+    let coverage = sancov::read_dir(coverage_dir.path()).await?;
+    if coverage.is_empty() {
+        warn!(
+            "no sancov files were written, check that {} is built with -fsanitize-coverage=trace-pc-guard",
+            target_exe.display()
+        );
+    }
+    let modoff = sancov::to_modoff(&coverage);
+
+    let cobertura = args.get_one::<PathBuf>(COBERTURA);
+
+    // srcview reads modoff from a file, so use a temporary one if no output was given.
+    let modoff_file;
+    let modoff_path = match args.get_one::<PathBuf>(OUTPUT) {
+        Some(output) => output.as_path(),
+        None if cobertura.is_some() => {
+            modoff_file = NamedTempFile::new().context("unable to create modoff file")?;
+            modoff_file.path()
+        }
+        None => {
+            print!("{modoff}");
+            return Ok(());
+        }
+    };
+    tokio::fs::write(modoff_path, modoff)
+        .await
+        .with_context(|| format!("unable to write modoff file: {}", modoff_path.display()))?;
+
+    if let Some(cobertura) = cobertura {
+        let pdb = args
+            .get_one::<PathBuf>(PDB)
+            .expect("required with cobertura");
+        let srcview = args
+            .get_one::<PathBuf>(SRCVIEW_EXE)
+            .expect("has default value");
+        write_cobertura(srcview, pdb, target_exe, modoff_path, cobertura).await?;
+    }
+
+    Ok(())
+}
+
+async fn write_cobertura(
+    srcview: &Path,
+    pdb: &Path,
+    target_exe: &Path,
+    modoff: &Path,
+    output: &Path,
+) -> Result<()> {
+    let mut cmd = tokio::process::Command::new(srcview);
+    cmd.arg("cobertura").arg(pdb).arg(modoff).arg(output);
+
+    if let Some(module_name) = target_exe.file_name() {
+        cmd.arg("--module-name").arg(module_name);
+    }
+
+    let result = cmd
+        .output()
+        .await
+        .with_context(|| format!("unable to run srcview: {}", srcview.display()))?;
+
+    if !result.status.success() {
+        bail!(
+            "srcview failed to write cobertura report: {:?}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+    }
+
+    info!("wrote cobertura report: {}", output.display());
+    Ok(())
+}
+
+pub fn build_shared_args() -> Vec<Arg> {
+    vec![
+        Arg::new(TARGET_EXE)
+            .long(TARGET_EXE)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new(TARGET_ENV).long(TARGET_ENV).num_args(0..),
+        Arg::new(TARGET_OPTIONS)
+            .long(TARGET_OPTIONS)
+            .value_delimiter(' ')
+            .help("Use a quoted string with space separation to denote multiple arguments"),
+        Arg::new(TARGET_TIMEOUT)
+            .long(TARGET_TIMEOUT)
+            .value_parser(value_parser!(u64))
+            .help("Per-input timeout in seconds, passed to libFuzzer as -timeout"),
+        Arg::new(INPUTS_DIR)
+            .long(INPUTS_DIR)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new(OUTPUT)
+            .long(OUTPUT)
+            .value_parser(value_parser!(PathBuf))
+            .help("File to write modoff coverage to, instead of stdout"),
+        Arg::new(COBERTURA)
+            .long(COBERTURA)
+            .requires(PDB)
+            .value_parser(value_parser!(PathBuf))
+            .help("Also write a Cobertura XML report, using srcview"),
+        Arg::new(PDB)
+            .long(PDB)
+            .value_parser(value_parser!(PathBuf))
+            .help("PDB of the target, for the Cobertura report"),
+        Arg::new(SRCVIEW_EXE)
+            .long(SRCVIEW_EXE)
+            .default_value("srcview")
+            .value_parser(value_parser!(PathBuf)),
+    ]
+}
+
+pub fn args(name: &'static str) -> Command {
+    Command::new(name)
+        .about("collect modoff coverage of a corpus by running a libfuzzer target with -runs=0")
+        .args(&build_shared_args())
+}
@@ -0,0 +1,224 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::local::common::{
+    build_local_context, get_cmd_arg, get_cmd_env, CmdType, UiEvent, CHECK_RETRY_COUNT, TARGET_ENV,
+    TARGET_EXE, TARGET_OPTIONS, TARGET_TIMEOUT,
+};
+use anyhow::{Context, Result};
+use clap::{Arg, Command};
+use flume::Sender;
+use onefuzz::{
+    input_tester::{TestResult, Tester},
+    libfuzzer::LibFuzzer,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+const CRASH_INPUT: &str = "crash_input";
+const OUTPUT: &str = "output";
+const TOOL: &str = "tool";
+
+const AFL_TMIN: &str = "afltmin";
+const LIBFUZZER: &str = "libfuzzer";
+
+pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>) -> Result<()> {
+    let context = build_local_context(args, false, event_sender).await?;
+    let setup_dir = &context.common_config.setup_dir;
+    let extra_setup_dir = context.common_config.extra_setup_dir.as_deref();
+    let machine_identity = &context.common_config.machine_identity;
+
+    let target_exe = args
+        .get_one::<PathBuf>(TARGET_EXE)
+        .expect("is marked required");
+    let target_env = get_cmd_env(CmdType::Target, args)?;
+    let target_options = get_cmd_arg(CmdType::Target, args);
+    let crash_input = args
+        .get_one::<PathBuf>(CRASH_INPUT)
+        .expect("is marked required");
+    let output = args.get_one::<PathBuf>(OUTPUT).expect("is marked required");
+    let tool = args
+        .get_one::<String>(TOOL)
+        .expect("has a default value")
+        .as_str();
+    let target_timeout = args.get_one::<u64>(TARGET_TIMEOUT).copied();
+    let check_retry_count = args
+        .get_one::<u64>(CHECK_RETRY_COUNT)
+        .copied()
+        .expect("has a default value");
+
+    let libfuzzer = LibFuzzer::new(
+        target_exe.clone(),
+        target_options.clone(),
+        target_env.clone(),
+        setup_dir.clone(),
+        extra_setup_dir.map(PathBuf::from),
+        None,
+        machine_identity.clone(),
+    );
+
+    let tester = Tester::new(
+        setup_dir,
+        extra_setup_dir,
+        target_exe,
+        &target_options,
+        &target_env,
+        machine_identity.clone(),
+    )
+    .check_asan_stderr(true)
+    .check_retry_count(check_retry_count)
+    .add_setup_to_path(true)
+    .add_setup_to_ld_library_path(cfg!(target_family = "unix"))
+    .set_optional(target_timeout, Tester::timeout);
+
+    let (libfuzzer, tester) = (&libfuzzer, &tester);
+    let test_input = move |input: PathBuf| async move {
+        match tool {
+            LIBFUZZER => {
+                libfuzzer
+                    .repro(input, target_timeout, check_retry_count)
+                    .await
+            }
+            _ => tester.test_input(input).await,
+        }
+    };
+
+    let original = test_input(crash_input.clone()).await?;
+    let original_hash = stack_hash(&original)
+        .with_context(|| format!("input does not crash: {}", crash_input.display()))?;
+    println!("original stack hash: {original_hash}");
+
+    match tool {
+        LIBFUZZER => libfuzzer.minimize_crash(crash_input, output).await?,
+        _ => {
+            afl_tmin(
+                target_exe,
+                &target_options,
+                &target_env,
+                target_timeout,
+                crash_input,
+                output,
+            )
+            .await?
+        }
+    }
+
+    let minimized = test_input(output.clone()).await?;
+    let minimized_hash = stack_hash(&minimized)
+        .with_context(|| format!("minimized input does not crash: {}", output.display()))?;
+    if minimized_hash != original_hash {
+        bail!(
+            "minimized input has a different stack hash: {} (original {})",
+            minimized_hash,
+            original_hash
+        );
+    }
+
+    let original_len = tokio::fs::metadata(crash_input).await?.len();
+    let minimized_len = tokio::fs::metadata(output).await?.len();
+    println!(
+        "minimized {} bytes to {} bytes: {}",
+        original_len,
+        minimized_len,
+        output.display()
+    );
+
+    Ok(())
+}
+
+fn stack_hash(result: &TestResult) -> Result<String> {
+    match &result.crash_log {
+        Some(crash_log) => Ok(crash_log.call_stack_sha256()),
+        None => match &result.error {
+            Some(err) => bail!("no crash found: {}", err),
+            None => bail!("no crash found"),
+        },
+    }
+}
+
+async fn afl_tmin(
+    target_exe: &Path,
+    target_options: &[String],
+    target_env: &HashMap<String, String>,
+    target_timeout: Option<u64>,
+    input: &Path,
+    output: &Path,
+) -> Result<()> {
+    let mut cmd = tokio::process::Command::new("afl-tmin");
+    cmd.arg("-i")
+        .arg(input)
+        .arg("-o")
+        .arg(output)
+        .envs(target_env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    if let Some(timeout) = target_timeout {
+        cmd.arg("-t").arg((timeout * 1000).to_string());
+    }
+
+    // afl-tmin passes the input file to the target as `@@`.
+    cmd.arg("--").arg(target_exe).args(
+        target_options
+            .iter()
+            .map(|option| option.replace("{input}", "@@")),
+    );
+
+    let result = cmd
+        .output()
+        .await
+        .context("unable to run afl-tmin, is it on the PATH?")?;
+
+    if !result.status.success() {
+        bail!(
+            "afl-tmin failed: {:?}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+pub fn build_shared_args() -> Vec<Arg> {
+    vec![
+        Arg::new(TARGET_EXE)
+            .long(TARGET_EXE)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new(CRASH_INPUT)
+            .long(CRASH_INPUT)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new(OUTPUT)
+            .long(OUTPUT)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new(TOOL)
+            .long(TOOL)
+            .value_parser([AFL_TMIN, LIBFUZZER])
+            .default_value(LIBFUZZER),
+        Arg::new(TARGET_ENV).long(TARGET_ENV).num_args(0..),
+        Arg::new(TARGET_OPTIONS)
+            .long(TARGET_OPTIONS)
+            .value_delimiter(' ')
+            .help("Use a quoted string with space separation to denote multiple arguments"),
+        Arg::new(TARGET_TIMEOUT)
+            .long(TARGET_TIMEOUT)
+            .value_parser(value_parser!(u64)),
+        Arg::new(CHECK_RETRY_COUNT)
+            .long(CHECK_RETRY_COUNT)
+            .value_parser(value_parser!(u64))
+            .default_value("0"),
+    ]
+}
+
+pub fn args(name: &'static str) -> Command {
+    Command::new(name)
+        .about("minimize a crashing input, checking that it keeps the same stack hash")
+        .args(&build_shared_args())
+}
@@ -5,15 +5,24 @@ pub mod cmd;
 pub mod common;
 #[cfg(any(target_os = "linux", target_os = "windows"))]
 pub mod coverage;
+pub mod coverage_diff;
+pub mod fuzz_with_grammar;
 pub mod generic_analysis;
 pub mod generic_crash_report;
 pub mod generic_generator;
 pub mod libfuzzer;
+pub mod libfuzzer_coverage;
 pub mod libfuzzer_crash_report;
 pub mod libfuzzer_fuzz;
 pub mod libfuzzer_merge;
 pub mod libfuzzer_regression;
 pub mod libfuzzer_test_input;
+pub mod minimize;
 pub mod radamsa;
+pub mod reproduce_all;
+pub mod sanitizer_coverage;
+pub mod symbolize_stack;
 pub mod test_input;
 pub mod tui;
+pub mod unique_inputs;
+pub mod verify_setup;
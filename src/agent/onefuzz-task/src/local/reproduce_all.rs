@@ -0,0 +1,185 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    local::common::{
+        build_local_context, get_cmd_arg, get_cmd_env, get_cmd_exe, CmdType, UiEvent,
+        CHECK_ASAN_LOG, CHECK_RETRY_COUNT, CRASHES_DIR, DISABLE_CHECK_DEBUGGER, TARGET_ENV,
+        TARGET_EXE, TARGET_OPTIONS, TARGET_TIMEOUT,
+    },
+    tasks::report::{
+        crash_report::CrashTestResult,
+        generic::{test_input, TestInputArgs},
+    },
+};
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, Command};
+use flume::Sender;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::Serialize;
+
+const OUTPUT_REPORT: &str = "output_report";
+const PARALLELISM: &str = "parallelism";
+
+#[derive(Debug, Serialize)]
+struct ReproduceResult {
+    input: PathBuf,
+
+    #[serde(flatten)]
+    result: CrashTestResult,
+}
+
+pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>) -> Result<()> {
+    let context = build_local_context(args, false, event_sender).await?;
+
+    let crashes_dir = args
+        .get_one::<PathBuf>(CRASHES_DIR)
+        .expect("is marked required");
+    let output_report = args
+        .get_one::<PathBuf>(OUTPUT_REPORT)
+        .expect("is marked required");
+    let parallelism = args
+        .get_one::<usize>(PARALLELISM)
+        .copied()
+        .expect("has a default");
+
+    let target_exe: PathBuf = get_cmd_exe(CmdType::Target, args)?.into();
+    let target_env = get_cmd_env(CmdType::Target, args)?;
+    let target_options = get_cmd_arg(CmdType::Target, args);
+    let target_timeout = args.get_one::<u64>(TARGET_TIMEOUT).copied();
+    let check_retry_count = args
+        .get_one::<u64>(CHECK_RETRY_COUNT)
+        .copied()
+        .expect("has a default");
+    let check_asan_log = args.get_flag(CHECK_ASAN_LOG);
+    let check_debugger = !args.get_flag(DISABLE_CHECK_DEBUGGER);
+
+    let inputs = list_inputs(crashes_dir).await?;
+    info!(
+        "reproducing {} inputs from {}",
+        inputs.len(),
+        crashes_dir.display()
+    );
+
+    let common = &context.common_config;
+    let mut results: Vec<ReproduceResult> = stream::iter(&inputs)
+        .map(|input| {
+            let config = TestInputArgs {
+                target_exe: &target_exe,
+                target_env: &target_env,
+                target_options: &target_options,
+                input_url: None,
+                input,
+                job_id: common.job_id,
+                task_id: common.task_id,
+                target_timeout,
+                check_retry_count,
+                setup_dir: &common.setup_dir,
+                extra_setup_dir: common.extra_setup_dir.as_deref(),
+                minimized_stack_depth: None,
+                check_asan_log,
+                check_debugger,
+                machine_identity: common.machine_identity.clone(),
+            };
+
+            async move {
+                let result = test_input(config)
+                    .await
+                    .with_context(|| format!("unable to test input: {}", input.display()))?;
+                Ok::<_, anyhow::Error>(ReproduceResult {
+                    input: input.clone(),
+                    result,
+                })
+            }
+        })
+        .buffer_unordered(parallelism.max(1))
+        .try_collect()
+        .await?;
+
+    // Inputs finish in any order, so report them in the order they were listed.
+    results.sort_by(|a, b| a.input.cmp(&b.input));
+
+    let data = serde_json::to_string_pretty(&results)?;
+    tokio::fs::write(output_report, data)
+        .await
+        .with_context(|| format!("unable to write report: {}", output_report.display()))?;
+    info!("wrote report: {}", output_report.display());
+
+    let reproduced = results
+        .iter()
+        .filter(|r| matches!(r.result, CrashTestResult::CrashReport(_)))
+        .count();
+    if reproduced > 0 {
+        bail!(
+            "{} of {} inputs reproduced a crash",
+            reproduced,
+            results.len()
+        );
+    }
+
+    info!("none of {} inputs reproduced a crash", results.len());
+    Ok(())
+}
+
+async fn list_inputs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = tokio::fs::read_dir(dir)
+        .await
+        .with_context(|| format!("unable to read crashes dir: {}", dir.display()))?;
+
+    let mut inputs = vec![];
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() {
+            inputs.push(entry.path());
+        }
+    }
+    inputs.sort();
+
+    Ok(inputs)
+}
+
+pub fn build_shared_args() -> Vec<Arg> {
+    vec![
+        Arg::new(TARGET_EXE).long(TARGET_EXE).required(true),
+        Arg::new(TARGET_ENV).long(TARGET_ENV).num_args(0..),
+        Arg::new(TARGET_OPTIONS)
+            .default_value("{input}")
+            .long(TARGET_OPTIONS)
+            .value_delimiter(' ')
+            .help("Use a quoted string with space separation to denote multiple arguments"),
+        Arg::new(CRASHES_DIR)
+            .long(CRASHES_DIR)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new(OUTPUT_REPORT)
+            .long(OUTPUT_REPORT)
+            .required(true)
+            .value_parser(value_parser!(PathBuf))
+            .help("File to write the results to, as a JSON array"),
+        Arg::new(PARALLELISM)
+            .long(PARALLELISM)
+            .value_parser(value_parser!(usize))
+            .default_value("1")
+            .help("Maximum number of inputs to test at once"),
+        Arg::new(TARGET_TIMEOUT)
+            .long(TARGET_TIMEOUT)
+            .value_parser(value_parser!(u64)),
+        Arg::new(CHECK_RETRY_COUNT)
+            .long(CHECK_RETRY_COUNT)
+            .value_parser(value_parser!(u64))
+            .default_value("0"),
+        Arg::new(CHECK_ASAN_LOG)
+            .action(ArgAction::SetTrue)
+            .long(CHECK_ASAN_LOG),
+        Arg::new(DISABLE_CHECK_DEBUGGER)
+            .action(ArgAction::SetTrue)
+            .long("disable_check_debugger"),
+    ]
+}
+
+pub fn args(name: &'static str) -> Command {
+    Command::new(name)
+        .about("reproduce every crash in a directory and write a summary report")
+        .args(&build_shared_args())
+}
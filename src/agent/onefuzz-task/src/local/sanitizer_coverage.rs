@@ -0,0 +1,117 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::local::common::{
+    build_local_context, get_cmd_arg, get_cmd_env, CmdType, UiEvent, INPUTS_DIR, TARGET_ENV,
+    TARGET_EXE, TARGET_OPTIONS, TARGET_TIMEOUT,
+};
+use anyhow::{Context, Result};
+use clap::{Arg, Command};
+use flume::Sender;
+use onefuzz::{expand::Expand, fs::list_files, process::run_cmd, sancov};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+use tempfile::tempdir;
+
+const OUTPUT: &str = "output";
+
+pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>) -> Result<()> {
+    let context = build_local_context(args, false, event_sender).await?;
+
+    let target_exe = args
+        .get_one::<PathBuf>(TARGET_EXE)
+        .expect("is marked required");
+    let target_env = get_cmd_env(CmdType::Target, args)?;
+    let target_options = get_cmd_arg(CmdType::Target, args);
+    let target_timeout = args
+        .get_one::<u64>(TARGET_TIMEOUT)
+        .copied()
+        .expect("has default value");
+    let inputs_dir = args
+        .get_one::<PathBuf>(INPUTS_DIR)
+        .expect("is marked required");
+
+    let inputs = list_files(inputs_dir).await?;
+    let coverage_dir = tempdir().context("unable to create sancov dir")?;
+
+    for input in &inputs {
+        let expand = Expand::new(&context.common_config.machine_identity)
+            .machine_id()
+            .input_path(input)
+            .target_exe(target_exe)
+            .target_options(&target_options)
+            .setup_dir(&context.common_config.setup_dir)
+            .set_optional_ref(
+                &context.common_config.extra_setup_dir,
+                Expand::extra_setup_dir,
+            );
+
+        let argv = expand.evaluate(&target_options)?;
+        let mut env: HashMap<String, String> = target_env
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), expand.evaluate_value(v)?)))
+            .collect::<Result<_>>()?;
+        sancov::add_sancov_env(&mut env, coverage_dir.path());
+
+        // Crashing inputs still dump coverage, so only a timeout loses it.
+        match run_cmd(target_exe, argv, &env, Duration::from_secs(target_timeout)).await {
+            Ok(output) => debug!("ran input {}: {:?}", input.display(), output.exit_status),
+            Err(err) => warn!("no coverage for input {}: {:?}", input.display(), err),
+        }
+    }
+
+    let coverage = sancov::read_dir(coverage_dir.path()).await?;
+    if coverage.is_empty() {
+        warn!(
+            "no sancov files were written, check that {} is built with SanitizerCoverage",
+            target_exe.display()
+        );
+    }
+
+    let modoff = sancov::to_modoff(&coverage);
+    match args.get_one::<PathBuf>(OUTPUT) {
+        Some(output) => tokio::fs::write(output, modoff)
+            .await
+            .with_context(|| format!("unable to write modoff file: {}", output.display()))?,
+        None => print!("{modoff}"),
+    }
+
+    info!(
+        "collected coverage of {} modules from {} inputs",
+        coverage.len(),
+        inputs.len()
+    );
+    Ok(())
+}
+
+pub fn build_shared_args() -> Vec<Arg> {
+    vec![
+        Arg::new(TARGET_EXE)
+            .long(TARGET_EXE)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new(TARGET_ENV).long(TARGET_ENV).num_args(0..),
+        Arg::new(TARGET_OPTIONS)
+            .default_value("{input}")
+            .long(TARGET_OPTIONS)
+            .value_delimiter(' ')
+            .help("Use a quoted string with space separation to denote multiple arguments"),
+        Arg::new(TARGET_TIMEOUT)
+            .long(TARGET_TIMEOUT)
+            .value_parser(value_parser!(u64))
+            .default_value("10"),
+        Arg::new(INPUTS_DIR)
+            .long(INPUTS_DIR)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new(OUTPUT)
+            .long(OUTPUT)
+            .value_parser(value_parser!(PathBuf))
+            .help("File to write modoff coverage to, instead of stdout"),
+    ]
+}
+
+pub fn args(name: &'static str) -> Command {
+    Command::new(name)
+        .about("collect modoff coverage of a corpus from a SanitizerCoverage-instrumented target")
+        .args(&build_shared_args())
+}
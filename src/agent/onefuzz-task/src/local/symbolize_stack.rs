@@ -0,0 +1,69 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::local::{common::UiEvent, coverage_diff::load_srcview};
+use anyhow::{Context, Result};
+use clap::{Arg, Command};
+use flume::Sender;
+use srcview::{ModOff, SrcView};
+use std::path::PathBuf;
+
+const PDB: &str = "pdb";
+const STACK: &str = "stack";
+const MODULE_NAME: &str = "module_name";
+
+pub async fn run(args: &clap::ArgMatches, _event_sender: Option<Sender<UiEvent>>) -> Result<()> {
+    let pdb = args.get_one::<PathBuf>(PDB).expect("is marked required");
+    let stack = args.get_one::<PathBuf>(STACK).expect("is marked required");
+    let module_name = args.get_one::<String>(MODULE_NAME);
+
+    let srcview = load_srcview(pdb, module_name.map(String::as_str))?;
+
+    let stack_data = tokio::fs::read_to_string(stack)
+        .await
+        .with_context(|| format!("unable to read stack file: {}", stack.display()))?;
+    let frames = ModOff::parse(&stack_data)
+        .with_context(|| format!("invalid stack file: {}", stack.display()))?;
+
+    for frame in &frames {
+        println!("{}", symbolize(&srcview, frame));
+    }
+
+    Ok(())
+}
+
+// Frames are printed as `function (file:line)`, falling back to the modoff for
+// whichever of the two can't be resolved.
+fn symbolize(srcview: &SrcView, frame: &ModOff) -> String {
+    let function = srcview.function_for_offset(&frame.module, frame.offset as u64);
+
+    match (function, srcview.modoff(frame)) {
+        (Some(function), Some(srcline)) => format!("{function} ({srcline})"),
+        (Some(function), None) => format!("{function} ({frame})"),
+        (None, Some(srcline)) => format!("{frame} ({srcline})"),
+        (None, None) => frame.to_string(),
+    }
+}
+
+pub fn build_shared_args() -> Vec<Arg> {
+    vec![
+        Arg::new(PDB)
+            .long(PDB)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new(STACK)
+            .long(STACK)
+            .required(true)
+            .value_parser(value_parser!(PathBuf))
+            .help("File with one module+offset stack frame per line, innermost first"),
+        Arg::new(MODULE_NAME).long(MODULE_NAME).help(
+            "Module name of the PDB in the stack frames, guessed from the PDB name if not set",
+        ),
+    ]
+}
+
+pub fn args(name: &'static str) -> Command {
+    Command::new(name)
+        .about("resolve a module+offset stack trace to functions and source lines")
+        .args(&build_shared_args())
+}
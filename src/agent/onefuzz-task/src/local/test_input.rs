@@ -3,16 +3,38 @@
 
 use crate::{
     local::common::{
-        build_local_context, get_cmd_arg, get_cmd_env, CmdType, UiEvent, CHECK_ASAN_LOG,
-        CHECK_RETRY_COUNT, DISABLE_CHECK_DEBUGGER, TARGET_ENV, TARGET_EXE, TARGET_OPTIONS,
-        TARGET_TIMEOUT,
+        build_local_context, get_cmd_arg, get_cmd_env, get_hash_map, CmdType, UiEvent,
+        CHECK_ASAN_LOG, CHECK_RETRY_COUNT, DISABLE_CHECK_DEBUGGER, TARGET_ENV, TARGET_EXE,
+        TARGET_OPTIONS, TARGET_TIMEOUT,
+    },
+    tasks::report::{
+        crash_report::CrashTestResult,
+        generic::{test_input, TestInputArgs},
     },
-    tasks::report::generic::{test_input, TestInputArgs},
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Arg, ArgAction, Command};
 use flume::Sender;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::{collections::HashMap, io::Write, path::PathBuf};
+use tempfile::NamedTempFile;
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+const INPUT: &str = "input";
+const STDIN: &str = "stdin";
+const TAGS: &str = "tags";
+const MACHINE_ID: &str = "machine_id";
+const OUTPUT_DIR: &str = "output_dir";
+
+#[derive(Debug, Serialize)]
+struct TestInputResult {
+    #[serde(flatten)]
+    result: CrashTestResult,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    tags: HashMap<String, String>,
+}
 
 pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>) -> Result<()> {
     let context = build_local_context(args, false, event_sender).await?;
@@ -22,9 +44,22 @@ pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>)
         .expect("is marked required");
     let target_env = get_cmd_env(CmdType::Target, args)?;
     let target_options = get_cmd_arg(CmdType::Target, args);
-    let input = args
-        .get_one::<PathBuf>("input")
-        .expect("is marked required");
+    let tags = get_hash_map(args, TAGS)?;
+
+    // Keep the temporary file alive until the test is done, it is deleted on drop.
+    let stdin_input = if args.get_flag(STDIN) {
+        Some(read_stdin_input().await?)
+    } else {
+        None
+    };
+    let input = match &stdin_input {
+        Some(file) => file.path(),
+        None => args
+            .get_one::<PathBuf>(INPUT)
+            .expect("is required unless reading from stdin")
+            .as_path(),
+    };
+
     let target_timeout = args.get_one::<u64>(TARGET_TIMEOUT).copied();
     let check_retry_count = args
         .get_one::<u64>(CHECK_RETRY_COUNT)
@@ -33,12 +68,17 @@ pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>)
     let check_asan_log = args.get_flag(CHECK_ASAN_LOG);
     let check_debugger = !args.get_flag(DISABLE_CHECK_DEBUGGER);
 
+    let mut machine_identity = context.common_config.machine_identity.clone();
+    if let Some(machine_id) = args.get_one::<Uuid>(MACHINE_ID) {
+        machine_identity.machine_id = *machine_id;
+    }
+
     let config = TestInputArgs {
         target_exe: target_exe.as_path(),
         target_env: &target_env,
         target_options: &target_options,
         input_url: None,
-        input: input.as_path(),
+        input,
         job_id: context.common_config.job_id,
         task_id: context.common_config.task_id,
         target_timeout,
@@ -48,20 +88,61 @@ pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>)
         minimized_stack_depth: None,
         check_asan_log,
         check_debugger,
-        machine_identity: context.common_config.machine_identity.clone(),
+        machine_identity,
+    };
+
+    let result = TestInputResult {
+        result: test_input(config).await?,
+        tags,
     };
+    let result = serde_json::to_string_pretty(&result)?;
+
+    if let Some(output_dir) = args.get_one::<PathBuf>(OUTPUT_DIR) {
+        tokio::fs::create_dir_all(output_dir)
+            .await
+            .with_context(|| format!("unable to create output dir: {}", output_dir.display()))?;
+
+        let name = match input.file_name() {
+            Some(name) if stdin_input.is_none() => name.to_string_lossy().into_owned(),
+            _ => "stdin".to_owned(),
+        };
+        let output = output_dir.join(format!("{name}.json"));
+        tokio::fs::write(&output, &result)
+            .await
+            .with_context(|| format!("unable to write result: {}", output.display()))?;
+        info!("wrote result: {}", output.display());
+    }
 
-    let result = test_input(config).await?;
-    println!("{}", serde_json::to_string_pretty(&result)?);
+    println!("{result}");
     Ok(())
 }
 
+async fn read_stdin_input() -> Result<NamedTempFile> {
+    let mut data = vec![];
+    tokio::io::stdin()
+        .read_to_end(&mut data)
+        .await
+        .context("unable to read input from stdin")?;
+
+    let mut file = NamedTempFile::new().context("unable to create temporary input file")?;
+    file.write_all(&data)
+        .and_then(|_| file.flush())
+        .context("unable to write stdin input to temporary file")?;
+
+    Ok(file)
+}
+
 pub fn build_shared_args() -> Vec<Arg> {
     vec![
         Arg::new(TARGET_EXE).required(true),
-        Arg::new("input")
-            .required(true)
+        Arg::new(INPUT)
+            .required_unless_present(STDIN)
             .value_parser(value_parser!(PathBuf)),
+        Arg::new(STDIN)
+            .long(STDIN)
+            .action(ArgAction::SetTrue)
+            .conflicts_with(INPUT)
+            .help("Read the input from stdin instead of a file"),
         Arg::new(TARGET_ENV).long(TARGET_ENV).num_args(0..),
         Arg::new(TARGET_OPTIONS)
             .default_value("{input}")
@@ -81,6 +162,18 @@ pub fn build_shared_args() -> Vec<Arg> {
         Arg::new(DISABLE_CHECK_DEBUGGER)
             .action(ArgAction::SetTrue)
             .long("disable_check_debugger"),
+        Arg::new(MACHINE_ID)
+            .long(MACHINE_ID)
+            .value_parser(value_parser!(Uuid))
+            .help("Machine ID to test with, instead of the default local machine ID"),
+        Arg::new(TAGS)
+            .long(TAGS)
+            .num_args(0..)
+            .help("Metadata to include in the result, as key=value pairs"),
+        Arg::new(OUTPUT_DIR)
+            .long(OUTPUT_DIR)
+            .value_parser(value_parser!(PathBuf))
+            .help("Directory to also write the result to, as <input name>.json"),
     ]
 }
 
@@ -0,0 +1,63 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::local::common::{build_local_context, UiEvent, TARGET_EXE};
+use anyhow::Result;
+use clap::{Arg, Command};
+use flume::Sender;
+use onefuzz::setup_script::SetupScript;
+use std::path::PathBuf;
+
+pub async fn run(args: &clap::ArgMatches, event_sender: Option<Sender<UiEvent>>) -> Result<()> {
+    let context = build_local_context(args, false, event_sender).await?;
+    let setup_dir = &context.common_config.setup_dir;
+
+    let target_exe = args
+        .get_one::<PathBuf>(TARGET_EXE)
+        .expect("is marked required");
+
+    let script_succeeded = match SetupScript::new(setup_dir).await? {
+        Some(script) => {
+            println!("running setup script: {}", script.command_line());
+            let output = script.invoke(None).await?;
+
+            println!("exit status: {:?}", output.exit_status);
+            println!("stdout:\n{}", output.stdout);
+            println!("stderr:\n{}", output.stderr);
+            output.exit_status.success
+        }
+        None => {
+            println!("no setup script in {}", setup_dir.display());
+            true
+        }
+    };
+
+    // Tasks resolve a relative `target_exe` against the setup directory.
+    let target_exe = setup_dir.join(target_exe);
+    let target_exe_exists = onefuzz::fs::exists(&target_exe).await?;
+    if target_exe_exists {
+        println!("found target exe: {}", target_exe.display());
+    } else {
+        println!("missing target exe: {}", target_exe.display());
+    }
+
+    if script_succeeded && target_exe_exists {
+        println!("setup succeeded");
+        Ok(())
+    } else {
+        bail!("setup failed")
+    }
+}
+
+pub fn build_shared_args() -> Vec<Arg> {
+    vec![Arg::new(TARGET_EXE)
+        .long(TARGET_EXE)
+        .required(true)
+        .value_parser(value_parser!(PathBuf))]
+}
+
+pub fn args(name: &'static str) -> Command {
+    Command::new(name)
+        .about("run only the setup script of a setup directory and check for the target")
+        .args(&build_shared_args())
+}
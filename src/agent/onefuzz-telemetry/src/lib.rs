@@ -84,6 +84,7 @@ pub enum Event {
     new_unable_to_reproduce,
     regression_report,
     regression_unable_to_reproduce,
+    resource_usage,
 }
 
 impl Event {
@@ -100,6 +101,7 @@ impl Event {
             Self::new_unable_to_reproduce => "new_unable_to_reproduce",
             Self::regression_report => "regression_report",
             Self::regression_unable_to_reproduce => "regression_unable_to_reproduce",
+            Self::resource_usage => "resource_usage",
         }
     }
 }
@@ -24,7 +24,9 @@ pub mod machine_id;
 pub mod memory;
 pub mod monitor;
 pub mod process;
+pub mod sancov;
 pub mod sanitizer;
+pub mod setup_script;
 pub mod sha256;
 pub mod syncdir;
 pub mod utils;
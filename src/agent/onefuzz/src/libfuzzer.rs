@@ -28,6 +28,11 @@ use lazy_static::lazy_static;
 lazy_static! {
     static ref LIBFUZZERLINEREGEX: regex::Regex =
         regex::Regex::new(r"#(\d+)\s*(?:pulse|INITED|NEW|REDUCE).*exec/s: (\d+)").unwrap();
+    static ref LIBFUZZERCOVREGEX: regex::Regex = regex::Regex::new(r"\bcov: (\d+)").unwrap();
+    static ref LIBFUZZERSTATSREGEX: regex::Regex = regex::Regex::new(
+        r"#(\d+)\s+\w+\s+cov: (\d+) ft: (\d+) corp: (\d+)/\S+ .*exec/s: (\d+) rss: (\d+)Mb"
+    )
+    .unwrap();
 }
 
 #[derive(Debug)]
@@ -397,6 +402,94 @@ impl LibFuzzer {
         tester.test_input(test_input).await
     }
 
+    /// Run each input in `corpus_dir` once, without fuzzing, and return the
+    /// final coverage count libFuzzer reports, if any.
+    pub async fn run_corpus(&self, corpus_dir: impl AsRef<Path>) -> Result<Option<u64>> {
+        let mut cmd = self.build_command(
+            None,
+            Some(corpus_dir.as_ref()),
+            None,
+            Some(&["-runs=0".as_ref()]),
+            // A custom `-runs=` would override ours and start fuzzing.
+            Some(&|arg: String| {
+                if arg.starts_with("-runs=") {
+                    None
+                } else {
+                    Some(arg)
+                }
+            }),
+        )?;
+
+        let output = cmd
+            .spawn()
+            .with_context(|| format_err!("libfuzzer failed to start: {}", self.exe.display()))?
+            .wait_with_output()
+            .await
+            .with_context(|| format_err!("libfuzzer failed to run: {}", self.exe.display()))?;
+
+        let output_text = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            bail!(
+                "libFuzzer failed when running the corpus {}: {:?}",
+                corpus_dir.as_ref().display(),
+                output_text,
+            );
+        }
+
+        Ok(parse_cov_count(&output_text))
+    }
+
+    /// Minimize a crashing input with `-minimize_crash=1`, writing the
+    /// smallest input that still crashes to `output`.
+    pub async fn minimize_crash(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+    ) -> Result<()> {
+        let input = input.as_ref();
+        let output = output.as_ref();
+
+        let mut artifact_path = OsString::from("-exact_artifact_path=");
+        artifact_path.push(output);
+
+        let mut cmd = self.build_command(
+            None,
+            None,
+            None,
+            Some(&[
+                "-minimize_crash=1".as_ref(),
+                artifact_path.as_os_str(),
+                input.as_os_str(),
+            ]),
+            // Artifacts must go to `output`, not wherever the options say.
+            Some(&|arg: String| {
+                if arg.starts_with("-exact_artifact_path=") || arg.starts_with("-artifact_prefix=")
+                {
+                    None
+                } else {
+                    Some(arg)
+                }
+            }),
+        )?;
+
+        let output_data = cmd
+            .spawn()
+            .with_context(|| format_err!("libfuzzer failed to start: {}", self.exe.display()))?
+            .wait_with_output()
+            .await
+            .with_context(|| format_err!("libfuzzer failed to run: {}", self.exe.display()))?;
+
+        if !output.exists() {
+            bail!(
+                "libFuzzer did not write a minimized input for {}: {:?}",
+                input.display(),
+                String::from_utf8_lossy(&output_data.stderr),
+            );
+        }
+
+        Ok(())
+    }
+
     pub async fn merge(
         &self,
         corpus_dir: impl AsRef<Path>,
@@ -439,6 +532,14 @@ impl LibFuzzer {
     }
 }
 
+// Returns the coverage from the last status line, e.g. `#2 INITED cov: 11 ...`.
+fn parse_cov_count(output: &str) -> Option<u64> {
+    LIBFUZZERCOVREGEX
+        .captures_iter(output)
+        .last()
+        .and_then(|caps| caps[1].parse().ok())
+}
+
 pub struct LibFuzzerLine {
     _line: String,
     iters: u64,
@@ -475,10 +576,71 @@ impl LibFuzzerLine {
     }
 }
 
+/// Progress reported by a libFuzzer status line, such as
+/// `#4096 pulse cov: 11 ft: 11 corp: 6/21b lim: 4096 exec/s: 2048 rss: 30Mb`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LibFuzzerStats {
+    pub iterations: u64,
+    pub coverage: u32,
+    pub features: u32,
+    pub corpus_size: u32,
+    pub exec_per_sec: f64,
+    pub rss_mb: u32,
+}
+
+impl LibFuzzerStats {
+    /// Parse a status line, returning `None` for any other line.
+    pub fn parse(line: &str) -> Option<Self> {
+        let caps = LIBFUZZERSTATSREGEX.captures(line)?;
+
+        Some(Self {
+            iterations: caps[1].parse().ok()?,
+            coverage: caps[2].parse().ok()?,
+            features: caps[3].parse().ok()?,
+            corpus_size: caps[4].parse().ok()?,
+            exec_per_sec: caps[5].parse().ok()?,
+            rss_mb: caps[6].parse().ok()?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_libfuzzer_stats() {
+        let line =
+            "#2097152        pulse  cov: 11 ft: 12 corp: 6/21b lim: 4096 exec/s: 699050 rss: 562Mb";
+        assert_eq!(
+            LibFuzzerStats::parse(line),
+            Some(LibFuzzerStats {
+                iterations: 2097152,
+                coverage: 11,
+                features: 12,
+                corpus_size: 6,
+                exec_per_sec: 699050.0,
+                rss_mb: 562,
+            })
+        );
+
+        let line = "#2\tINITED cov: 5 ft: 5 corp: 1/1b exec/s: 0 rss: 30Mb";
+        assert_eq!(
+            LibFuzzerStats::parse(line),
+            Some(LibFuzzerStats {
+                iterations: 2,
+                coverage: 5,
+                features: 5,
+                corpus_size: 1,
+                exec_per_sec: 0.0,
+                rss_mb: 30,
+            })
+        );
+
+        assert_eq!(LibFuzzerStats::parse("INFO: Seed: 1"), None);
+        assert_eq!(LibFuzzerStats::parse("#1\tpulse cov: 5"), None);
+    }
+
     #[test]
     fn test_libfuzzer_line_pulse() {
         let line = r"#2097152        pulse  cov: 11 ft: 11 corp: 6/21b lim: 4096 exec/s: 699050 rss: 562Mb";
@@ -495,6 +657,13 @@ mod tests {
         assert!((execs_sec - expected).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_parse_cov_count() {
+        let output = "INFO: Seed: 1\n#2\tINITED cov: 5 ft: 5 corp: 1/1b exec/s: 0 rss: 30Mb\n#4\tDONE   cov: 11 ft: 12 corp: 3/7b lim: 4 exec/s: 0 rss: 31Mb\n";
+        assert_eq!(parse_cov_count(output), Some(11));
+        assert_eq!(parse_cov_count("INFO: Seed: 1\n"), None);
+    }
+
     #[tokio::test]
     #[cfg(target_family = "unix")]
     async fn verify_initial_inputs() -> Result<()> {
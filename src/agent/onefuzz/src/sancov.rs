@@ -0,0 +1,230 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Support for the `.sancov` files written by the sanitizer runtimes when a
+//! SanitizerCoverage-instrumented target runs with `coverage=1`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::TryInto;
+use std::hash::BuildHasher;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use tokio::fs;
+
+const MAGIC_64: u64 = 0xC0BF_FFFF_FFFF_FF64;
+const MAGIC_32: u64 = 0xC0BF_FFFF_FFFF_FF32;
+const MAGIC_LEN: usize = 8;
+
+const SANCOV_EXTENSION: &str = "sancov";
+
+// Each sanitizer runtime reads its own options variable.
+const SANITIZER_OPTIONS: &[&str] = &["ASAN_OPTIONS", "UBSAN_OPTIONS"];
+
+/// Covered offsets, relative to the module base, keyed by module name.
+pub type ModuleCoverage = BTreeMap<String, BTreeSet<u64>>;
+
+/// Enable coverage dumps in the sanitizer options of `env`, so that an
+/// instrumented target writes its `.sancov` files to `coverage_dir` on exit.
+pub fn add_sancov_env<S: BuildHasher>(env: &mut HashMap<String, String, S>, coverage_dir: &Path) {
+    let options = format!("coverage=1:coverage_dir={}", sanitizer_path(coverage_dir));
+
+    for name in SANITIZER_OPTIONS {
+        match env.get_mut(*name) {
+            Some(v) if !v.is_empty() => {
+                v.push(':');
+                v.push_str(&options);
+            }
+            _ => {
+                env.insert(name.to_string(), options.clone());
+            }
+        }
+    }
+}
+
+// Sanitizer options are split on ':', so drive letter paths must be rewritten
+// as the equivalent local network path. See `asan::add_asan_log_env`.
+#[cfg(target_family = "windows")]
+fn sanitizer_path(path: &Path) -> String {
+    let re = regex::Regex::new(r"^(?P<d>[a-zA-Z]):\\").expect("static regex parse failed");
+    re.replace(&path.to_string_lossy(), "\\\\127.0.0.1\\$d$\\")
+        .into_owned()
+}
+
+#[cfg(target_family = "unix")]
+fn sanitizer_path(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Parse the contents of a `.sancov` file into module-relative offsets.
+///
+/// The file is a magic number giving the PC width, followed by an array of
+/// little-endian PCs of that width.
+pub fn parse(data: &[u8]) -> Result<Vec<u64>> {
+    if data.len() < MAGIC_LEN {
+        bail!("sancov data too short: {} bytes", data.len());
+    }
+
+    let (magic, pcs) = data.split_at(MAGIC_LEN);
+    let magic = u64::from_le_bytes(magic.try_into()?);
+
+    let width = match magic {
+        MAGIC_64 => 8,
+        MAGIC_32 => 4,
+        _ => bail!("invalid sancov magic: {:#x}", magic),
+    };
+
+    if pcs.len() % width != 0 {
+        bail!(
+            "sancov data has a partial pc: {} bytes after the magic, pc width {}",
+            pcs.len(),
+            width
+        );
+    }
+
+    let offsets = pcs
+        .chunks_exact(width)
+        .map(|pc| {
+            if width == 8 {
+                u64::from_le_bytes(pc.try_into().expect("chunk has pc width"))
+            } else {
+                u32::from_le_bytes(pc.try_into().expect("chunk has pc width")) as u64
+            }
+        })
+        .collect();
+
+    Ok(offsets)
+}
+
+/// Module name of a `.sancov` file, which the runtime names
+/// `<module>.<pid>.sancov`.
+pub fn module_name(path: &Path) -> Option<&str> {
+    if path.extension()? != SANCOV_EXTENSION {
+        return None;
+    }
+
+    let (module, pid) = path.file_stem()?.to_str()?.rsplit_once('.')?;
+    if module.is_empty() || pid.is_empty() || !pid.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(module)
+}
+
+/// Read every `.sancov` file in `dir`, merging the covered offsets of all runs.
+pub async fn read_dir(dir: impl AsRef<Path>) -> Result<ModuleCoverage> {
+    let dir = dir.as_ref();
+    let mut coverage = ModuleCoverage::new();
+
+    let mut entries = fs::read_dir(dir)
+        .await
+        .with_context(|| format!("unable to read sancov dir: {}", dir.display()))?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let module = match module_name(&path) {
+            Some(module) => module.to_owned(),
+            None => continue,
+        };
+
+        let data = fs::read(&path)
+            .await
+            .with_context(|| format!("unable to read sancov file: {}", path.display()))?;
+        let offsets = parse(&data)
+            .with_context(|| format!("unable to parse sancov file: {}", path.display()))?;
+
+        coverage.entry(module).or_default().extend(offsets);
+    }
+
+    Ok(coverage)
+}
+
+/// Format coverage as modoff lines, `<module>+<hex offset>`, as read by `srcview`.
+pub fn to_modoff(coverage: &ModuleCoverage) -> String {
+    let mut text = String::new();
+
+    for (module, offsets) in coverage {
+        for offset in offsets {
+            text.push_str(&format!("{module}+{offset:x}\n"));
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_64() -> Result<()> {
+        let mut data = MAGIC_64.to_le_bytes().to_vec();
+        data.extend(0x1234u64.to_le_bytes());
+        data.extend(0xdead_beefu64.to_le_bytes());
+
+        assert_eq!(parse(&data)?, vec![0x1234, 0xdead_beef]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_32() -> Result<()> {
+        let mut data = MAGIC_32.to_le_bytes().to_vec();
+        data.extend(0x1234u32.to_le_bytes());
+        data.extend(0x5678u32.to_le_bytes());
+
+        assert_eq!(parse(&data)?, vec![0x1234, 0x5678]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse(&[0x64]).is_err());
+        assert!(parse(&0u64.to_le_bytes()).is_err());
+
+        let mut data = MAGIC_64.to_le_bytes().to_vec();
+        data.extend(0x1234u32.to_le_bytes());
+        assert!(parse(&data).is_err());
+    }
+
+    #[test]
+    fn test_module_name() {
+        assert_eq!(
+            module_name(Path::new("fuzz.exe.1234.sancov")),
+            Some("fuzz.exe")
+        );
+        assert_eq!(
+            module_name(Path::new("/tmp/libfoo.so.42.sancov")),
+            Some("libfoo.so")
+        );
+        assert_eq!(module_name(Path::new("fuzz.exe.1234.txt")), None);
+        assert_eq!(module_name(Path::new("fuzz.exe.sancov")), None);
+        assert_eq!(module_name(Path::new("1234.sancov")), None);
+    }
+
+    #[test]
+    fn test_to_modoff() {
+        let mut coverage = ModuleCoverage::new();
+        coverage
+            .entry("fuzz.exe".to_owned())
+            .or_default()
+            .extend([0x20, 0x10, 0x20]);
+
+        assert_eq!(to_modoff(&coverage), "fuzz.exe+10\nfuzz.exe+20\n");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_add_sancov_env() {
+        let dir = Path::new("/tmp/cov");
+        let mut env = HashMap::new();
+        env.insert("ASAN_OPTIONS".to_owned(), "detect_leaks=0".to_owned());
+
+        add_sancov_env(&mut env, dir);
+
+        assert_eq!(
+            env["ASAN_OPTIONS"],
+            "detect_leaks=0:coverage=1:coverage_dir=/tmp/cov"
+        );
+        assert_eq!(env["UBSAN_OPTIONS"], "coverage=1:coverage_dir=/tmp/cov");
+    }
+}
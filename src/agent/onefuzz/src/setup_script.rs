@@ -0,0 +1,107 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::process::Command;
+
+use crate::process::Output;
+
+// Default to 59 minutes, just under the service's `NODE_EXPIRATION_TIME` of 1 hour.
+const DEFAULT_SETUP_SCRIPT_TIMEOUT: Duration = Duration::from_secs(59 * 60);
+
+const SETUP_PATH_ENV: &str = "ONEFUZZ_TARGET_SETUP_PATH";
+
+#[cfg(target_family = "windows")]
+const SETUP_SCRIPT: &str = "setup.ps1";
+
+#[cfg(target_family = "unix")]
+const SETUP_SCRIPT: &str = "setup.sh";
+
+pub struct SetupScript {
+    setup_dir: PathBuf,
+    script_path: PathBuf,
+}
+
+impl SetupScript {
+    /// The setup script of the setup directory, if it has one.
+    pub async fn new(setup_dir: impl AsRef<Path>) -> Result<Option<Self>> {
+        let script = Self::in_dir(setup_dir);
+
+        if crate::fs::exists(&script.script_path).await? {
+            Ok(Some(script))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The setup script a setup directory would have, whether or not it exists.
+    pub fn in_dir(setup_dir: impl AsRef<Path>) -> Self {
+        let setup_dir = setup_dir.as_ref().to_path_buf();
+        let script_path = setup_dir.join(SETUP_SCRIPT);
+
+        Self {
+            setup_dir,
+            script_path,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.script_path
+    }
+
+    pub async fn invoke(&self, timeout: impl Into<Option<Duration>>) -> Result<Output> {
+        let timeout = timeout.into().unwrap_or(DEFAULT_SETUP_SCRIPT_TIMEOUT);
+
+        let timed = tokio::time::timeout(timeout, self.setup_command().output())
+            .await
+            .context("setup script timed out")?;
+        let output = timed?.into();
+
+        Ok(output)
+    }
+
+    /// The command line the script is invoked with, including its environment.
+    pub fn command_line(&self) -> String {
+        let cmd = self.setup_command();
+        let cmd = cmd.as_std();
+
+        let mut words = vec![
+            format!("{}={}", SETUP_PATH_ENV, self.setup_dir.display()),
+            cmd.get_program().to_string_lossy().into_owned(),
+        ];
+        words.extend(cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()));
+
+        words.join(" ")
+    }
+
+    #[cfg(target_family = "windows")]
+    fn setup_command(&self) -> Command {
+        let mut cmd = Command::new("powershell.exe");
+
+        cmd.env(SETUP_PATH_ENV, &self.setup_dir);
+        cmd.arg("-ExecutionPolicy");
+        cmd.arg("Unrestricted");
+        cmd.arg("-File");
+        cmd.arg(&self.script_path);
+        cmd.stderr(Stdio::piped());
+        cmd.stdout(Stdio::piped());
+
+        cmd
+    }
+
+    #[cfg(target_family = "unix")]
+    fn setup_command(&self) -> Command {
+        let mut cmd = Command::new("bash");
+
+        cmd.env(SETUP_PATH_ENV, &self.setup_dir);
+        cmd.arg(&self.script_path);
+        cmd.stderr(Stdio::piped());
+        cmd.stdout(Stdio::piped());
+
+        cmd
+    }
+}
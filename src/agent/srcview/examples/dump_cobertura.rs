@@ -54,6 +54,6 @@ fn main() {
     // Format it as cobertura and display it
     // output is built incrementally so buffer it
     let mut buffered_stdout = BufWriter::new(stdout().lock());
-    r.cobertura(Some(r"E:\\1f\\coverage\\"), &mut buffered_stdout)
+    r.cobertura(Some(r"E:\\1f\\coverage\\"), None, &mut buffered_stdout)
         .unwrap();
 }
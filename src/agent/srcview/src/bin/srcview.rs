@@ -1,18 +1,79 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use anyhow::{format_err, Context, Result};
-use clap::Parser;
-use srcview::{ModOff, Report, SrcLine, SrcView};
+use anyhow::{bail, format_err, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use notify::{EventKind, RecursiveMode, Watcher};
+use quick_xml::escape::escape;
+use serde::Serialize;
+use srcview::{
+    DebugId, FuzzingTarget, ModOff, PdbCache, PdbStats, PublicSymbols, Report, SrcLine, SrcView,
+};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::{self, OpenOptions};
-use std::io::{stdout, BufWriter, Write};
+use std::io::{stdin, stdout, BufRead, BufReader, BufWriter, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, UNIX_EPOCH};
 
 #[derive(Parser, Debug)]
+struct Args {
+    /// when to color the output
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// never color the output, same as `--color never`
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    #[command(subcommand)]
+    opt: Opt,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ColorChoice {
+    Always,
+    /// color the output when writing to a terminal
+    Auto,
+    Never,
+}
+
+impl Args {
+    fn use_color(&self) -> bool {
+        if self.no_color {
+            return false;
+        }
+
+        match self.color {
+            ColorChoice::Always => true,
+            ColorChoice::Auto => stdout().is_terminal(),
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
 enum Opt {
     Srcloc(SrcLocOpt),
     PdbPaths(PdbPathsOpt),
+    PdbStats(PdbStatsOpt),
+    PdbValidate(PdbValidateOpt),
+    FuzzingTargets(FuzzingTargetsOpt),
+    AddressToSymbol(AddressToSymbolOpt),
+    ResolveAddr2line(ResolveAddr2lineOpt),
+    ExportPdb(ExportPdbOpt),
+    MergePdbDirs(MergePdbDirsOpt),
     Cobertura(CoberturaOpt),
+    CoverageSummary(CoverageSummaryOpt),
+    AnnotateSource(AnnotateSourceOpt),
+    Diff(DiffOpt),
+    Histogram(HistogramOpt),
+    HotFunctions(HotFunctionsOpt),
+    CoverageHeatMap(CoverageHeatMapOpt),
+    WatchCoverage(WatchCoverageOpt),
+    /// Convert modoff files between the text and binary formats
+    #[command(subcommand)]
+    Modoff(ModOffOpt),
     /// Print 3rd-party license information
     Licenses,
 }
@@ -21,6 +82,99 @@ enum Opt {
 #[derive(Parser, Debug)]
 struct PdbPathsOpt {
     pdb_path: PathBuf,
+
+    /// also print the number of instrumented source lines of each file, and
+    /// sort by it, most lines first
+    #[arg(long)]
+    line_numbers: bool,
+}
+
+/// Print the GUID, age and other metadata of the provided PDB
+///
+/// This does not load line tables, so it is a quick check that a PDB matches
+/// a binary before running the full coverage pipeline.
+#[derive(Parser, Debug)]
+struct PdbStatsOpt {
+    pdb_path: PathBuf,
+}
+
+/// Check that a PDB matches a binary by comparing their GUID and age
+///
+/// For PE files, these are read from the CodeView entry of the debug
+/// directory. For ELF files, the GUID is taken from the GNU build ID, with an
+/// age of 0.
+#[derive(Parser, Debug)]
+struct PdbValidateOpt {
+    pdb_path: PathBuf,
+    binary_path: PathBuf,
+}
+
+/// Print the functions in the provided PDB that are plausible fuzzing targets
+///
+/// These are libFuzzer harnesses, and functions whose first two arguments are
+/// a byte pointer and a size, like `(const uint8_t*, size_t)`. Each function
+/// is printed with its offset from the module's base.
+#[derive(Parser, Debug)]
+struct FuzzingTargetsOpt {
+    pdb_path: PathBuf,
+}
+
+/// Print the public symbol containing an address of the provided PDB's module
+///
+/// The symbol is the closest one at or before the address, and is printed with
+/// the offset of the address from it. Only the public symbol table is read, so
+/// this works with stripped PDBs.
+#[derive(Parser, Debug)]
+struct AddressToSymbolOpt {
+    pdb_path: PathBuf,
+
+    /// offset from the module's base, in hex with an optional 0x prefix
+    #[arg(value_parser = parse_hex_rva)]
+    rva: u32,
+}
+
+/// Resolve addresses to functions and source lines, like `addr2line -f`
+///
+/// Addresses are offsets from the base of the PDB's module, in hex with an
+/// optional 0x prefix. They are read from the arguments, or else from stdin,
+/// one per line. For each address, the function name and `file:line` are
+/// printed on separate lines, or `??` and `??:0` if the address can't be
+/// resolved. Output is flushed after each address, so that tools can use
+/// srcview interactively through a pipe, in place of `addr2line`.
+#[derive(Parser, Debug)]
+struct ResolveAddr2lineOpt {
+    #[arg(long, value_name = "PDB")]
+    pdb: PathBuf,
+
+    addresses: Vec<String>,
+}
+
+/// Export the symbol and line info of a PDB to a portable JSON file
+///
+/// The export can be loaded with `SrcView::insert_from_export`, without
+/// access to the PDB. The JSON is written to either a file or stdout if the
+/// argument is a single dash.
+#[derive(Parser, Debug)]
+struct ExportPdbOpt {
+    pdb_path: PathBuf,
+    #[arg(default_value = "-")]
+    output_path: String,
+    #[arg(long)]
+    module_name: Option<String>,
+}
+
+/// Export the symbol and line info of every PDB in a directory to one JSON
+/// mapping
+///
+/// Multi-module programs have one PDB per binary. Each PDB is inserted under
+/// the module names guessed from its file stem, as when no module name is
+/// given to the other subcommands. The mapping can be passed to `cobertura
+/// --mapping`, so that later runs do not load the PDBs again.
+#[derive(Parser, Debug)]
+struct MergePdbDirsOpt {
+    pdb_dir: PathBuf,
+    #[arg(long, value_name = "JSON")]
+    output_mapping: PathBuf,
 }
 
 /// Print modoffset file with file and source lines
@@ -30,6 +184,20 @@ struct SrcLocOpt {
     modoff_path: PathBuf,
     #[arg(long)]
     module_name: Option<String>,
+
+    /// rename a module after the PDB is loaded, in the form OLD=NEW. can be
+    /// specified multiple times
+    #[arg(long, value_parser = parse_rename)]
+    rename_module: Vec<(String, String)>,
+
+    /// stop after N source lines have been resolved. unresolved entries do not
+    /// count towards the limit
+    #[arg(long, value_name = "N")]
+    max_lines: Option<usize>,
+
+    /// print the name of the function containing each resolved source line
+    #[arg(long)]
+    show_function: bool,
 }
 
 /// Generate a Cobertura XML coverage report
@@ -54,6 +222,11 @@ struct CoberturaOpt {
     #[arg(long)]
     module_name: Option<String>,
 
+    /// rename a module after the PDB is loaded, in the form OLD=NEW. can be
+    /// specified multiple times
+    #[arg(long, value_parser = parse_rename)]
+    rename_module: Vec<(String, String)>,
+
     /// regular expression that will be applied against the file paths from the
     /// srcview
     #[arg(long)]
@@ -63,17 +236,252 @@ struct CoberturaOpt {
     /// paths that will appear in the output report
     #[arg(long)]
     filter_regex: Option<String>,
+
+    /// make all file paths in the output report relative to DIR, after
+    /// applying the filter regex. fails if a path is not inside DIR
+    #[arg(long, value_name = "DIR")]
+    source_root: Option<String>,
+
+    /// omit files without any covered lines from the report, including from
+    /// the total line count
+    #[arg(long)]
+    filter_zero_coverage: bool,
+
+    /// also count the source lines of each function inlined at a covered
+    /// offset as covered
+    #[arg(long)]
+    expand_inlines: bool,
+
+    /// stop loading the PDB after SECONDS and report on the partially loaded
+    /// data. the report notes that the analysis is incomplete
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+
+    /// load modules from a mapping written by `merge-pdb-dirs`. the PDB is
+    /// only loaded if its module is not in the mapping
+    #[arg(long, value_name = "FILE")]
+    mapping: Option<PathBuf>,
+}
+
+/// Print a table of the line coverage of each source file
+///
+/// Files are sorted by coverage percentage, so the least-covered files come
+/// first.
+#[derive(Parser, Debug)]
+struct CoverageSummaryOpt {
+    pdb_path: PathBuf,
+    modoff_path: PathBuf,
+    #[arg(long)]
+    module_name: Option<String>,
+
+    /// rename a module after the PDB is loaded, in the form OLD=NEW. can be
+    /// specified multiple times
+    #[arg(long, value_parser = parse_rename)]
+    rename_module: Vec<(String, String)>,
+
+    /// regular expression that will be applied against the file paths from the
+    /// srcview
+    #[arg(long)]
+    include_regex: Option<String>,
+
+    /// highlight files with a coverage percentage below PCT in red, if the
+    /// output is colored
+    #[arg(long, value_name = "PCT")]
+    min_coverage: Option<f64>,
+}
+
+/// Print the source lines covered by a second run that were not covered by
+/// the first
+///
+/// This is the coverage contributed by the second run, such as a new seed or
+/// corpus entry. One `path:line` is printed per line.
+#[derive(Parser, Debug)]
+struct DiffOpt {
+    pdb_path: PathBuf,
+    base_modoff_path: PathBuf,
+    modoff_path: PathBuf,
+    #[arg(long)]
+    module_name: Option<String>,
+
+    /// rename a module after the PDB is loaded, in the form OLD=NEW. can be
+    /// specified multiple times
+    #[arg(long, value_parser = parse_rename)]
+    rename_module: Vec<(String, String)>,
+
+    /// regular expression that will be applied against the file paths from the
+    /// srcview
+    #[arg(long)]
+    include_regex: Option<String>,
+}
+
+/// Print an ASCII histogram of the offsets of each module in a modoff file
+///
+/// Each module's address space is divided into buckets of BUCKET_SIZE bytes,
+/// and the number of offsets in each bucket is drawn as a bar. Empty buckets
+/// are regions of code that were never reached.
+///
+/// The address space of the module matching the PDB extends to the end of its
+/// image, so unreached code at the end of the image is shown. Other modules
+/// extend to their largest offset.
+#[derive(Parser, Debug)]
+struct HistogramOpt {
+    pdb_path: PathBuf,
+    modoff_path: PathBuf,
+
+    /// name of the module the PDB is for. defaults to any module with the
+    /// same file stem as the PDB
+    #[arg(long)]
+    module_name: Option<String>,
+
+    #[arg(long, value_name = "BUCKET_SIZE", default_value_t = 0x1000)]
+    bucket_size: usize,
+}
+
+/// Print the functions with the most distinct offsets in a modoff file
+///
+/// Unlike line coverage, this shows how much of each function's code was
+/// exercised, so the least-hit functions are those that need more diverse
+/// inputs. Each function is printed as `module!function` after its hit count.
+#[derive(Parser, Debug)]
+struct HotFunctionsOpt {
+    pdb_path: PathBuf,
+    modoff_path: PathBuf,
+    #[arg(long)]
+    module_name: Option<String>,
+
+    /// rename a module after the PDB is loaded, in the form OLD=NEW. can be
+    /// specified multiple times
+    #[arg(long, value_parser = parse_rename)]
+    rename_module: Vec<(String, String)>,
+
+    /// print only the N most hit functions
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    top: usize,
+}
+
+/// Write an SVG heat map of coverage density to a file
+///
+/// Each module is drawn as a horizontal bar, divided into buckets of
+/// BUCKET_SIZE bytes like `histogram`. Buckets are colored by the number of
+/// times their offsets appear in the modoff file, from blue for code that was
+/// never reached to red for the most hit bucket of the module. Hovering a
+/// bucket shows its address range, hit count, and the functions in it.
+#[derive(Parser, Debug)]
+struct CoverageHeatMapOpt {
+    pdb_path: PathBuf,
+    modoff_path: PathBuf,
+
+    #[arg(long, value_name = "SVG")]
+    output: PathBuf,
+
+    /// name of the module the PDB is for. defaults to any module with the
+    /// same file stem as the PDB
+    #[arg(long)]
+    module_name: Option<String>,
+
+    #[arg(long, value_name = "BUCKET_SIZE", default_value_t = 0x1000)]
+    bucket_size: usize,
+}
+
+/// Print a source file annotated with coverage
+///
+/// Covered lines are prefixed with '>' and all other lines with ' '.
+///
+/// PDBs record absolute paths from the build machine, so the source file is
+/// matched against them by its trailing path components, ignoring case and
+/// path separator style. Pass the source path relative to the build root, such
+/// as `src/main.c`, to avoid matching a different file with the same name.
+#[derive(Parser, Debug)]
+struct AnnotateSourceOpt {
+    pdb_path: PathBuf,
+    modoff_path: PathBuf,
+    source_path: PathBuf,
+    #[arg(long)]
+    module_name: Option<String>,
+
+    /// rename a module after the PDB is loaded, in the form OLD=NEW. can be
+    /// specified multiple times
+    #[arg(long, value_parser = parse_rename)]
+    rename_module: Vec<(String, String)>,
+
+    #[arg(long, value_enum, default_value_t = AnnotateFormat::Text)]
+    format: AnnotateFormat,
+}
+
+/// Watch a directory of modoff files and print the total line coverage as
+/// JSON whenever a file is added, changed or removed
+///
+/// Each update is a single line:
+///   {"timestamp":"...","files":N,"covered_lines":N,"total_lines":N,"percent":N.NN}
+///
+/// The current coverage is printed once at startup, before watching.
+#[derive(Parser, Debug)]
+struct WatchCoverageOpt {
+    pdb_path: PathBuf,
+    coverage_dir: PathBuf,
+    #[arg(long)]
+    module_name: Option<String>,
+
+    /// rename a module after the PDB is loaded, in the form OLD=NEW. can be
+    /// specified multiple times
+    #[arg(long, value_parser = parse_rename)]
+    rename_module: Vec<(String, String)>,
+
+    /// regular expression that will be applied against the file paths from the
+    /// srcview
+    #[arg(long)]
+    include_regex: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum AnnotateFormat {
+    Text,
+    Html,
+}
+
+#[derive(Subcommand, Debug)]
+enum ModOffOpt {
+    /// Convert a text modoff file to the binary format
+    ToBinary(ModOffConvertOpt),
+    /// Convert a binary modoff file to the text format
+    FromBinary(ModOffConvertOpt),
+}
+
+/// The input and output are read from stdin and written to stdout,
+/// respectively, if the argument is a single dash.
+#[derive(Parser, Debug)]
+struct ModOffConvertOpt {
+    #[arg(default_value = "-")]
+    input_path: String,
+    #[arg(default_value = "-")]
+    output_path: String,
 }
 
 fn main() -> Result<()> {
     env_logger::init();
 
-    let opt = Opt::parse();
+    let args = Args::parse();
+    let use_color = args.use_color();
 
-    match opt {
+    match args.opt {
         Opt::Srcloc(opts) => srcloc(opts)?,
         Opt::PdbPaths(opts) => pdb_paths(opts)?,
+        Opt::PdbStats(opts) => pdb_stats(opts)?,
+        Opt::PdbValidate(opts) => pdb_validate(opts)?,
+        Opt::FuzzingTargets(opts) => fuzzing_targets(opts)?,
+        Opt::AddressToSymbol(opts) => address_to_symbol(opts)?,
+        Opt::ResolveAddr2line(opts) => resolve_addr2line(opts)?,
+        Opt::ExportPdb(opts) => export_pdb(opts)?,
+        Opt::MergePdbDirs(opts) => merge_pdb_dirs(opts)?,
         Opt::Cobertura(opts) => cobertura(opts)?,
+        Opt::CoverageSummary(opts) => coverage_summary(opts, use_color)?,
+        Opt::AnnotateSource(opts) => annotate_source(opts)?,
+        Opt::Diff(opts) => diff(opts)?,
+        Opt::Histogram(opts) => histogram(opts)?,
+        Opt::HotFunctions(opts) => hot_functions(opts)?,
+        Opt::CoverageHeatMap(opts) => coverage_heat_map(opts)?,
+        Opt::WatchCoverage(opts) => watch_coverage(opts)?,
+        Opt::Modoff(opts) => modoff(opts)?,
         Opt::Licenses => licenses()?,
     };
 
@@ -90,7 +498,26 @@ fn licenses() -> Result<()> {
 //
 // This is a last-ditch effort to ensure the coverage report has something
 // consumable.
-fn add_common_extensions(srcview: &mut SrcView, pdb_path: &Path) -> Result<()> {
+fn add_common_extensions(
+    srcview: &mut SrcView,
+    pdb_path: &Path,
+    timeout: Option<Duration>,
+) -> Result<()> {
+    let stem = pdb_stem(pdb_path)?;
+
+    // load the pdb once, as it is the same for every name
+    let cache = load_pdb(pdb_path, timeout)?;
+
+    // add module without extension
+    srcview.insert_cache(&stem, cache.clone());
+    // add common module extensions
+    for ext in ["sys", "exe", "dll"] {
+        srcview.insert_cache(&format!("{stem}.{ext}"), cache.clone());
+    }
+    Ok(())
+}
+
+fn pdb_stem(pdb_path: &Path) -> Result<String> {
     let pdb_file_name = pdb_path.file_name().ok_or_else(|| {
         format_err!(
             "unable to identify file name from path: {}",
@@ -106,56 +533,63 @@ fn add_common_extensions(srcview: &mut SrcView, pdb_path: &Path) -> Result<()> {
                 pdb_path.display()
             )
         })?
-        .to_string_lossy();
+        .to_string_lossy()
+        .into_owned();
 
-    // add module without extension
-    srcview.insert(&stem, pdb_path)?;
-    // add common module extensions
-    for ext in ["sys", "exe", "dll"] {
-        srcview.insert(&format!("{stem}.{ext}"), pdb_path)?;
-    }
-    Ok(())
+    Ok(stem)
 }
 
-fn srcloc(opts: SrcLocOpt) -> Result<()> {
-    let modoff_data = fs::read_to_string(&opts.modoff_path)
-        .with_context(|| format!("unable to read modoff_path: {}", opts.modoff_path.display()))?;
-    let modoffs = ModOff::parse(&modoff_data)?;
-    let mut srcview = SrcView::new();
+// Load a PDB, reusing the pdb info saved next to it in a `.srcview_cache` file by an
+// earlier run. The info is cached under the PDB's modification time, so the cache is
+// ignored and rewritten once the PDB changes.
+fn load_pdb(pdb_path: &Path, timeout: Option<Duration>) -> Result<PdbCache> {
+    let cache_path = pdb_path.with_extension("srcview_cache");
+    let key = modified_key(pdb_path);
 
-    if let Some(module_name) = &opts.module_name {
-        srcview.insert(module_name, &opts.pdb_path)?;
-    } else {
-        add_common_extensions(&mut srcview, &opts.pdb_path)?;
+    if let Some(key) = &key {
+        if let Ok(cached) = SrcView::load(&cache_path) {
+            if let Some(cache) = cached.pdb_cache(key) {
+                return Ok(cache.clone());
+            }
+        }
     }
 
-    for modoff in &modoffs {
-        print!(" +{:04x} ", modoff.offset);
-        match srcview.modoff(modoff) {
-            Some(srcloc) => println!("{srcloc}"),
-            None => println!(),
+    let cache = PdbCache::new_with_timeout(pdb_path, timeout)?;
+
+    // a partially loaded PDB is not cached, so a later run can load all of it
+    if let Some(key) = key.filter(|_| cache.is_complete()) {
+        let mut cached = SrcView::new();
+        cached.insert_cache(&key, cache.clone());
+        if let Err(err) = cached.save(&cache_path) {
+            log::warn!("unable to cache PDB info: {:?}", err);
         }
     }
 
-    Ok(())
+    Ok(cache)
 }
 
-fn pdb_paths(opts: PdbPathsOpt) -> Result<()> {
-    let mut srcview = SrcView::new();
-    srcview.insert(&opts.pdb_path.to_string_lossy(), &opts.pdb_path)?;
-
-    for path in srcview.paths() {
-        println!("{}", path.display());
-    }
-    Ok(())
+fn modified_key(path: &Path) -> Option<String> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let since_epoch = modified.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!(
+        "{}.{:09}",
+        since_epoch.as_secs(),
+        since_epoch.subsec_nanos()
+    ))
 }
 
-fn cobertura(opts: CoberturaOpt) -> Result<()> {
-    // read our modoff file and parse it to a vector
-    let modoff_data = fs::read_to_string(&opts.modoff_path)?;
-    let modoffs = ModOff::parse(&modoff_data)?;
+fn open_input(path: &str) -> Result<Box<dyn Read>> {
+    let reader = match path {
+        "-" => Box::new(BufReader::new(stdin())) as Box<dyn Read>,
+        path => Box::new(BufReader::new(
+            fs::File::open(path).with_context(|| format!("unable to open input: {path}"))?,
+        )) as Box<dyn Read>,
+    };
+    Ok(reader)
+}
 
-    let mut output_writer = match opts.output_path.as_str() {
+fn open_output(path: &str) -> Result<Box<dyn Write>> {
+    let writer = match path {
         "-" => Box::new(BufWriter::new(stdout())) as Box<dyn Write>,
         path => {
             let path = Path::new(path);
@@ -170,28 +604,888 @@ fn cobertura(opts: CoberturaOpt) -> Result<()> {
             )) as Box<dyn Write>
         }
     };
+    Ok(writer)
+}
 
-    // create our new SrcView and insert our only pdb into it
-    // we don't know what the modoff module will be, so create a mapping from
-    // all likely names to the pdb
+fn parse_rename(rename: &str) -> Result<(String, String)> {
+    let (old, new) = rename
+        .split_once('=')
+        .ok_or_else(|| format_err!("expected OLD=NEW, got: {}", rename))?;
+    Ok((old.to_owned(), new.to_owned()))
+}
+
+fn parse_hex_rva(rva: &str) -> Result<u32> {
+    let digits = rva.strip_prefix("0x").unwrap_or(rva);
+    u32::from_str_radix(digits, 16).with_context(|| format!("invalid hex RVA: {rva}"))
+}
+
+fn rename_modules(srcview: &mut SrcView, renames: &[(String, String)]) -> Result<()> {
+    for (old, new) in renames {
+        srcview
+            .rename_module(old, new)
+            .with_context(|| format!("unable to rename module {old} to {new}"))?;
+    }
+    Ok(())
+}
+
+fn srcloc(opts: SrcLocOpt) -> Result<()> {
+    let modoff_data = fs::read_to_string(&opts.modoff_path)
+        .with_context(|| format!("unable to read modoff_path: {}", opts.modoff_path.display()))?;
+    let modoffs = ModOff::parse(&modoff_data)?;
     let mut srcview = SrcView::new();
 
     if let Some(module_name) = &opts.module_name {
-        srcview.insert(module_name, &opts.pdb_path)?;
+        srcview.insert_cache(module_name, load_pdb(&opts.pdb_path, None)?);
     } else {
-        add_common_extensions(&mut srcview, &opts.pdb_path)?;
+        add_common_extensions(&mut srcview, &opts.pdb_path, None)?;
     }
 
-    // Convert our ModOffs to SrcLine so we can draw it
-    let coverage: Vec<SrcLine> = modoffs
-        .into_iter()
-        .filter_map(|m| srcview.modoff(&m))
-        .collect();
+    rename_modules(&mut srcview, &opts.rename_module)?;
 
-    // Generate our report, filtering on our example path
-    let r = Report::new(&coverage, &srcview, opts.include_regex.as_deref())?;
+    let mut resolved = 0;
+    for modoff in &modoffs {
+        if opts.max_lines.map_or(false, |max| resolved >= max) {
+            break;
+        }
+
+        print!(" +{:04x} ", modoff.offset);
+        match srcview.modoff(modoff) {
+            Some(srcloc) => {
+                let function = opts
+                    .show_function
+                    .then(|| srcview.function_for_offset(&modoff.module, modoff.offset as u64))
+                    .flatten();
+
+                match function {
+                    Some(function) => println!("{srcloc} ({function})"),
+                    None => println!("{srcloc}"),
+                }
+                resolved += 1;
+            }
+            None => println!(),
+        }
+    }
+
+    Ok(())
+}
+
+fn diff(opts: DiffOpt) -> Result<()> {
+    let mut srcview = SrcView::new();
+
+    if let Some(module_name) = &opts.module_name {
+        srcview.insert_cache(module_name, load_pdb(&opts.pdb_path, None)?);
+    } else {
+        add_common_extensions(&mut srcview, &opts.pdb_path, None)?;
+    }
+
+    rename_modules(&mut srcview, &opts.rename_module)?;
+
+    let report = |modoff_path: &Path| -> Result<Report> {
+        let modoff_data = fs::read_to_string(modoff_path)
+            .with_context(|| format!("unable to read modoff_path: {}", modoff_path.display()))?;
+        let coverage: Vec<SrcLine> = ModOff::parse(&modoff_data)?
+            .into_iter()
+            .filter_map(|m| srcview.modoff(&m))
+            .collect();
+        Report::new(&coverage, &srcview, opts.include_regex.as_deref())
+    };
+
+    let base = report(&opts.base_modoff_path)?;
+    let new = report(&opts.modoff_path)?;
+
+    let mut out = BufWriter::new(stdout().lock());
+    for srcloc in new.subtract(&base).covered_lines() {
+        writeln!(out, "{srcloc}")?;
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+fn histogram(opts: HistogramOpt) -> Result<()> {
+    const BAR_WIDTH: usize = 50;
+
+    if opts.bucket_size == 0 {
+        bail!("bucket size must be greater than 0");
+    }
+
+    let modoff_data = fs::read_to_string(&opts.modoff_path)
+        .with_context(|| format!("unable to read modoff_path: {}", opts.modoff_path.display()))?;
+    let modoffs = ModOff::parse(&modoff_data)?;
+
+    let stats = PdbStats::new(&opts.pdb_path)
+        .with_context(|| format!("unable to read PDB: {}", opts.pdb_path.display()))?;
+
+    let mut modules: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for modoff in &modoffs {
+        modules
+            .entry(modoff.module.as_str())
+            .or_default()
+            .push(modoff.offset);
+    }
+
+    let mut out = BufWriter::new(stdout().lock());
+
+    for (module, offsets) in modules {
+        let max_offset = offsets.iter().copied().max().unwrap_or_default();
+        let mut extent = max_offset + 1;
+        if is_pdb_module(&opts.pdb_path, opts.module_name.as_deref(), module) {
+            extent = extent.max(stats.image_size as usize);
+        }
+
+        let mut buckets = vec![0usize; (extent + opts.bucket_size - 1) / opts.bucket_size];
+        for offset in &offsets {
+            buckets[offset / opts.bucket_size] += 1;
+        }
+
+        let empty = buckets.iter().filter(|count| **count == 0).count();
+        writeln!(
+            out,
+            "{module} ({} offsets, {} of {} buckets empty)",
+            offsets.len(),
+            empty,
+            buckets.len()
+        )?;
+
+        let max_count = buckets.iter().copied().max().unwrap_or_default();
+        for (i, count) in buckets.iter().enumerate() {
+            // round up, so that no non-empty bucket is drawn as empty
+            let width = (count * BAR_WIDTH + max_count - 1) / max_count;
+            writeln!(
+                out,
+                "  {:#010x} | {:<BAR_WIDTH$} {count}",
+                i * opts.bucket_size,
+                "#".repeat(width)
+            )?;
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+// Whether a module in a modoff file is the one the PDB is for: the given module name, or
+// else any module with the same file stem as the PDB.
+fn is_pdb_module(pdb_path: &Path, module_name: Option<&str>, module: &str) -> bool {
+    if let Some(module_name) = module_name {
+        return module == module_name;
+    }
+
+    let pdb_stem = pdb_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase());
+    let module = ModOff::normalize_module_name(module);
+    let module_stem = Path::new(&module)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned());
+    module_stem == pdb_stem
+}
+
+fn coverage_heat_map(opts: CoverageHeatMapOpt) -> Result<()> {
+    const BAR_WIDTH: f64 = 1000.0;
+    const BAR_HEIGHT: usize = 24;
+    const LABEL_HEIGHT: usize = 20;
+    const MARGIN: usize = 10;
+
+    if opts.bucket_size == 0 {
+        bail!("bucket size must be greater than 0");
+    }
+
+    let modoff_data = fs::read_to_string(&opts.modoff_path)
+        .with_context(|| format!("unable to read modoff_path: {}", opts.modoff_path.display()))?;
+    let modoffs = ModOff::parse(&modoff_data)?;
+
+    let stats = PdbStats::new(&opts.pdb_path)
+        .with_context(|| format!("unable to read PDB: {}", opts.pdb_path.display()))?;
+
+    let mut srcview = SrcView::new();
+    if let Some(module_name) = &opts.module_name {
+        srcview.insert_cache(module_name, load_pdb(&opts.pdb_path, None)?);
+    } else {
+        add_common_extensions(&mut srcview, &opts.pdb_path, None)?;
+    }
+
+    // hits of each offset, by module
+    let mut modules: BTreeMap<&str, BTreeMap<usize, usize>> = BTreeMap::new();
+    for modoff in &modoffs {
+        *modules
+            .entry(modoff.module.as_str())
+            .or_default()
+            .entry(modoff.offset)
+            .or_default() += 1;
+    }
+
+    let row_height = LABEL_HEIGHT + BAR_HEIGHT + MARGIN;
+    let height = MARGIN + modules.len() * row_height;
+    let width = BAR_WIDTH as usize + 2 * MARGIN;
+
+    let mut out = BufWriter::new(
+        fs::File::create(&opts.output)
+            .with_context(|| format!("unable to create output: {}", opts.output.display()))?,
+    );
+
+    writeln!(
+        out,
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" "#,
+            r#"font-family="monospace" font-size="12">"#
+        ),
+        width, height
+    )?;
+    writeln!(
+        out,
+        r#"<rect width="{width}" height="{height}" fill="white"/>"#
+    )?;
+
+    for (row, (module, offsets)) in modules.iter().enumerate() {
+        let max_offset = offsets.keys().next_back().copied().unwrap_or_default();
+        let mut extent = max_offset + 1;
+        if is_pdb_module(&opts.pdb_path, opts.module_name.as_deref(), module) {
+            extent = extent.max(stats.image_size as usize);
+        }
+
+        let bucket_count = (extent + opts.bucket_size - 1) / opts.bucket_size;
+        let mut buckets = vec![0usize; bucket_count];
+        let mut functions = vec![BTreeSet::new(); bucket_count];
+        for (offset, hits) in offsets {
+            let bucket = offset / opts.bucket_size;
+            buckets[bucket] += hits;
+            if let Some(function) = srcview.function_for_offset(module, *offset as u64) {
+                functions[bucket].insert(function);
+            }
+        }
+
+        let y = MARGIN + row * row_height;
+        let total_hits: usize = buckets.iter().sum();
+        writeln!(
+            out,
+            r#"<text x="{MARGIN}" y="{}">{} ({} offsets, {total_hits} hits)</text>"#,
+            y + LABEL_HEIGHT - 6,
+            escape(*module),
+            offsets.len(),
+        )?;
+
+        let max_hits = buckets.iter().copied().max().unwrap_or_default();
+        let bucket_width = BAR_WIDTH / bucket_count as f64;
+        for (i, hits) in buckets.iter().enumerate() {
+            let start = i * opts.bucket_size;
+
+            // unreached buckets have no hit offsets, but may still be in a function
+            if let Some(function) = srcview.function_for_offset(module, start as u64) {
+                functions[i].insert(function);
+            }
+
+            let mut title = format!(
+                "{module}+{start:x}..{:x}: {hits} hits",
+                start + opts.bucket_size
+            );
+            for function in &functions[i] {
+                title.push('\n');
+                title.push_str(function);
+            }
+
+            writeln!(
+                out,
+                concat!(
+                    r#"<rect x="{:.3}" y="{}" width="{:.3}" height="{}" fill="{}">"#,
+                    "<title>{}</title></rect>"
+                ),
+                MARGIN as f64 + i as f64 * bucket_width,
+                y + LABEL_HEIGHT,
+                bucket_width,
+                BAR_HEIGHT,
+                heat_color(*hits, max_hits),
+                escape(title.as_str()),
+            )?;
+        }
+    }
+
+    writeln!(out, "</svg>")?;
+    out.flush()?;
+
+    Ok(())
+}
+
+// Color of a heat map bucket, from blue for no hits to red for `max_hits`. Hit counts are
+// log-scaled, so a few very hot buckets don't leave every other bucket looking cold.
+fn heat_color(hits: usize, max_hits: usize) -> String {
+    let heat = if hits == 0 || max_hits == 0 {
+        0.0
+    } else {
+        (hits as f64).ln_1p() / (max_hits as f64).ln_1p()
+    };
+
+    let red = (255.0 * heat).round() as u8;
+    let blue = (255.0 * (1.0 - heat)).round() as u8;
+    format!("#{red:02x}00{blue:02x}")
+}
+
+fn hot_functions(opts: HotFunctionsOpt) -> Result<()> {
+    let modoff_data = fs::read_to_string(&opts.modoff_path)
+        .with_context(|| format!("unable to read modoff_path: {}", opts.modoff_path.display()))?;
+    let modoffs = ModOff::parse(&modoff_data)?;
+    let mut srcview = SrcView::new();
+
+    if let Some(module_name) = &opts.module_name {
+        srcview.insert_cache(module_name, load_pdb(&opts.pdb_path, None)?);
+    } else {
+        add_common_extensions(&mut srcview, &opts.pdb_path, None)?;
+    }
+
+    rename_modules(&mut srcview, &opts.rename_module)?;
+
+    let mut out = BufWriter::new(stdout().lock());
+    for (function, hits) in srcview.function_hit_counts(&modoffs).iter().take(opts.top) {
+        writeln!(out, "{hits:>8} {function}")?;
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+fn pdb_paths(opts: PdbPathsOpt) -> Result<()> {
+    let mut srcview = SrcView::new();
+    srcview.insert(&opts.pdb_path.to_string_lossy(), &opts.pdb_path)?;
+
+    if !opts.line_numbers {
+        for path in srcview.paths() {
+            println!("{}", path.display());
+        }
+        return Ok(());
+    }
+
+    let mut paths: Vec<(&PathBuf, usize)> = srcview
+        .paths()
+        .map(|path| {
+            let lines = srcview.path_lines(path).map_or(0, Iterator::count);
+            (path, lines)
+        })
+        .collect();
+    // stable, so files with the same count stay ordered by path
+    paths.sort_by(|a, b| b.1.cmp(&a.1));
+
+    for (path, lines) in paths {
+        println!("{} ({} lines)", path.display(), lines);
+    }
+    Ok(())
+}
+
+fn pdb_stats(opts: PdbStatsOpt) -> Result<()> {
+    let stats = PdbStats::new(&opts.pdb_path)
+        .with_context(|| format!("unable to read PDB: {}", opts.pdb_path.display()))?;
+    println!("{stats}");
+    Ok(())
+}
+
+fn pdb_validate(opts: PdbValidateOpt) -> Result<()> {
+    let pdb = DebugId::from_pdb(&opts.pdb_path)
+        .with_context(|| format!("unable to read PDB: {}", opts.pdb_path.display()))?;
+    let binary = DebugId::from_binary(&opts.binary_path).with_context(|| {
+        format!(
+            "unable to read debug id of binary: {}",
+            opts.binary_path.display()
+        )
+    })?;
+
+    let mismatches = pdb.mismatches(&binary);
+    if !mismatches.is_empty() {
+        for mismatch in &mismatches {
+            println!("{mismatch}");
+        }
+        bail!(
+            "{} does not match {}",
+            opts.pdb_path.display(),
+            opts.binary_path.display()
+        );
+    }
+
+    println!("PDB matches binary: {pdb}");
+    Ok(())
+}
+
+fn fuzzing_targets(opts: FuzzingTargetsOpt) -> Result<()> {
+    let targets = FuzzingTarget::find(&opts.pdb_path)
+        .with_context(|| format!("unable to read PDB: {}", opts.pdb_path.display()))?;
+    for target in targets {
+        println!("{target}");
+    }
+    Ok(())
+}
+
+fn address_to_symbol(opts: AddressToSymbolOpt) -> Result<()> {
+    let symbols = PublicSymbols::new(&opts.pdb_path)
+        .with_context(|| format!("unable to read PDB: {}", opts.pdb_path.display()))?;
+
+    match symbols.lookup(opts.rva) {
+        Some(symbol) => println!("{symbol}"),
+        None => bail!("no public symbol at or before {:#x}", opts.rva),
+    }
+    Ok(())
+}
+
+fn resolve_addr2line(opts: ResolveAddr2lineOpt) -> Result<()> {
+    let cache = load_pdb(&opts.pdb, None)?;
+    let mut out = stdout().lock();
+
+    let mut resolve = |address: &str| -> Result<()> {
+        let offset = parse_hex_rva(address.trim()).ok().map(|rva| rva as usize);
+        let function = offset.and_then(|offset| cache.function(offset));
+        let line = offset.and_then(|offset| cache.line_containing(offset));
+
+        writeln!(out, "{}", function.unwrap_or("??"))?;
+        match line {
+            Some(line) => writeln!(out, "{line}")?,
+            None => writeln!(out, "??:0")?,
+        }
+        out.flush()?;
+
+        Ok(())
+    };
+
+    if !opts.addresses.is_empty() {
+        for address in &opts.addresses {
+            resolve(address)?;
+        }
+        return Ok(());
+    }
+
+    for line in stdin().lock().lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            resolve(&line)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn export_pdb(opts: ExportPdbOpt) -> Result<()> {
+    let mut srcview = SrcView::new();
+
+    if let Some(module_name) = &opts.module_name {
+        srcview.insert_cache(module_name, load_pdb(&opts.pdb_path, None)?);
+    } else {
+        add_common_extensions(&mut srcview, &opts.pdb_path, None)?;
+    }
+
+    let mut output_writer = open_output(&opts.output_path)?;
+    srcview.export(&mut output_writer)?;
+    output_writer.flush()?;
+    Ok(())
+}
+
+fn merge_pdb_dirs(opts: MergePdbDirsOpt) -> Result<()> {
+    let entries = fs::read_dir(&opts.pdb_dir)
+        .with_context(|| format!("unable to read pdb_dir: {}", opts.pdb_dir.display()))?;
+
+    let mut pdb_paths = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        let is_pdb = path
+            .extension()
+            .map_or(false, |ext| ext.eq_ignore_ascii_case("pdb"));
+        if is_pdb && path.is_file() {
+            pdb_paths.push(path);
+        }
+    }
+    pdb_paths.sort();
+
+    if pdb_paths.is_empty() {
+        bail!("no PDBs found in {}", opts.pdb_dir.display());
+    }
+
+    let mut srcview = SrcView::new();
+    for pdb_path in &pdb_paths {
+        add_common_extensions(&mut srcview, pdb_path, None)
+            .with_context(|| format!("unable to load PDB: {}", pdb_path.display()))?;
+    }
+
+    let output = fs::File::create(&opts.output_mapping).with_context(|| {
+        format!(
+            "unable to create output_mapping: {}",
+            opts.output_mapping.display()
+        )
+    })?;
+    let mut output_writer = BufWriter::new(output);
+    srcview.export(&mut output_writer)?;
+    output_writer.flush()?;
+
+    eprintln!(
+        "merged {} PDBs into {}",
+        pdb_paths.len(),
+        opts.output_mapping.display()
+    );
+    Ok(())
+}
+
+fn cobertura(opts: CoberturaOpt) -> Result<()> {
+    // read our modoff file and parse it to a vector
+    let modoff_data = fs::read_to_string(&opts.modoff_path)?;
+    let modoffs = ModOff::parse(&modoff_data)?;
+
+    let mut output_writer = open_output(&opts.output_path)?;
+
+    // create our new SrcView and insert our only pdb into it
+    // we don't know what the modoff module will be, so create a mapping from
+    // all likely names to the pdb
+    let mut srcview = SrcView::new();
+
+    if let Some(mapping) = &opts.mapping {
+        srcview.insert_from_export(mapping)?;
+    }
+
+    let timeout = opts.timeout.map(Duration::from_secs);
+    if let Some(module_name) = &opts.module_name {
+        if srcview.pdb_cache(module_name).is_none() {
+            srcview.insert_cache(module_name, load_pdb(&opts.pdb_path, timeout)?);
+        }
+    } else if srcview.pdb_cache(&pdb_stem(&opts.pdb_path)?).is_none() {
+        add_common_extensions(&mut srcview, &opts.pdb_path, timeout)?;
+    }
+
+    rename_modules(&mut srcview, &opts.rename_module)?;
+
+    let incomplete_modules = srcview.incomplete_modules();
+    if !incomplete_modules.is_empty() {
+        eprintln!(
+            "warning: timed out loading the PDB, continuing with partial data for modules: {}",
+            incomplete_modules.join(", ")
+        );
+    }
+
+    // Convert our ModOffs to SrcLine so we can draw it
+    let mut coverage: Vec<SrcLine> = modoffs.iter().filter_map(|m| srcview.modoff(m)).collect();
+
+    if opts.expand_inlines {
+        for m in &modoffs {
+            let sites = srcview.inline_sites(&m.module, m.offset as u64);
+            coverage.extend(
+                sites
+                    .into_iter()
+                    .map(|site| SrcLine::new(site.file, site.line as usize)),
+            );
+        }
+    }
+
+    // Generate our report, filtering on our example path
+    let mut r = Report::new(&coverage, &srcview, opts.include_regex.as_deref())?;
+
+    if opts.filter_zero_coverage {
+        r.filter_zero_coverage();
+    }
+
+    // Format it as cobertura and display it
+    r.cobertura(
+        opts.filter_regex.as_deref(),
+        opts.source_root.as_deref(),
+        &mut output_writer,
+    )?;
+
+    let unused_modules = unused_pdb_modules(&srcview);
+    if !unused_modules.is_empty() {
+        eprintln!(
+            "warning: no coverage was found for modules, their PDBs could be left out: {}",
+            unused_modules.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+// The modules of `srcview` that no coverage was resolved against. A PDB inserted
+// under several guessed names (see `add_common_extensions`) is used if any of its
+// names is, so only PDBs without any coverage are listed.
+fn unused_pdb_modules(srcview: &SrcView) -> Vec<String> {
+    fn stem(module: &str) -> Option<String> {
+        Path::new(module)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+    }
+
+    let unused = srcview.unused_modules();
+    let used_stems: BTreeSet<Option<String>> = srcview
+        .modules()
+        .filter(|module| !unused.iter().any(|unused| unused == module))
+        .map(stem)
+        .collect();
+
+    unused
+        .into_iter()
+        .filter(|module| !used_stems.contains(&stem(module)))
+        .collect()
+}
+
+fn coverage_summary(opts: CoverageSummaryOpt, use_color: bool) -> Result<()> {
+    let modoff_data = fs::read_to_string(&opts.modoff_path)
+        .with_context(|| format!("unable to read modoff_path: {}", opts.modoff_path.display()))?;
+    let modoffs = ModOff::parse(&modoff_data)?;
+
+    let mut srcview = SrcView::new();
+
+    if let Some(module_name) = &opts.module_name {
+        srcview.insert_cache(module_name, load_pdb(&opts.pdb_path, None)?);
+    } else {
+        add_common_extensions(&mut srcview, &opts.pdb_path, None)?;
+    }
+
+    rename_modules(&mut srcview, &opts.rename_module)?;
+
+    let coverage: Vec<SrcLine> = modoffs
+        .into_iter()
+        .filter_map(|m| srcview.modoff(&m))
+        .collect();
+
+    let r = Report::new(&coverage, &srcview, opts.include_regex.as_deref())?;
+
+    let mut rows: Vec<(String, usize, usize, f64)> = r
+        .file_summary()
+        .map(|(path, lines, covered)| {
+            let percent = if lines == 0 {
+                0.0
+            } else {
+                100.0 * covered as f64 / lines as f64
+            };
+            (path.display().to_string(), lines, covered, percent)
+        })
+        .collect();
+    rows.sort_by(|a, b| a.3.total_cmp(&b.3));
+
+    let width = rows
+        .iter()
+        .map(|(path, ..)| path.len())
+        .chain(std::iter::once("File".len()))
+        .max()
+        .unwrap_or_default();
+
+    let mut out = BufWriter::new(stdout().lock());
+
+    writeln!(out, "{:<width$} | Lines | Covered |      %", "File")?;
+    for (path, lines, covered, percent) in rows {
+        let row = format!("{path:<width$} | {lines:>5} | {covered:>7} | {percent:>6.2}");
+
+        match opts.min_coverage {
+            Some(min) if use_color && percent < min => writeln!(out, "\x1b[31m{row}\x1b[0m")?,
+            _ => writeln!(out, "{row}")?,
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+fn annotate_source(opts: AnnotateSourceOpt) -> Result<()> {
+    let modoff_data = fs::read_to_string(&opts.modoff_path)
+        .with_context(|| format!("unable to read modoff_path: {}", opts.modoff_path.display()))?;
+    let modoffs = ModOff::parse(&modoff_data)?;
+    let source = fs::read_to_string(&opts.source_path)
+        .with_context(|| format!("unable to read source_path: {}", opts.source_path.display()))?;
+
+    let mut srcview = SrcView::new();
+
+    if let Some(module_name) = &opts.module_name {
+        srcview.insert_cache(module_name, load_pdb(&opts.pdb_path, None)?);
+    } else {
+        add_common_extensions(&mut srcview, &opts.pdb_path, None)?;
+    }
+
+    rename_modules(&mut srcview, &opts.rename_module)?;
+
+    let covered: BTreeSet<usize> = modoffs
+        .iter()
+        .filter_map(|m| srcview.modoff(m))
+        .filter(|srcline| is_same_source(&srcline.path, &opts.source_path))
+        .map(|srcline| srcline.line)
+        .collect();
+
+    let mut out = BufWriter::new(stdout().lock());
+
+    if let AnnotateFormat::Html = opts.format {
+        writeln!(out, "<pre>")?;
+    }
+
+    // line numbers in debug info are 1-based
+    for (line, text) in (1..).zip(source.lines()) {
+        let is_covered = covered.contains(&line);
+
+        match opts.format {
+            AnnotateFormat::Text => {
+                let prefix = if is_covered { '>' } else { ' ' };
+                writeln!(out, "{prefix}{text}")?;
+            }
+            AnnotateFormat::Html => {
+                let class = if is_covered { "covered" } else { "uncovered" };
+                writeln!(out, "<span class=\"{class}\">{}</span>", escape(text))?;
+            }
+        }
+    }
+
+    if let AnnotateFormat::Html = opts.format {
+        writeln!(out, "</pre>")?;
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct CoverageUpdate {
+    timestamp: String,
+    files: usize,
+    covered_lines: usize,
+    total_lines: usize,
+    percent: f64,
+}
+
+fn watch_coverage(opts: WatchCoverageOpt) -> Result<()> {
+    let mut srcview = SrcView::new();
+
+    if let Some(module_name) = &opts.module_name {
+        srcview.insert_cache(module_name, load_pdb(&opts.pdb_path, None)?);
+    } else {
+        add_common_extensions(&mut srcview, &opts.pdb_path, None)?;
+    }
+
+    rename_modules(&mut srcview, &opts.rename_module)?;
+
+    // start watching before the initial scan, so no file is missed in between
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher
+        .watch(&opts.coverage_dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("unable to watch: {}", opts.coverage_dir.display()))?;
+
+    // covered source lines, by modoff file
+    let mut coverage: BTreeMap<PathBuf, Vec<SrcLine>> = BTreeMap::new();
+
+    for entry in fs::read_dir(&opts.coverage_dir)
+        .with_context(|| format!("unable to read: {}", opts.coverage_dir.display()))?
+    {
+        let path = entry?.path();
+        if path.is_file() {
+            update_coverage(&mut coverage, &srcview, &path);
+        }
+    }
+    print_coverage_update(&coverage, &srcview, opts.include_regex.as_deref())?;
+
+    for event in rx {
+        let event = event?;
+
+        let is_update = matches!(
+            event.kind,
+            EventKind::Create(..) | EventKind::Modify(..) | EventKind::Remove(..)
+        );
+        if !is_update {
+            continue;
+        }
+
+        let mut changed = false;
+        for path in &event.paths {
+            if path.is_file() {
+                changed |= update_coverage(&mut coverage, &srcview, path);
+            } else {
+                changed |= coverage.remove(path).is_some();
+            }
+        }
+
+        if changed {
+            print_coverage_update(&coverage, &srcview, opts.include_regex.as_deref())?;
+        }
+    }
+
+    Ok(())
+}
+
+// Returns whether the coverage of the file was updated. Files which can't be
+// parsed as modoff are skipped, since they may still be being written.
+fn update_coverage(
+    coverage: &mut BTreeMap<PathBuf, Vec<SrcLine>>,
+    srcview: &SrcView,
+    path: &Path,
+) -> bool {
+    let modoffs = match fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|data| ModOff::parse(&data))
+    {
+        Ok(modoffs) => modoffs,
+        Err(err) => {
+            log::warn!("skipping {}: {:?}", path.display(), err);
+            return false;
+        }
+    };
+
+    let lines = modoffs.iter().filter_map(|m| srcview.modoff(m)).collect();
+    coverage.insert(path.to_owned(), lines);
+    true
+}
+
+fn print_coverage_update(
+    coverage: &BTreeMap<PathBuf, Vec<SrcLine>>,
+    srcview: &SrcView,
+    include_regex: Option<&str>,
+) -> Result<()> {
+    let lines: Vec<SrcLine> = coverage.values().flatten().cloned().collect();
+    let r = Report::new(&lines, srcview, include_regex)?;
+
+    let (total_lines, covered_lines) = r
+        .file_summary()
+        .fold((0, 0), |(total, covered), (_, lines, hits)| {
+            (total + lines, covered + hits)
+        });
+    let percent = if total_lines == 0 {
+        0.0
+    } else {
+        // rounded to two decimal places
+        (10000.0 * covered_lines as f64 / total_lines as f64).round() / 100.0
+    };
+
+    let update = CoverageUpdate {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        files: coverage.len(),
+        covered_lines,
+        total_lines,
+        percent,
+    };
+
+    let mut out = stdout().lock();
+    writeln!(out, "{}", serde_json::to_string(&update)?)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn is_same_source(pdb_path: &Path, source_path: &Path) -> bool {
+    let normalize = |path: &Path| {
+        let path = SrcLine::new(path, 0).normalize().path;
+        path.to_string_lossy().into_owned()
+    };
+
+    let pdb_path = normalize(pdb_path);
+    let source_path = normalize(source_path);
+    let source_path = source_path.trim_start_matches("./");
+
+    pdb_path == source_path || pdb_path.ends_with(&format!("/{source_path}"))
+}
+
+fn modoff(opts: ModOffOpt) -> Result<()> {
+    match opts {
+        ModOffOpt::ToBinary(opts) => {
+            let mut modoff_data = String::new();
+            open_input(&opts.input_path)?.read_to_string(&mut modoff_data)?;
+            let modoffs = ModOff::parse(&modoff_data)?;
+
+            let mut output_writer = open_output(&opts.output_path)?;
+            ModOff::write_binary(&modoffs, &mut output_writer)?;
+            output_writer.flush()?;
+        }
+        ModOffOpt::FromBinary(opts) => {
+            let modoffs = ModOff::read_binary(open_input(&opts.input_path)?)?;
+
+            let mut output_writer = open_output(&opts.output_path)?;
+            for modoff in &modoffs {
+                writeln!(output_writer, "{modoff}")?;
+            }
+            output_writer.flush()?;
+        }
+    }
 
-    // Format it as cobertura and display it
-    r.cobertura(opts.filter_regex.as_deref(), &mut output_writer)?;
     Ok(())
 }
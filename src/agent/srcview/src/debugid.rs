@@ -0,0 +1,167 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use goblin::elf::note::NT_GNU_BUILD_ID;
+use goblin::Object;
+
+use crate::PdbStats;
+
+/// The GUID and age identifying the debug info of a binary.
+///
+/// A PDB matches a binary when both have the same `DebugId`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DebugId {
+    /// GUID, in uppercase hyphenated form
+    pub guid: String,
+    pub age: u32,
+}
+
+impl DebugId {
+    pub fn from_pdb<P: AsRef<Path>>(pdb: P) -> Result<Self> {
+        let stats = PdbStats::new(pdb)?;
+
+        Ok(Self {
+            guid: stats.guid,
+            age: stats.age,
+        })
+    }
+
+    /// Read the debug id of a binary.
+    ///
+    /// For PE files, this is the GUID and age of the CodeView record in the
+    /// debug directory. ELF files don't record a PDB, so their id follows the
+    /// Breakpad convention: the first 16 bytes of the GNU build ID as a GUID,
+    /// with an age of 0.
+    pub fn from_binary<P: AsRef<Path>>(binary: P) -> Result<Self> {
+        let data = std::fs::read(binary)?;
+
+        match Object::parse(&data)? {
+            Object::PE(pe) => {
+                let codeview = match pe.debug_data.and_then(|d| d.codeview_pdb70_debug_info) {
+                    Some(codeview) => codeview,
+                    None => bail!("PE file has no CodeView (PDB 7.0) debug directory entry"),
+                };
+
+                Ok(Self {
+                    guid: format_guid(&codeview.signature),
+                    age: codeview.age,
+                })
+            }
+            Object::Elf(elf) => {
+                let build_id = elf
+                    .iter_note_sections(&data, Some(".note.gnu.build-id"))
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|note| note.ok())
+                    .find(|note| note.n_type == NT_GNU_BUILD_ID)
+                    .map(|note| note.desc);
+
+                let build_id = match build_id {
+                    Some(build_id) => build_id,
+                    None => bail!("ELF file has no .note.gnu.build-id section"),
+                };
+
+                // Short build IDs are zero-padded to the size of a GUID.
+                let mut guid = [0u8; 16];
+                let len = build_id.len().min(guid.len());
+                guid[..len].copy_from_slice(&build_id[..len]);
+
+                Ok(Self {
+                    guid: format_guid(&guid),
+                    age: 0,
+                })
+            }
+            _ => bail!("binary is neither a PE nor an ELF file"),
+        }
+    }
+
+    /// Describe how `self`, the id of a PDB, differs from the id expected by
+    /// a binary. Empty if the PDB matches.
+    pub fn mismatches(&self, binary: &Self) -> Vec<String> {
+        let mut mismatches = vec![];
+
+        if self.guid != binary.guid {
+            mismatches.push(format!(
+                "GUID mismatch: PDB has {}, binary expects {}",
+                self.guid, binary.guid
+            ));
+        }
+
+        if self.age != binary.age {
+            mismatches.push(format!(
+                "age mismatch: PDB has {}, binary expects {}",
+                self.age, binary.age
+            ));
+        }
+
+        mismatches
+    }
+}
+
+impl fmt::Display for DebugId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (age {})", self.guid, self.age)
+    }
+}
+
+/// Format the on-disk bytes of a GUID, whose first three fields are
+/// little-endian.
+fn format_guid(bytes: &[u8; 16]) -> String {
+    format!(
+        "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        u16::from_le_bytes([bytes[6], bytes[7]]),
+        bytes[8],
+        bytes[9],
+        bytes[10],
+        bytes[11],
+        bytes[12],
+        bytes[13],
+        bytes[14],
+        bytes[15],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_guid() {
+        let bytes = [
+            0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
+            0xEE, 0xFF,
+        ];
+        assert_eq!(format_guid(&bytes), "00112233-4455-6677-8899-AABBCCDDEEFF");
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let pdb = DebugId {
+            guid: "00112233-4455-6677-8899-AABBCCDDEEFF".to_owned(),
+            age: 2,
+        };
+
+        assert!(pdb.mismatches(&pdb).is_empty());
+
+        let binary = DebugId {
+            age: 3,
+            ..pdb.clone()
+        };
+        assert_eq!(
+            pdb.mismatches(&binary),
+            vec!["age mismatch: PDB has 2, binary expects 3"]
+        );
+
+        let binary = DebugId {
+            guid: "FFEEDDCC-BBAA-9988-7766-554433221100".to_owned(),
+            age: 3,
+        };
+        assert_eq!(pdb.mismatches(&binary).len(), 2);
+    }
+}
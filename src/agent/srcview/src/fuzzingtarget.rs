@@ -0,0 +1,163 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::Result;
+use pdb::{FallibleIterator, SymbolData, TypeData, TypeFinder, TypeIndex, PDB};
+
+const LIBFUZZER_ENTRY_POINT: &str = "LLVMFuzzerTestOneInput";
+
+// type indexes below this are primitive types, which are encoded in the index itself
+// rather than stored in the type stream
+const FIRST_NON_PRIMITIVE_TYPE: u32 = 0x1000;
+
+// CodeView primitive types for `char`, `unsigned char`, real `char`, `__int8` and
+// `unsigned __int8`
+const BYTE_TYPES: &[u32] = &[0x10, 0x20, 0x70, 0x68, 0x69];
+
+// CodeView primitive types for the 32-bit and 64-bit unsigned integers, one of which is
+// `size_t` on any target
+const SIZE_TYPES: &[u32] = &[0x22, 0x23, 0x75, 0x77];
+
+/// Why a function was identified as a fuzzing target
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FuzzingTargetKind {
+    /// the function is a libFuzzer harness
+    LibFuzzer,
+    /// the function's first two arguments are a byte pointer and a size
+    PointerSize,
+}
+
+/// A function that is a plausible fuzzing entry point, found by the types of its
+/// arguments in the PDB.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FuzzingTarget {
+    pub name: String,
+    /// offset of the function from the module's base
+    pub rva: u32,
+    pub kind: FuzzingTargetKind,
+}
+
+impl FuzzingTarget {
+    /// Returns the fuzzing targets in a PDB, ordered by address.
+    ///
+    /// A target is either `LLVMFuzzerTestOneInput`, or a function whose first two
+    /// arguments have the types of `(const uint8_t*, size_t)`, ignoring `const`.
+    pub fn find<P: AsRef<Path>>(pdb: P) -> Result<Vec<Self>> {
+        let pdbfile = File::open(pdb)?;
+        let mut pdb = PDB::open(pdbfile)?;
+
+        let address_map = pdb.address_map()?;
+
+        let type_information = pdb.type_information()?;
+        let mut type_finder = type_information.finder();
+        let mut types = type_information.iter();
+        while types.next()?.is_some() {
+            type_finder.update(&types);
+        }
+
+        // keyed by address, as a function can have a procedure record in more than one
+        // module
+        let mut targets: BTreeMap<u32, Self> = BTreeMap::new();
+
+        let dbi = pdb.debug_information()?;
+        let mut modules = dbi.modules()?;
+        while let Some(module) = modules.next()? {
+            let info = match pdb.module_info(&module)? {
+                Some(info) => info,
+                None => continue,
+            };
+
+            let mut symbols = info.symbols()?;
+            while let Some(symbol) = symbols.next()? {
+                let proc = match symbol.parse() {
+                    Ok(SymbolData::Procedure(proc)) => proc,
+                    _ => continue,
+                };
+
+                let rva = match proc.offset.to_rva(&address_map) {
+                    Some(rva) => rva.0,
+                    None => continue,
+                };
+
+                let name = proc.name.to_string().into_owned();
+                let kind = if name == LIBFUZZER_ENTRY_POINT {
+                    FuzzingTargetKind::LibFuzzer
+                } else if takes_pointer_and_size(&type_finder, proc.type_index) {
+                    FuzzingTargetKind::PointerSize
+                } else {
+                    continue;
+                };
+
+                targets.insert(rva, Self { name, rva, kind });
+            }
+        }
+
+        Ok(targets.into_values().collect())
+    }
+}
+
+impl fmt::Display for FuzzingTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            FuzzingTargetKind::LibFuzzer => "libfuzzer",
+            FuzzingTargetKind::PointerSize => "pointer+size",
+        };
+        write!(f, "{:#010x} {} ({})", self.rva, self.name, kind)
+    }
+}
+
+fn parse_type<'t>(types: &TypeFinder<'t>, index: TypeIndex) -> Option<TypeData<'t>> {
+    types.find(index).ok()?.parse().ok()
+}
+
+fn takes_pointer_and_size(types: &TypeFinder<'_>, index: TypeIndex) -> bool {
+    let argument_list = match parse_type(types, index) {
+        Some(TypeData::Procedure(proc)) => proc.argument_list,
+        // the argument list of a member function does not include `this`
+        Some(TypeData::MemberFunction(func)) => func.argument_list,
+        _ => return false,
+    };
+
+    let arguments = match parse_type(types, argument_list) {
+        Some(TypeData::ArgumentList(list)) => list.arguments,
+        _ => return false,
+    };
+
+    match arguments.as_slice() {
+        [data, size, ..] => is_byte_pointer(types, *data) && is_primitive(types, *size, SIZE_TYPES),
+        _ => false,
+    }
+}
+
+fn is_byte_pointer(types: &TypeFinder<'_>, index: TypeIndex) -> bool {
+    if index.0 < FIRST_NON_PRIMITIVE_TYPE {
+        // the mode bits of a primitive type are set if it is a pointer
+        let mode = (index.0 >> 8) & 0x7;
+        return mode != 0 && BYTE_TYPES.contains(&(index.0 & 0xff));
+    }
+
+    match parse_type(types, index) {
+        Some(TypeData::Pointer(pointer)) => {
+            is_primitive(types, pointer.underlying_type, BYTE_TYPES)
+        }
+        Some(TypeData::Modifier(modifier)) => is_byte_pointer(types, modifier.underlying_type),
+        _ => false,
+    }
+}
+
+// Whether a type is one of the primitive `kinds`, ignoring `const` and `volatile`
+fn is_primitive(types: &TypeFinder<'_>, index: TypeIndex, kinds: &[u32]) -> bool {
+    if index.0 < FIRST_NON_PRIMITIVE_TYPE {
+        return kinds.contains(&index.0);
+    }
+
+    match parse_type(types, index) {
+        Some(TypeData::Modifier(modifier)) => is_primitive(types, modifier.underlying_type, kinds),
+        _ => false,
+    }
+}
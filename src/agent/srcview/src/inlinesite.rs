@@ -0,0 +1,27 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// A function inlined at a call site, and the line within it
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct InlineSite {
+    pub function_name: String,
+    pub file: PathBuf,
+    pub line: u32,
+}
+
+impl fmt::Display for InlineSite {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "{} ({}:{})",
+            self.function_name,
+            self.file.display(),
+            self.line
+        )
+    }
+}
@@ -92,14 +92,24 @@
 //!
 //! `Report` is significantly messier than `SrcView` and as of writing this I expect there to still be bugs.
 //!
+mod debugid;
+mod fuzzingtarget;
+mod inlinesite;
 mod modoff;
 mod pdbcache;
+mod pdbstats;
+mod publics;
 mod report;
 mod srcline;
 mod srcview;
 
-pub use self::srcview::SrcView;
+pub use self::srcview::{FunctionCoverage, SharedSrcView, SrcView};
+pub use debugid::DebugId;
+pub use fuzzingtarget::{FuzzingTarget, FuzzingTargetKind};
+pub use inlinesite::InlineSite;
 pub use modoff::{ModOff, ModOffParseError};
 pub use pdbcache::PdbCache;
+pub use pdbstats::PdbStats;
+pub use publics::{PublicSymbols, SymbolOffset};
 pub use report::Report;
 pub use srcline::SrcLine;
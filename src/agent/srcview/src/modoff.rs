@@ -2,9 +2,13 @@
 // Licensed under the MIT License.
 
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::fmt;
+use std::io::{Read, Write};
 
+use anyhow::{bail, format_err, Context, Result};
 use log::*;
 
 use nom::bytes::complete::{tag, take_till1, take_while};
@@ -85,6 +89,28 @@ impl ModOff {
         }
     }
 
+    /// Normalize a module name for case-insensitive matching, by stripping any
+    /// directory components and lowercasing the remaining file name. Both `/` and `\`
+    /// are treated as separators, regardless of the host platform.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use srcview::ModOff;
+    ///
+    /// assert_eq!(
+    ///     ModOff::normalize_module_name(r"C:\Windows\System32\Foo.dll"),
+    ///     "foo.dll"
+    /// );
+    /// ```
+    pub fn normalize_module_name(name: &str) -> String {
+        let file_name = name
+            .rsplit(|c| c == '/' || c == '\\')
+            .next()
+            .unwrap_or(name);
+        file_name.to_lowercase()
+    }
+
     fn parse_module(input: &str) -> IResult<&str, String> {
         let (input, module) = take_till1(|c| c == '+')(input)?;
 
@@ -144,6 +170,112 @@ impl ModOff {
 
         Ok(res)
     }
+
+    /// Write modoffs in the compact binary modoff format
+    ///
+    /// The output starts with a module table: the number of modules, followed by each
+    /// module name as a length-prefixed UTF-8 string. Each modoff is then written as
+    /// an index into the module table and an offset. All integers are little-endian
+    /// `u32`s.
+    ///
+    /// # Errors
+    ///
+    /// If an offset or the number of modules does not fit in a `u32`, or writing fails
+    ///
+    /// # Example
+    /// ```
+    /// use srcview::ModOff;
+    ///
+    /// let modoffs = vec![ModOff::new("foo.exe", 0x4141)];
+    ///
+    /// let mut data = vec![];
+    /// ModOff::write_binary(&modoffs, &mut data).unwrap();
+    ///
+    /// assert_eq!(modoffs, ModOff::read_binary(data.as_slice()).unwrap());
+    /// ```
+    pub fn write_binary<W: Write>(modoffs: &[Self], mut writer: W) -> Result<()> {
+        let mut modules: Vec<&str> = vec![];
+        let mut indexes: HashMap<&str, u32> = HashMap::new();
+        let mut records = Vec::with_capacity(modoffs.len());
+
+        for modoff in modoffs {
+            let index = match indexes.get(modoff.module.as_str()) {
+                Some(index) => *index,
+                None => {
+                    let index = u32::try_from(modules.len()).context("too many modules")?;
+                    modules.push(&modoff.module);
+                    indexes.insert(&modoff.module, index);
+                    index
+                }
+            };
+            let offset = u32::try_from(modoff.offset)
+                .with_context(|| format!("offset too large: {modoff}"))?;
+            records.push((index, offset));
+        }
+
+        writer.write_all(&u32::try_from(modules.len())?.to_le_bytes())?;
+        for module in modules {
+            writer.write_all(&u32::try_from(module.len())?.to_le_bytes())?;
+            writer.write_all(module.as_bytes())?;
+        }
+
+        for (index, offset) in records {
+            writer.write_all(&index.to_le_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Read modoffs written in the binary modoff format by [`ModOff::write_binary`]
+    ///
+    /// # Errors
+    ///
+    /// If the input is truncated, a module name is not valid UTF-8, or a modoff refers
+    /// to a module that is not in the module table
+    pub fn read_binary<R: Read>(mut reader: R) -> Result<Vec<Self>> {
+        fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+            let mut buf = [0u8; 4];
+            reader.read_exact(&mut buf)?;
+            Ok(u32::from_le_bytes(buf))
+        }
+
+        let count = read_u32(&mut reader).context("unable to read module count")?;
+        let mut modules = vec![];
+        for _ in 0..count {
+            let len = read_u32(&mut reader).context("unable to read module name length")?;
+            let mut name = vec![0u8; len as usize];
+            reader
+                .read_exact(&mut name)
+                .context("unable to read module name")?;
+            modules.push(String::from_utf8(name).context("invalid module name")?);
+        }
+
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        if data.len() % 8 != 0 {
+            bail!("truncated modoff entry");
+        }
+
+        let res = data
+            .chunks_exact(8)
+            .map(|record| -> Result<Self> {
+                let (index, offset) = record.split_at(4);
+                let index = u32::from_le_bytes(index.try_into()?) as usize;
+                let offset = u32::from_le_bytes(offset.try_into()?) as usize;
+                let module = modules
+                    .get(index)
+                    .ok_or_else(|| format_err!("invalid module index: {}", index))?;
+
+                Ok(Self::new(module, offset))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        info!("read {} binary modoff entries", res.len());
+
+        Ok(res)
+    }
 }
 
 #[cfg(test)]
@@ -151,6 +283,17 @@ mod tests {
     use super::*;
     use anyhow::Result;
 
+    #[test]
+    fn normalize_module_name() {
+        assert_eq!(ModOff::normalize_module_name("foo.dll"), "foo.dll");
+        assert_eq!(ModOff::normalize_module_name("FOO.DLL"), "foo.dll");
+        assert_eq!(
+            ModOff::normalize_module_name(r"C:\Windows\System32\foo.dll"),
+            "foo.dll"
+        );
+        assert_eq!(ModOff::normalize_module_name("/usr/lib/Foo.so"), "foo.so");
+    }
+
     #[test]
     fn parse_empty() -> Result<()> {
         let empty: Vec<ModOff> = Vec::new();
@@ -219,6 +362,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn binary_roundtrip() -> Result<()> {
+        let modoffs = vec![
+            ModOff::new("foo.exe", 0x4141),
+            ModOff::new("bar.dll", 0x4242),
+            ModOff::new("foo.exe", 0x4343),
+        ];
+
+        let mut data = vec![];
+        ModOff::write_binary(&modoffs, &mut data)?;
+
+        assert_eq!(modoffs, ModOff::read_binary(data.as_slice())?);
+        Ok(())
+    }
+
+    #[test]
+    fn binary_bad_module_index() {
+        let mut data = vec![];
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0x4141u32.to_le_bytes());
+
+        assert!(ModOff::read_binary(data.as_slice()).is_err());
+    }
+
+    #[test]
+    fn binary_bad_truncated() -> Result<()> {
+        let mut data = vec![];
+        ModOff::write_binary(&[ModOff::new("foo.exe", 0x4141)], &mut data)?;
+        data.pop();
+
+        assert!(ModOff::read_binary(data.as_slice()).is_err());
+        Ok(())
+    }
+
     #[test]
     fn parse_bad_bad_digits() {
         assert_eq!(
@@ -4,13 +4,14 @@
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use anyhow::{format_err, Result};
 use log::*;
-use pdb::{FallibleIterator, SymbolData, PDB};
+use pdb::{FallibleIterator, IdData, IdFinder, IdIndex, Inlinee, SymbolData, PDB};
 use serde::{Deserialize, Serialize};
 
-use crate::SrcLine;
+use crate::{InlineSite, SrcLine};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct PdbCache {
@@ -18,12 +19,38 @@ pub struct PdbCache {
     symbol_to_lines: BTreeMap<String, Vec<SrcLine>>,
     path_to_symbols: BTreeMap<PathBuf, Vec<String>>,
     path_to_lines: BTreeMap<PathBuf, Vec<usize>>,
+
+    // start offset to (length, name) of each function
+    #[serde(default)]
+    offset_to_function: BTreeMap<usize, (usize, String)>,
+
+    // (start offset, end offset, site) of each inlined line, outermost first
+    #[serde(default)]
+    inline_sites: Vec<(usize, usize, InlineSite)>,
+
+    // set if loading stopped before all of the pdb's modules were read
+    #[serde(default)]
+    incomplete: bool,
 }
 
 impl PdbCache {
     pub fn new<P: AsRef<Path>>(pdb: P) -> Result<Self> {
+        Self::new_with_timeout(pdb, None)
+    }
+
+    /// Load a pdb, giving up after `timeout` and keeping what was loaded so far.
+    ///
+    /// The timeout is checked between the pdb's modules, so loading can run past it
+    /// by the time taken to read one module. Use [`PdbCache::is_complete`] to check
+    /// whether loading finished.
+    pub fn new_with_timeout<P: AsRef<Path>>(pdb: P, timeout: Option<Duration>) -> Result<Self> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut incomplete = false;
+
         let mut offset_to_line: BTreeMap<usize, SrcLine> = BTreeMap::new();
         let mut symbol_to_lines: BTreeMap<String, Vec<SrcLine>> = BTreeMap::new();
+        let mut offset_to_function: BTreeMap<usize, (usize, String)> = BTreeMap::new();
+        let mut inline_sites: Vec<(usize, usize, InlineSite)> = vec![];
 
         // NOTE: We're using strings as the keys for now while we build the trees, since
         // PathBuf comparisons are expensive.
@@ -36,9 +63,26 @@ impl PdbCache {
         let address_map = pdb.address_map()?;
         let string_table = pdb.string_table()?;
 
+        // inlined functions are named by their id records
+        let id_information = pdb.id_information()?;
+        let mut id_finder = id_information.finder();
+        let mut ids = id_information.iter();
+        while ids.next()?.is_some() {
+            id_finder.update(&ids);
+        }
+
         let dbi = pdb.debug_information()?;
         let mut modules = dbi.modules()?;
         while let Some(module) = modules.next()? {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                warn!(
+                    "timed out loading pdb, stopping at module: {}",
+                    module.module_name()
+                );
+                incomplete = true;
+                break;
+            }
+
             let info = match pdb.module_info(&module)? {
                 Some(info) => info,
                 None => {
@@ -48,11 +92,64 @@ impl PdbCache {
             };
 
             let program = info.line_program()?;
+            let inlinees: BTreeMap<IdIndex, Inlinee> = info
+                .inlinees()?
+                .map(|inlinee| Ok((inlinee.index(), inlinee)))
+                .collect()?;
             let mut symbols = info.symbols()?;
 
+            // inline site lines are relative to the enclosing procedure
+            let mut proc_offset = None;
+
             while let Some(symbol) = symbols.next()? {
-                if let Ok(SymbolData::Procedure(proc)) = symbol.parse() {
+                let parsed = symbol.parse();
+
+                if let (Ok(SymbolData::InlineSite(site)), Some(parent_offset)) =
+                    (&parsed, proc_offset)
+                {
+                    if let Some(inlinee) = inlinees.get(&site.inlinee) {
+                        let function_name = inlinee_name(&id_finder, site.inlinee);
+                        let mut lines = inlinee.lines(parent_offset, site);
+
+                        while let Some(line_info) = lines.next()? {
+                            let rva = match line_info.offset.to_rva(&address_map) {
+                                Some(rva) => rva,
+                                None => continue,
+                            };
+                            let file_info = program.get_file_info(line_info.file_index)?;
+                            let file_name = file_info.name.to_string_lossy(&string_table)?;
+                            let path = file_name.into_owned();
+
+                            // the parent's line program attributes this code to the call
+                            // site, but the inlined line is instrumented all the same
+                            path_to_lines
+                                .entry(path.clone())
+                                .or_default()
+                                .push(line_info.line_start as usize);
+
+                            let start = rva.0 as usize;
+                            let end = start + line_info.length.unwrap_or(1) as usize;
+                            let site = InlineSite {
+                                function_name: function_name.clone(),
+                                file: PathBuf::from(path),
+                                line: line_info.line_start,
+                            };
+                            inline_sites.push((start, end, site));
+                        }
+                    }
+                }
+
+                if let Ok(SymbolData::Procedure(proc)) = parsed {
+                    proc_offset = Some(proc.offset);
                     let proc_name = proc.name.to_string();
+
+                    // procedure records hold the undecorated name, so there is no
+                    // need to demangle it
+                    if let Some(rva) = proc.offset.to_rva(&address_map) {
+                        offset_to_function
+                            .insert(rva.0 as usize, (proc.len as usize, proc_name.to_string()));
+                    }
+
                     let mut lines = program.lines_for_symbol(proc.offset);
 
                     let symbol_to_lines = symbol_to_lines.entry(proc_name.to_string()).or_default();
@@ -93,13 +190,74 @@ impl PdbCache {
                 .into_iter()
                 .map(|(p, l)| (PathBuf::from(p), l))
                 .collect(),
+            offset_to_function,
+            inline_sites,
+            incomplete,
         })
     }
 
+    /// Returns false if loading timed out, so the pdb info is partial.
+    pub fn is_complete(&self) -> bool {
+        !self.incomplete
+    }
+
     pub fn offset(&self, off: &usize) -> Option<&SrcLine> {
         self.offset_to_line.get(off)
     }
 
+    /// Returns the line whose code contains the offset: the closest line at or
+    /// before it, in the same function. Offsets outside of any function have
+    /// no line.
+    pub fn line_containing(&self, off: usize) -> Option<&SrcLine> {
+        let (start, (len, _)) = self.offset_to_function.range(..=off).next_back()?;
+        if off >= start + len {
+            return None;
+        }
+
+        self.offset_to_line
+            .range(*start..=off)
+            .next_back()
+            .map(|(_, line)| line)
+    }
+
+    /// Returns all instrumented lines, ordered by offset.
+    pub fn lines(&self) -> impl Iterator<Item = &SrcLine> {
+        self.offset_to_line.values()
+    }
+
+    /// Returns the name of the function whose code contains the offset, if any.
+    pub fn function(&self, off: usize) -> Option<&str> {
+        let (start, (len, name)) = self.offset_to_function.range(..=off).next_back()?;
+
+        if off < start + len {
+            Some(name.as_str())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the name of each function and the lines of its code, ordered by offset.
+    pub fn function_lines(&self) -> impl Iterator<Item = (&str, Vec<&SrcLine>)> {
+        self.offset_to_function
+            .iter()
+            .map(move |(start, (len, name))| {
+                let lines = self
+                    .offset_to_line
+                    .range(*start..start + len)
+                    .map(|(_, line)| line)
+                    .collect();
+                (name.as_str(), lines)
+            })
+    }
+
+    /// Returns the inline frames that contain the offset, outermost first.
+    pub fn inline_sites(&self, off: usize) -> impl Iterator<Item = &InlineSite> {
+        self.inline_sites
+            .iter()
+            .filter(move |(start, end, _)| *start <= off && off < *end)
+            .map(|(_, _, site)| site)
+    }
+
     pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
         self.path_to_lines.keys()
     }
@@ -118,3 +276,14 @@ impl PdbCache {
         self.symbol_to_lines.get(sym).map(|x| x.iter())
     }
 }
+
+fn inlinee_name(id_finder: &IdFinder, index: IdIndex) -> String {
+    match id_finder.find(index).and_then(|id| id.parse()) {
+        Ok(IdData::Function(function)) => function.name.to_string().into_owned(),
+        Ok(IdData::MemberFunction(function)) => function.name.to_string().into_owned(),
+        _ => {
+            warn!("unable to resolve inlinee name: {:?}", index);
+            format!("{index:?}")
+        }
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::Result;
+use pdb::{FallibleIterator, SymbolData, PDB};
+
+/// Identifying metadata of a PDB, read without building any line tables.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PdbStats {
+    /// PDB GUID, in uppercase hyphenated form
+    pub guid: String,
+    pub age: u32,
+    /// PDB signature, which is the build time in seconds since the Unix epoch
+    pub timestamp: u32,
+    pub machine_type: String,
+    /// compiler of the first module with compile flags, if any
+    pub compiler: Option<String>,
+    pub section_count: usize,
+    /// size of the image when loaded, i.e. the end of the last section
+    pub image_size: u64,
+    pub source_file_count: usize,
+}
+
+impl PdbStats {
+    pub fn new<P: AsRef<Path>>(pdb: P) -> Result<Self> {
+        let pdbfile = File::open(pdb)?;
+        let mut pdb = PDB::open(pdbfile)?;
+
+        let info = pdb.pdb_information()?;
+        let string_table = pdb.string_table()?;
+        let dbi = pdb.debug_information()?;
+
+        let sections = pdb.sections()?.unwrap_or_default();
+        let section_count = sections.len();
+        let image_size = sections
+            .iter()
+            .map(|section| u64::from(section.virtual_address) + u64::from(section.virtual_size))
+            .max()
+            .unwrap_or_default();
+
+        let mut compiler = None;
+        let mut source_files = BTreeSet::new();
+
+        let mut modules = dbi.modules()?;
+        while let Some(module) = modules.next()? {
+            let module_info = match pdb.module_info(&module)? {
+                Some(module_info) => module_info,
+                None => continue,
+            };
+
+            // only the file checksums are read, not the line records
+            let program = module_info.line_program()?;
+            let mut files = program.files();
+            while let Some(file) = files.next()? {
+                source_files.insert(file.name.to_string_lossy(&string_table)?.into_owned());
+            }
+
+            if compiler.is_none() {
+                let mut symbols = module_info.symbols()?;
+                while let Some(symbol) = symbols.next()? {
+                    if let Ok(SymbolData::CompileFlags(flags)) = symbol.parse() {
+                        let version = flags.frontend_version;
+                        compiler = Some(format!(
+                            "{} {}.{}.{}",
+                            flags.version_string, version.major, version.minor, version.build
+                        ));
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            guid: info.guid.to_string().to_uppercase(),
+            age: dbi.age().unwrap_or(info.age),
+            timestamp: info.signature,
+            machine_type: format!("{:?}", dbi.machine_type()?),
+            compiler,
+            section_count,
+            image_size,
+            source_file_count: source_files.len(),
+        })
+    }
+
+    /// The id symbol servers index the PDB by: the GUID without hyphens,
+    /// followed by the age in hex.
+    pub fn symbol_server_id(&self) -> String {
+        format!("{}{:X}", self.guid.replace('-', ""), self.age)
+    }
+}
+
+impl fmt::Display for PdbStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "guid:              {}", self.guid)?;
+        writeln!(f, "age:               {}", self.age)?;
+        writeln!(f, "symbol server id:  {}", self.symbol_server_id())?;
+        writeln!(f, "timestamp:         {}", self.timestamp)?;
+        writeln!(f, "machine type:      {}", self.machine_type)?;
+        writeln!(
+            f,
+            "compiler:          {}",
+            self.compiler.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(f, "sections:          {}", self.section_count)?;
+        writeln!(f, "image size:        {:#x}", self.image_size)?;
+        write!(f, "source files:      {}", self.source_file_count)
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::Result;
+use msvc_demangler::DemangleFlags;
+use pdb::{FallibleIterator, SymbolData, PDB};
+
+/// The code symbols of a PDB's public symbol table, by address.
+///
+/// Unlike [`crate::PdbCache`], this does not need private symbols or line info, so
+/// it also works with the stripped PDBs published to symbol servers.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PublicSymbols(BTreeMap<u32, String>);
+
+/// A public symbol and an offset from its address, see [`PublicSymbols::lookup`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SymbolOffset {
+    /// demangled name, or the name as recorded in the PDB if it can't be demangled
+    pub name: String,
+    pub offset: u32,
+}
+
+impl PublicSymbols {
+    pub fn new<P: AsRef<Path>>(pdb: P) -> Result<Self> {
+        let pdbfile = File::open(pdb)?;
+        let mut pdb = PDB::open(pdbfile)?;
+
+        let address_map = pdb.address_map()?;
+        let global_symbols = pdb.global_symbols()?;
+
+        let mut symbols = BTreeMap::new();
+        let mut iter = global_symbols.iter();
+        while let Some(symbol) = iter.next()? {
+            let public = match symbol.parse() {
+                Ok(SymbolData::Public(public)) if public.code || public.function => public,
+                _ => continue,
+            };
+
+            if let Some(rva) = public.offset.to_rva(&address_map) {
+                symbols.insert(rva.0, public.name.to_string().into_owned());
+            }
+        }
+
+        Ok(Self(symbols))
+    }
+
+    /// Returns the closest public symbol at or before an address, and the offset of
+    /// the address from it.
+    ///
+    /// Public symbols have no size, so an address past the end of the last function
+    /// is still attributed to it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::PublicSymbols;
+    ///
+    /// let symbols = PublicSymbols::new(r"z:\src\example.pdb").unwrap();
+    ///
+    /// if let Some(symbol) = symbols.lookup(0x4141) {
+    ///     println!("0x4141 is {}", symbol);
+    /// }
+    /// ```
+    pub fn lookup(&self, rva: u32) -> Option<SymbolOffset> {
+        let (address, name) = self.0.range(..=rva).next_back()?;
+
+        Some(SymbolOffset {
+            name: demangle(name),
+            offset: rva - address,
+        })
+    }
+
+    /// Returns the number of public code symbols
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if the PDB has no public code symbols
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for SymbolOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{:#x}", self.name, self.offset)
+    }
+}
+
+// Public symbols hold decorated names, of which only C++ names start with `?`
+fn demangle(name: &str) -> String {
+    if name.starts_with('?') {
+        if let Ok(demangled) = msvc_demangler::demangle(name, DemangleFlags::llvm()) {
+            return demangled;
+        }
+    }
+
+    name.to_owned()
+}
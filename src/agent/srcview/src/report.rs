@@ -7,10 +7,11 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::{format_err, Context, Result};
+use anyhow::{bail, format_err, Context, Result};
 use log::warn;
 use regex::Regex;
 
+use crate::srcline::normalize_path;
 use crate::{SrcLine, SrcView};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -38,6 +39,8 @@ pub struct Report {
     filecov: BTreeMap<PathBuf, FileCov>,
     dircov: BTreeMap<PathBuf, DirCov>,
     overall: DirCov,
+    // modules whose pdb info was only partially loaded, see `SrcView::incomplete_modules`
+    incomplete_modules: Vec<String>,
 }
 
 impl Report {
@@ -58,7 +61,7 @@ impl Report {
     ///
     /// # Errors
     ///
-    /// If the regex cannot be compiled
+    /// If the regex cannot be compiled, or the SrcView has no modules loaded
     ///
     /// # Example
     /// ```no_run
@@ -82,6 +85,10 @@ impl Report {
         srcview: &SrcView,
         include_regex: Option<&str>,
     ) -> Result<Self> {
+        if srcview.is_empty() {
+            bail!("SrcView has no modules loaded; did you forget to call insert()?");
+        }
+
         let include = include_regex.map(Regex::new).transpose()?;
         let filecov = Self::compute_filecov(coverage, srcview, &include)?;
 
@@ -92,6 +99,7 @@ impl Report {
             // these will be populated by compute_dircov
             dircov: BTreeMap::new(),
             overall: DirCov::new(0, 0),
+            incomplete_modules: srcview.incomplete_modules(),
         };
 
         r.compute_dircov();
@@ -105,9 +113,13 @@ impl Report {
         srcview: &SrcView,
         include: &Option<Regex>,
     ) -> Result<BTreeMap<PathBuf, FileCov>> {
-        let uniq_cov: BTreeSet<SrcLine> = coverage.iter().cloned().collect();
+        let uniq_cov: BTreeSet<SrcLine> = coverage.iter().map(SrcLine::normalize).collect();
+
+        let mut filecov: BTreeMap<PathBuf, FileCov> = BTreeMap::new();
 
-        let mut filecov = BTreeMap::new();
+        // the same file can appear under several paths, e.g. in different pdbs; its
+        // coverage is merged under the first path seen
+        let mut file_paths: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
 
         for path in srcview.paths() {
             if !Self::relevant_path(path, include)? {
@@ -121,48 +133,176 @@ impl Report {
                 })?
                 .map(|line| SrcLine::new(path, line));
 
-            let mut lines = vec![];
-            let mut hits = vec![];
-            let mut symbols = BTreeMap::new();
+            let file_path = file_paths
+                .entry(normalize_path(path))
+                .or_insert_with(|| path.clone());
+            let file = filecov.entry(file_path.clone()).or_insert_with(|| FileCov {
+                lines: vec![],
+                hits: vec![],
+                symbols: BTreeMap::new(),
+            });
 
             for srcloc in path_srclocs {
-                lines.push(srcloc.line);
+                file.lines.push(srcloc.line);
 
-                if uniq_cov.contains(&srcloc) {
-                    hits.push(srcloc.line);
+                if uniq_cov.contains(&srcloc.normalize()) {
+                    file.hits.push(srcloc.line);
                 }
             }
 
-            lines.sort_unstable();
-            hits.sort_unstable();
-
             if let Some(path_symbols) = srcview.path_symbols(path) {
                 for symbol in path_symbols {
                     let symbol_srclocs: BTreeSet<SrcLine> = srcview
                         .symbol(&symbol)
                         .ok_or_else(|| format_err!("unable to resolve symbol: {}", symbol))?
-                        .cloned()
+                        .map(SrcLine::normalize)
                         .collect();
 
-                    symbols.insert(symbol, symbol_srclocs);
+                    file.symbols
+                        .entry(symbol)
+                        .or_default()
+                        .extend(symbol_srclocs);
                 }
             }
+        }
 
-            filecov.insert(
-                path.clone(),
-                FileCov {
-                    lines,
-                    hits,
-                    symbols,
-                },
-            );
+        for file in filecov.values_mut() {
+            file.lines.sort_unstable();
+            file.lines.dedup();
+            file.hits.sort_unstable();
+            file.hits.dedup();
         }
 
         Ok(filecov)
     }
 
-    // should only be called from `new`, function to initialize directory coverage and overall
-    // coverage. File coverage must be already initialized at this point
+    /// Remove all files without any covered lines from the report
+    ///
+    /// The removed files are excluded from both the emitted report and the overall line
+    /// counts, which keeps large, mostly-unreached projects from dragging the coverage
+    /// percentage down.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use srcview::{ModOff, Report, SrcLine, SrcView};
+    ///
+    /// let modoff_data = std::fs::read_to_string("coverage.modoff.txt").unwrap();
+    /// let modoffs = ModOff::parse(&modoff_data).unwrap();
+    ///
+    /// let mut srcview = SrcView::new();
+    /// srcview.insert("example.exe", "example.pdb").unwrap();
+    ///
+    /// let coverage: Vec<SrcLine> = modoffs
+    ///     .into_iter()
+    ///     .filter_map(|m| srcview.modoff(&m))
+    ///     .collect();
+    ///
+    /// let mut r = Report::new(&coverage, &srcview, None).unwrap();
+    /// r.filter_zero_coverage();
+    /// ```
+    pub fn filter_zero_coverage(&mut self) {
+        self.filecov.retain(|_, cov| !cov.hits.is_empty());
+
+        // directory stats were computed with the removed files included, start over
+        self.dircov.clear();
+        self.compute_dircov();
+    }
+
+    /// Returns a report with only the lines covered by `self` that are not covered by
+    /// `other`
+    ///
+    /// The instrumented lines of each file are kept, so the coverage percentages of the
+    /// result measure the coverage added by `self`. Files only in `other` are ignored.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use srcview::{ModOff, Report, SrcLine, SrcView};
+    ///
+    /// let mut srcview = SrcView::new();
+    /// srcview.insert("example.exe", "example.pdb").unwrap();
+    ///
+    /// let coverage = |path: &str| -> Vec<SrcLine> {
+    ///     let modoff_data = std::fs::read_to_string(path).unwrap();
+    ///     let modoffs = ModOff::parse(&modoff_data).unwrap();
+    ///     modoffs
+    ///         .into_iter()
+    ///         .filter_map(|m| srcview.modoff(&m))
+    ///         .collect()
+    /// };
+    ///
+    /// let base = Report::new(&coverage("base.modoff.txt"), &srcview, None).unwrap();
+    /// let new = Report::new(&coverage("new.modoff.txt"), &srcview, None).unwrap();
+    ///
+    /// for srcloc in new.subtract(&base).covered_lines() {
+    ///     println!("{}", srcloc);
+    /// }
+    /// ```
+    pub fn subtract(&self, other: &Report) -> Report {
+        // match files by normalized path, as the reports may spell paths differently
+        let other_filecov: BTreeMap<PathBuf, &FileCov> = other
+            .filecov
+            .iter()
+            .map(|(path, cov)| (normalize_path(path), cov))
+            .collect();
+
+        let filecov = self
+            .filecov
+            .iter()
+            .map(|(path, cov)| {
+                let mut cov = cov.clone();
+                if let Some(other_cov) = other_filecov.get(&normalize_path(path)) {
+                    cov.hits
+                        .retain(|line| other_cov.hits.binary_search(line).is_err());
+                }
+                (path.clone(), cov)
+            })
+            .collect();
+
+        let mut r = Self {
+            filecov,
+            // these will be populated by compute_dircov
+            dircov: BTreeMap::new(),
+            overall: DirCov::new(0, 0),
+            incomplete_modules: self.incomplete_modules.clone(),
+        };
+
+        r.compute_dircov();
+
+        r
+    }
+
+    /// Returns every covered line in the report, ordered by path and line
+    pub fn covered_lines(&self) -> impl Iterator<Item = SrcLine> + '_ {
+        self.filecov
+            .iter()
+            .flat_map(|(path, cov)| cov.hits.iter().map(move |line| SrcLine::new(path, *line)))
+    }
+
+    /// Returns the number of instrumented and covered lines of each file in the report,
+    /// ordered by path
+    ///
+    /// # Example
+    /// ```no_run
+    /// use srcview::{Report, SrcView};
+    ///
+    /// let mut srcview = SrcView::new();
+    /// srcview.insert("example.exe", "example.pdb").unwrap();
+    ///
+    /// let r = Report::new(&[], &srcview, None).unwrap();
+    ///
+    /// for (path, lines, covered) in r.file_summary() {
+    ///     println!("{}: {}/{}", path.display(), covered, lines);
+    /// }
+    /// ```
+    pub fn file_summary(&self) -> impl Iterator<Item = (&Path, usize, usize)> {
+        self.filecov
+            .iter()
+            .map(|(path, cov)| (path.as_path(), cov.lines.len(), cov.hits.len()))
+    }
+
+    // should only be called from `new` or after `filecov` has been filtered, function to
+    // initialize directory coverage and overall coverage. File coverage must be already
+    // initialized at this point
     fn compute_dircov(&mut self) {
         // need to make a copy so we don't hold an immutable reference to self in the loop
         let paths: Vec<PathBuf> = self.paths().cloned().collect();
@@ -283,6 +423,20 @@ impl Report {
         }
     }
 
+    // applies the filter regex, then makes the path relative to the source root
+    fn display_path<P: AsRef<Path> + fmt::Debug>(
+        path: P,
+        filter: &Option<Regex>,
+        source_root: Option<&str>,
+    ) -> Result<String> {
+        let path = Self::filter_path(path, filter)?.display().to_string();
+
+        match source_root {
+            Some(source_root) => relative_to_source_root(&path, source_root),
+            None => Ok(path),
+        }
+    }
+
     // wrapper to allow ergonomic testing of our include regex inside an option against a
     // path
     fn relevant_path<P: AsRef<Path> + fmt::Debug>(
@@ -304,6 +458,9 @@ impl Report {
 
     /// Generate a Cobertura report
     ///
+    /// If loading the pdb info of any module timed out, the report starts with a comment
+    /// noting the analysis is incomplete and listing those modules.
+    ///
     /// # Arguments
     ///
     /// * `filter_regex` - This a search and replace regex that is applied to all file
@@ -318,10 +475,15 @@ impl Report {
     ///                    paths this will replace that regex with the empty string, leaving the
     ///                    path `test.c` which relative to our repo root is correct. A value of
     ///                    `None` will not filter any paths.
+    /// * `source_root` - A directory that all file paths are made relative to, after the
+    ///                   filter regex is applied. Paths are compared ignoring case and path
+    ///                   separator style, and are written with `/` separators. A value of
+    ///                   `None` leaves paths as they are.
     ///
     /// # Errors
     ///
     /// * If the filter regex cannot be compiled
+    /// * If a path is not inside the source root
     /// * If there is an error writing the output xml
     ///
     /// # Example
@@ -350,11 +512,16 @@ impl Report {
     ///
     /// // However when generating the report, we want to strip off only the repo name --
     /// // `example` is inside the repo so to make the paths line up we need to leave it.
-    /// r.cobertura(Some(r"E:\\1f\coverage\\"), &mut xml).unwrap();
+    /// r.cobertura(Some(r"E:\\1f\coverage\\"), None, &mut xml).unwrap();
     ///
     /// println!("{}", std::str::from_utf8(&xml).unwrap());
     /// ```
-    pub fn cobertura<W: Write>(&self, filter_regex: Option<&str>, output: &mut W) -> Result<()> {
+    pub fn cobertura<W: Write>(
+        &self,
+        filter_regex: Option<&str>,
+        source_root: Option<&str>,
+        output: &mut W,
+    ) -> Result<()> {
         use quick_xml::{
             events::{BytesEnd, BytesStart, BytesText, Event},
             Writer,
@@ -379,6 +546,14 @@ impl Report {
             .context("system time before unix epoch")?
             .as_secs();
 
+        if !self.incomplete_modules.is_empty() {
+            let comment = format!(
+                " incomplete analysis: loading the pdb info timed out for modules: {} ",
+                self.incomplete_modules.join(", ")
+            );
+            ew.write_event(Event::Comment(BytesText::new(&comment)))?;
+        }
+
         ew.write_event(Event::Start(
             BytesStart::new("coverage").with_attributes([
                 ("lines-valid", format!("{}", self.overall.lines).as_str()),
@@ -414,7 +589,7 @@ impl Report {
                 continue;
             }
 
-            let display_dir = Self::filter_path(dir, &filter)?.display().to_string();
+            let display_dir = Self::display_path(dir, &filter, source_root)?;
 
             ew.write_event(Event::Start(
                 el_start
@@ -430,7 +605,7 @@ impl Report {
             //
 
             for path in self.filter_files(dir) {
-                let display_path = Self::filter_path(path, &filter)?.display().to_string();
+                let display_path = Self::display_path(path, &filter, source_root)?;
 
                 let filecov = match self.file(path) {
                     Some(filecov) => filecov,
@@ -443,12 +618,12 @@ impl Report {
                 let file_srclocs: BTreeSet<SrcLine> = filecov
                     .lines
                     .iter()
-                    .map(|line| SrcLine::new(path, *line))
+                    .map(|line| SrcLine::new(path, *line).normalize())
                     .collect();
                 let hit_srclocs: BTreeSet<SrcLine> = filecov
                     .hits
                     .iter()
-                    .map(|line| SrcLine::new(path, *line))
+                    .map(|line| SrcLine::new(path, *line).normalize())
                     .collect();
 
                 ew.write_event(Event::Start(
@@ -568,3 +743,112 @@ impl Report {
         Ok(())
     }
 }
+
+/// Make `path` relative to `source_root`, comparing components ignoring case
+/// and path separator style, since PDB paths come from the build machine.
+///
+/// Fails if the path would escape the source root, i.e. start with `../`.
+fn relative_to_source_root(path: &str, source_root: &str) -> Result<String> {
+    let components = |path: &str| -> Vec<String> {
+        path.split(['/', '\\'])
+            .filter(|c| !c.is_empty() && *c != ".")
+            .map(str::to_owned)
+            .collect()
+    };
+
+    let path_components = components(path);
+    let root_components = components(source_root);
+
+    let common = path_components
+        .iter()
+        .zip(&root_components)
+        .take_while(|(p, r)| p.to_lowercase() == r.to_lowercase())
+        .count();
+
+    if common < root_components.len() {
+        bail!(
+            "path {} is outside of source root {}; it would be rewritten to start with `../`",
+            path,
+            source_root
+        );
+    }
+
+    let relative = path_components[common..].join("/");
+    if relative.is_empty() {
+        Ok(".".to_owned())
+    } else {
+        Ok(relative)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(files: &[(&str, &[usize], &[usize])]) -> Report {
+        let filecov = files
+            .iter()
+            .map(|(path, lines, hits)| {
+                let cov = FileCov {
+                    symbols: BTreeMap::new(),
+                    lines: lines.to_vec(),
+                    hits: hits.to_vec(),
+                };
+                (PathBuf::from(path), cov)
+            })
+            .collect();
+
+        let mut r = Report {
+            filecov,
+            dircov: BTreeMap::new(),
+            overall: DirCov::new(0, 0),
+            incomplete_modules: vec![],
+        };
+        r.compute_dircov();
+        r
+    }
+
+    #[test]
+    fn test_filter_zero_coverage() {
+        let mut r = report(&[
+            ("/src/foo.c", &[1, 2, 3], &[1, 3]),
+            ("/src/bar.c", &[1, 2], &[]),
+            ("/src/lib/baz.c", &[4, 5], &[]),
+            ("/src/lib/qux.c", &[7], &[7]),
+        ]);
+        assert_eq!(r.overall, DirCov::new(3, 8));
+
+        r.filter_zero_coverage();
+
+        let summary: Vec<_> = r.file_summary().collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Path::new("/src/foo.c"), 3, 2),
+                (Path::new("/src/lib/qux.c"), 1, 1),
+            ]
+        );
+
+        // directory stats only count the remaining files
+        assert_eq!(r.dir("/src"), Some(&DirCov::new(3, 4)));
+        assert_eq!(r.dir("/src/lib"), Some(&DirCov::new(1, 1)));
+        assert_eq!(r.overall, DirCov::new(3, 4));
+    }
+
+    #[test]
+    fn test_relative_to_source_root() -> Result<()> {
+        assert_eq!(
+            relative_to_source_root(r"z:\src\Foo\lib\test.c", r"Z:\src\foo")?,
+            "lib/test.c"
+        );
+        assert_eq!(
+            relative_to_source_root("/build/foo/test.c", "/build/foo/")?,
+            "test.c"
+        );
+        assert_eq!(relative_to_source_root(r"z:\src\foo", r"z:\src\foo")?, ".");
+
+        assert!(relative_to_source_root(r"z:\src\bar\test.c", r"z:\src\foo").is_err());
+        assert!(relative_to_source_root(r"z:\src", r"z:\src\foo").is_err());
+        Ok(())
+    }
+}
@@ -43,4 +43,58 @@ impl SrcLine {
         let path = path.as_ref().to_owned();
         Self { path, line }
     }
+
+    /// Returns the SrcLine with its path normalized for comparison, so that the
+    /// same file matches however its path is spelled in debug info or coverage.
+    ///
+    /// Windows drive letters are stripped, `\` separators are replaced with `/`,
+    /// and the path is lowercased, regardless of the host platform.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use srcview::SrcLine;
+    ///
+    /// assert_eq!(
+    ///     SrcLine::new(r"Z:\src\Example.c", 3).normalize(),
+    ///     SrcLine::new("/src/example.c", 3)
+    /// );
+    /// ```
+    pub fn normalize(&self) -> SrcLine {
+        SrcLine {
+            path: normalize_path(&self.path),
+            line: self.line,
+        }
+    }
+}
+
+/// Normalize a path as in [`SrcLine::normalize`]
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let path = path.to_string_lossy().replace('\\', "/").to_lowercase();
+
+    let mut chars = path.chars();
+    let path = match (chars.next(), chars.next()) {
+        (Some(drive), Some(':')) if drive.is_ascii_alphabetic() => &path[2..],
+        _ => &path,
+    };
+
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        let normalized = SrcLine::new("/src/example.c", 3);
+
+        assert_eq!(SrcLine::new("/src/example.c", 3).normalize(), normalized);
+        assert_eq!(SrcLine::new(r"\src\example.c", 3).normalize(), normalized);
+        assert_eq!(SrcLine::new(r"z:\src\Example.c", 3).normalize(), normalized);
+        assert_eq!(SrcLine::new("Z:/SRC/example.c", 3).normalize(), normalized);
+
+        assert_ne!(SrcLine::new("/src/example.c", 4).normalize(), normalized);
+        assert_ne!(SrcLine::new("src/example.c", 3).normalize(), normalized);
+    }
 }
@@ -2,15 +2,92 @@
 // Licensed under the MIT License.
 
 use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{
+    Arc, Mutex, MutexGuard, OnceLock, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::{ModOff, PdbCache, SrcLine};
+use crate::srcline::normalize_path;
+use crate::{InlineSite, ModOff, PdbCache, SrcLine};
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
-pub struct SrcView(BTreeMap<String, PdbCache>);
+#[serde(transparent)]
+pub struct SrcView(
+    BTreeMap<String, PdbCache>,
+    #[serde(skip)] FileLineIndex,
+    #[serde(skip)] ModuleUsage,
+);
+
+// Unique instrumented lines of each normalized source path across all modules, built on
+// the first call to [`SrcView::coverage_for_file`]. Derived from the pdb info, so it is
+// ignored when comparing SrcViews and must be reset whenever the pdb info changes.
+#[derive(Clone, Debug, Default)]
+struct FileLineIndex(OnceLock<BTreeMap<PathBuf, BTreeSet<usize>>>);
+
+impl FileLineIndex {
+    fn reset(&mut self) {
+        self.0.take();
+    }
+}
+
+impl PartialEq for FileLineIndex {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for FileLineIndex {}
+
+// Names of the modules that [`SrcView::modoff`] has looked up offsets in, for
+// [`SrcView::unused_modules`]. Like the [`FileLineIndex`], it is not pdb info, so it is
+// ignored when comparing SrcViews.
+#[derive(Debug, Default)]
+struct ModuleUsage(Mutex<BTreeSet<String>>);
+
+impl ModuleUsage {
+    fn used(&self) -> MutexGuard<'_, BTreeSet<String>> {
+        // the set is only ever added to, so it cannot be left inconsistent
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn record(&self, module: &str) {
+        let mut used = self.used();
+        if !used.contains(module) {
+            used.insert(module.to_owned());
+        }
+    }
+}
+
+impl Clone for ModuleUsage {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.used().clone()))
+    }
+}
+
+impl PartialEq for ModuleUsage {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ModuleUsage {}
+
+/// Line coverage of a single function, see [`SrcView::function_coverage_summary`]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FunctionCoverage {
+    pub name: String,
+    /// file containing the function's first line
+    pub file: PathBuf,
+    pub total_lines: u32,
+    pub covered_lines: u32,
+    pub percent: f64,
+}
 
 /// A SrcView is a collection of zero or more PdbCaches for easy querying. It stores all
 /// the mapping information from the PDBs. It does _not_ contain any coverage information.
@@ -46,9 +123,36 @@ impl SrcView {
     /// ```
     pub fn insert<P: AsRef<Path>>(&mut self, module: &str, pdb: P) -> Result<Option<PdbCache>> {
         let cache = PdbCache::new(pdb)?;
+        self.1.reset();
         Ok(self.0.insert(module.to_owned(), cache))
     }
 
+    /// Insert already loaded pdb info into the SrcView, returning any previous pdb info
+    /// that you're replacing. This allows one pdb to be loaded once and inserted under
+    /// several module names.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use srcview::{PdbCache, SrcView};
+    ///
+    /// let timeout = Some(Duration::from_secs(60));
+    /// let cache = PdbCache::new_with_timeout(r"z:\src\example.pdb", timeout).unwrap();
+    ///
+    /// let mut sv = SrcView::new();
+    /// sv.insert_cache("example.exe", cache.clone());
+    /// sv.insert_cache("example.dll", cache);
+    ///
+    /// for module in sv.incomplete_modules() {
+    ///     println!("{} was not fully loaded", module);
+    /// }
+    /// ```
+    pub fn insert_cache(&mut self, module: &str, cache: PdbCache) -> Option<PdbCache> {
+        self.1.reset();
+        self.0.insert(module.to_owned(), cache)
+    }
+
     /// Insert a new pdb into the SrcView only if the `pdb` path is not in the SrcView already,
     /// returning a  [Result] indicating the success of the insert, if any was necessary.
     /// If the [Result] is [Ok], the contained bool indicates whether a value was inserted.
@@ -89,6 +193,202 @@ impl SrcView {
         }
     }
 
+    /// Insert the pdb info from a file written by [`SrcView::export`], returning the
+    /// names of the inserted modules. Like `insert`, this replaces the pdb info of any
+    /// modules with the same names.
+    ///
+    /// This does not need the PDBs themselves, so an export can be analyzed on a
+    /// machine without access to them.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the exported pdb info
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be read or is not a valid export.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let mut sv = SrcView::new();
+    ///
+    /// for module in sv.insert_from_export("example.srcview.json").unwrap() {
+    ///     println!("loaded {}", module);
+    /// }
+    /// ```
+    pub fn insert_from_export<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<String>> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("unable to open export: {}", path.display()))?;
+        let exported: SrcView = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("invalid export: {}", path.display()))?;
+
+        let modules = exported.0.keys().cloned().collect();
+        self.1.reset();
+        self.0.extend(exported.0);
+        Ok(modules)
+    }
+
+    /// Write the pdb info of all modules as JSON, which can be loaded again with
+    /// [`SrcView::insert_from_export`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let mut sv = SrcView::new();
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// let file = std::fs::File::create("example.srcview.json").unwrap();
+    /// sv.export(file).unwrap();
+    /// ```
+    pub fn export<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    /// Write the pdb info of all modules to a binary cache file, which is much faster
+    /// to read with [`SrcView::load`] than parsing the PDBs again.
+    ///
+    /// The cache is only readable by the same version of srcview. Unlike
+    /// [`SrcView::export`], it is not meant to be portable.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let mut sv = SrcView::new();
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// sv.save("example.srcview_cache").unwrap();
+    /// ```
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("unable to create cache: {}", path.display()))?;
+
+        let mut writer = BufWriter::new(file);
+        bincode::serialize_into(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read a SrcView from a cache file written by [`SrcView::save`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let sv = SrcView::load("example.srcview_cache").unwrap();
+    ///
+    /// for module in sv.modules() {
+    ///     println!("loaded {}", module);
+    /// }
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("unable to open cache: {}", path.display()))?;
+
+        let srcview = bincode::deserialize_from(BufReader::new(file))
+            .with_context(|| format!("invalid cache: {}", path.display()))?;
+        Ok(srcview)
+    }
+
+    /// Returns the pdb info stored under a module name, if any.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let sv = SrcView::load("example.srcview_cache").unwrap();
+    ///
+    /// if let Some(cache) = sv.pdb_cache("example.exe") {
+    ///     let mut other = SrcView::new();
+    ///     other.insert_cache("example.dll", cache.clone());
+    /// }
+    /// ```
+    pub fn pdb_cache(&self, module: &str) -> Option<&PdbCache> {
+        self.module_cache(module)
+    }
+
+    /// Move the pdb info stored under one module name to another module name. This is
+    /// useful when the module name in the coverage differs from the name the PDB was
+    /// inserted as (e.g. by case or by a path prefix).
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - Module name the PDB info is currently stored as
+    /// * `new` - Module name to store the PDB info as
+    ///
+    /// # Errors
+    ///
+    /// If `old` is not in the SrcView, or if `new` is already in the SrcView. In either
+    /// case the SrcView is left unmodified.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let mut sv = SrcView::new();
+    ///
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// // the coverage refers to the module by its upper case name
+    /// sv.rename_module("example.exe", "EXAMPLE.EXE").unwrap();
+    /// ```
+    pub fn rename_module(&mut self, old: &str, new: &str) -> Result<()> {
+        if self.0.contains_key(new) {
+            bail!("module already exists: {}", new);
+        }
+
+        match self.0.remove(old) {
+            Some(cache) => {
+                self.0.insert(new.to_owned(), cache);
+                Ok(())
+            }
+            None => bail!("module not found: {}", old),
+        }
+    }
+
+    /// Remove the pdb info stored under a module name, returning `true` if the module
+    /// was in the SrcView. A later `insert` with the same module name will load the
+    /// PDB again, which allows picking up a rebuilt binary's PDB.
+    ///
+    /// A [`SharedSrcView`] must be cleared with [`SharedSrcView::clear_module`], which
+    /// holds the write lock so that no lookup observes a partially updated SrcView.
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - Module name the PDB info is stored as
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let mut sv = SrcView::new();
+    ///
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// // example.exe was rebuilt, reload its PDB
+    /// if sv.clear_module("example.exe") {
+    ///     sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    /// }
+    /// ```
+    pub fn clear_module(&mut self, module: &str) -> bool {
+        self.1.reset();
+        self.0.remove(module).is_some()
+    }
+
     /// Resolve a modoff to SrcLine, if one exists
     ///
     /// # Arguments
@@ -112,10 +412,141 @@ impl SrcView {
     /// }
     /// ```
     pub fn modoff(&self, modoff: &ModOff) -> Option<SrcLine> {
-        match self.0.get(&modoff.module) {
-            Some(cache) => cache.offset(&modoff.offset).cloned(),
-            None => None,
+        let (module, cache) = self.module_entry(&modoff.module)?;
+        self.2.record(module);
+        cache.offset(&modoff.offset).cloned()
+    }
+
+    // Find the pdb info for a module name, falling back to comparing normalized names
+    // (see [`ModOff::normalize_module_name`]) if there is no exact match
+    fn module_cache(&self, module: &str) -> Option<&PdbCache> {
+        self.module_entry(module).map(|(_, cache)| cache)
+    }
+
+    // Like `module_cache`, also returning the name the pdb info is stored as
+    fn module_entry(&self, module: &str) -> Option<(&String, &PdbCache)> {
+        if let Some(entry) = self.0.get_key_value(module) {
+            return Some(entry);
         }
+
+        let normalized = ModOff::normalize_module_name(module);
+        self.0
+            .iter()
+            .find(|(name, _)| ModOff::normalize_module_name(name) == normalized)
+    }
+
+    /// Resolve a module offset to the name of the function containing it, if one exists.
+    /// The name is the undecorated name recorded in the PDB.
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - Module name the PDB info is stored as
+    /// * `offset` - Offset from the module's base
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let mut sv = SrcView::new();
+    ///
+    /// // Map the contents of 'example.pdb' to the module name 'example.exe'
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// if let Some(function) = sv.function_for_offset("example.exe", 0x4141) {
+    ///     println!("example.exe+4141 is in {}", function);
+    /// }
+    /// ```
+    pub fn function_for_offset(&self, module: &str, offset: u64) -> Option<&str> {
+        let offset = usize::try_from(offset).ok()?;
+        self.module_cache(module)?.function(offset)
+    }
+
+    /// Resolve a module offset to the source line whose code contains it. Unlike
+    /// [`SrcView::modoff`], the offset need not be the start of a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - Module name the PDB info is stored as
+    /// * `offset` - Offset from the module's base
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let mut sv = SrcView::new();
+    ///
+    /// // Map the contents of 'example.pdb' to the module name 'example.exe'
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// if let Some(line) = sv.line_for_offset("example.exe", 0x4141) {
+    ///     println!("example.exe+4141 is at {}", line);
+    /// }
+    /// ```
+    pub fn line_for_offset(&self, module: &str, offset: u64) -> Option<SrcLine> {
+        let offset = usize::try_from(offset).ok()?;
+        self.module_cache(module)?.line_containing(offset).cloned()
+    }
+
+    /// Returns the frames of any functions inlined at a module offset, outermost first.
+    /// The result is empty if the offset is not in an inlined function.
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - Module name the PDB info is stored as
+    /// * `offset` - Offset from the module's base
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let mut sv = SrcView::new();
+    ///
+    /// // Map the contents of 'example.pdb' to the module name 'example.exe'
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// for site in sv.inline_sites("example.exe", 0x4141) {
+    ///     println!("inlined: {}", site);
+    /// }
+    /// ```
+    pub fn inline_sites(&self, module: &str, offset: u64) -> Vec<InlineSite> {
+        match (self.module_cache(module), usize::try_from(offset)) {
+            (Some(cache), Ok(offset)) => cache.inline_sites(offset).cloned().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns every instrumented source line of a module, ordered by offset, if the
+    /// module is in the SrcView. A line appears once for each offset that maps to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - Module name the PDB info is stored as
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let mut sv = SrcView::new();
+    ///
+    /// // Map the contents of 'example.pdb' to the module name 'example.exe'
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// if let Some(lines) = sv.line_table("example.exe") {
+    ///     println!("example.exe has {} instrumented lines", lines.len());
+    /// }
+    /// ```
+    pub fn line_table(&self, module: &str) -> Option<Vec<(PathBuf, u32)>> {
+        self.0.get(module).map(|cache| {
+            cache
+                .lines()
+                // line numbers come from the PDB as u32
+                .map(|srcline| (srcline.path.clone(), srcline.line as u32))
+                .collect()
+        })
     }
 
     /// Resolve a symbol (e.g. module!name) to its possible SrcLines, if such a symbol
@@ -254,6 +685,75 @@ impl SrcView {
         Some(v.into_iter())
     }
 
+    /// Returns true if no modules have been inserted into the SrcView
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over all module names in the SrcView
+    pub fn modules(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// Returns the names of modules whose pdb info is partial because loading timed
+    /// out, see [`PdbCache::new_with_timeout`]
+    pub fn incomplete_modules(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter(|(_, cache)| !cache.is_complete())
+            .map(|(module, _)| module.clone())
+            .collect()
+    }
+
+    /// Returns the names of modules that no [`SrcView::modoff`] lookup has resolved to,
+    /// such as PDBs inserted for modules that are not in the coverage
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::{ModOff, SrcView};
+    ///
+    /// let mut sv = SrcView::new();
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    /// sv.insert("other.dll", r"z:\src\other.pdb").unwrap();
+    ///
+    /// sv.modoff(&ModOff::new("example.exe", 0x4141));
+    ///
+    /// assert_eq!(sv.unused_modules(), vec!["other.dll".to_owned()]);
+    /// ```
+    pub fn unused_modules(&self) -> Vec<String> {
+        let used = self.2.used();
+        self.0
+            .keys()
+            .filter(|module| !used.contains(*module))
+            .cloned()
+            .collect()
+    }
+
+    /// Wrap the SrcView so that lookups can be shared across threads once it has
+    /// been fully loaded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::{ModOff, SrcView};
+    ///
+    /// let mut sv = SrcView::new();
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// let shared = sv.into_shared();
+    ///
+    /// let handles: Vec<_> = (0..4)
+    ///     .map(|i| {
+    ///         let shared = shared.clone();
+    ///         std::thread::spawn(move || shared.modoff(&ModOff::new("example.exe", 0x4141 + i)))
+    ///     })
+    ///     .collect();
+    /// ```
+    pub fn into_shared(self) -> SharedSrcView {
+        SharedSrcView(Arc::new(RwLock::new(self)))
+    }
+
     /// Returns an iterator over all paths in the SrcView
     ///
     /// # Example
@@ -283,4 +783,249 @@ impl SrcView {
 
         r.into_iter()
     }
+
+    /// Returns the line coverage of every function in the SrcView, including functions
+    /// without any covered lines, ordered by file and name
+    ///
+    /// The lines of a function are those of the code in its address range, so lines of
+    /// inlined code are not counted. A PDB inserted under several module names has its
+    /// functions reported once.
+    ///
+    /// # Arguments
+    ///
+    /// * `coverage` - The hit set of SrcLines
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::{ModOff, SrcLine, SrcView};
+    ///
+    /// let mut sv = SrcView::new();
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// let coverage: Vec<SrcLine> = [ModOff::new("example.exe", 0x4141)]
+    ///     .iter()
+    ///     .filter_map(|m| sv.modoff(m))
+    ///     .collect();
+    ///
+    /// for function in sv.function_coverage_summary(&coverage) {
+    ///     if function.covered_lines == 0 {
+    ///         println!("{} was never entered", function.name);
+    ///     }
+    /// }
+    /// ```
+    pub fn function_coverage_summary(&self, coverage: &[SrcLine]) -> Vec<FunctionCoverage> {
+        let covered: BTreeSet<SrcLine> = coverage.iter().map(SrcLine::normalize).collect();
+
+        let mut functions: BTreeMap<(&Path, &str), BTreeSet<SrcLine>> = BTreeMap::new();
+        for cache in self.0.values() {
+            for (name, lines) in cache.function_lines() {
+                // functions without line info have no lines to cover
+                if let Some(first) = lines.first() {
+                    functions
+                        .entry((first.path.as_path(), name))
+                        .or_default()
+                        .extend(lines.iter().map(SrcLine::normalize));
+                }
+            }
+        }
+
+        functions
+            .into_iter()
+            .map(|((file, name), lines)| {
+                let total_lines = lines.len() as u32;
+                let covered_lines =
+                    lines.iter().filter(|line| covered.contains(line)).count() as u32;
+                let percent = if total_lines == 0 {
+                    0.0
+                } else {
+                    100.0 * covered_lines as f64 / total_lines as f64
+                };
+
+                FunctionCoverage {
+                    name: name.to_owned(),
+                    file: file.to_path_buf(),
+                    total_lines,
+                    covered_lines,
+                    percent,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the number of unique source files across all modules
+    ///
+    /// Paths are counted after [`SrcLine::normalize`], ignoring drive letters, case and
+    /// path separator style: `z:\src\Foo.c` and `/src/foo.c` are the same file.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::SrcView;
+    ///
+    /// let mut sv = SrcView::new();
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// println!("example.pdb has {} source files", sv.source_file_count());
+    /// ```
+    pub fn source_file_count(&self) -> usize {
+        let files: BTreeSet<PathBuf> = self
+            .0
+            .values()
+            .flat_map(PdbCache::paths)
+            .map(|path| normalize_path(path))
+            .collect();
+
+        files.len()
+    }
+
+    /// Returns the number of covered and total lines of a single source file, as
+    /// `(covered, total)`
+    ///
+    /// Paths are compared after [`SrcLine::normalize`], and only covered lines that are
+    /// instrumented lines of the file are counted. The total line count of every file is
+    /// indexed on the first call, so later calls don't need to walk the pdb info.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - A path that possibly matches one from the debug info
+    /// * `coverage` - The hit set of SrcLines
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::{ModOff, SrcLine, SrcView};
+    ///
+    /// let mut sv = SrcView::new();
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// let coverage: Vec<SrcLine> = [ModOff::new("example.exe", 0x4141)]
+    ///     .iter()
+    ///     .filter_map(|m| sv.modoff(m))
+    ///     .collect();
+    ///
+    /// for path in sv.paths() {
+    ///     let (covered, total) = sv.coverage_for_file(path, &coverage);
+    ///     println!("{}: {}/{}", path.display(), covered, total);
+    /// }
+    /// ```
+    pub fn coverage_for_file(&self, file: &Path, coverage: &[SrcLine]) -> (u32, u32) {
+        let file = normalize_path(file);
+
+        let lines = match self.file_line_index().get(&file) {
+            Some(lines) => lines,
+            None => return (0, 0),
+        };
+
+        let covered = coverage
+            .iter()
+            .map(SrcLine::normalize)
+            .filter(|srcline| srcline.path == file && lines.contains(&srcline.line))
+            .map(|srcline| srcline.line)
+            .collect::<BTreeSet<usize>>()
+            .len() as u32;
+
+        (covered, lines.len() as u32)
+    }
+
+    /// Returns the number of distinct offsets hit in each function, as `module!function`
+    /// names ordered by hit count, most hit first
+    ///
+    /// Offsets that aren't in a function of a known module are ignored, and an offset
+    /// listed more than once is only counted once.
+    ///
+    /// # Arguments
+    ///
+    /// * `modoffs` - The hit set of ModOffs
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use srcview::{ModOff, SrcView};
+    ///
+    /// let mut sv = SrcView::new();
+    /// sv.insert("example.exe", r"z:\src\example.pdb").unwrap();
+    ///
+    /// let modoffs = [ModOff::new("example.exe", 0x4141)];
+    /// for (function, hits) in sv.function_hit_counts(&modoffs).iter().take(10) {
+    ///     println!("{}: {}", function, hits);
+    /// }
+    /// ```
+    pub fn function_hit_counts(&self, modoffs: &[ModOff]) -> Vec<(String, usize)> {
+        let mut offsets: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+        for modoff in modoffs {
+            if let Some(function) = self.function_for_offset(&modoff.module, modoff.offset as u64) {
+                offsets
+                    .entry(format!("{}!{}", modoff.module, function))
+                    .or_default()
+                    .insert(modoff.offset);
+            }
+        }
+
+        let mut counts: Vec<(String, usize)> = offsets
+            .into_iter()
+            .map(|(function, offsets)| (function, offsets.len()))
+            .collect();
+
+        // ties stay ordered by name
+        counts.sort_by(|a, b| b.1.cmp(&a.1));
+        counts
+    }
+
+    fn file_line_index(&self) -> &BTreeMap<PathBuf, BTreeSet<usize>> {
+        self.1 .0.get_or_init(|| {
+            let mut files: BTreeMap<PathBuf, BTreeSet<usize>> = BTreeMap::new();
+            for cache in self.0.values() {
+                for path in cache.paths() {
+                    if let Some(lines) = cache.path_lines(path) {
+                        files
+                            .entry(normalize_path(path))
+                            .or_default()
+                            .extend(lines.copied());
+                    }
+                }
+            }
+
+            files
+        })
+    }
+}
+
+/// A SrcView that can be cheaply cloned and queried from multiple threads.
+///
+/// Lookups take a read lock on the underlying SrcView and return owned values.
+#[derive(Clone, Debug, Default)]
+pub struct SharedSrcView(Arc<RwLock<SrcView>>);
+
+impl SharedSrcView {
+    fn read(&self) -> RwLockReadGuard<'_, SrcView> {
+        // lookups never modify the SrcView, so it cannot be left inconsistent by
+        // a panicking reader
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, SrcView> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Resolve a modoff to SrcLine, if one exists. See [`SrcView::modoff`].
+    pub fn modoff(&self, modoff: &ModOff) -> Option<SrcLine> {
+        self.read().modoff(modoff)
+    }
+
+    /// Returns all paths in the SrcView. See [`SrcView::paths`].
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.read().paths().cloned().collect()
+    }
+
+    /// Returns all module names in the SrcView. See [`SrcView::modules`].
+    pub fn modules(&self) -> Vec<String> {
+        self.read().modules().map(str::to_owned).collect()
+    }
+
+    /// Remove the pdb info stored under a module name while holding the write lock.
+    /// See [`SrcView::clear_module`].
+    pub fn clear_module(&self, module: &str) -> bool {
+        self.write().clear_module(module)
+    }
 }
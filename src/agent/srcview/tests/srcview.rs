@@ -7,15 +7,22 @@
 
 use std::env;
 use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 
-use srcview::{ModOff, SrcLine, SrcView};
+use srcview::{
+    FunctionCoverage, FuzzingTarget, ModOff, PdbCache, PdbStats, PublicSymbols, Report, SrcLine,
+    SrcView,
+};
 
-fn test_srcview() -> SrcView {
+fn test_pdb_path() -> PathBuf {
     let root = env::var("CARGO_MANIFEST_DIR").unwrap();
-    let pdb_path: PathBuf = [&root, "res", "example.pdb"].iter().collect();
+    [&root, "res", "example.pdb"].iter().collect()
+}
 
+fn test_srcview() -> SrcView {
     let mut srcview = SrcView::new();
-    srcview.insert("example.exe", pdb_path).unwrap();
+    srcview.insert("example.exe", test_pdb_path()).unwrap();
 
     srcview
 }
@@ -75,3 +82,401 @@ fn path() {
 
     assert!(srcview.path_lines("z:\\does\\not\\exist.c").is_none());
 }
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn source_file_count() {
+    let mut srcview = test_srcview();
+    let count = srcview.source_file_count();
+    assert!(count > 0);
+    assert!(count <= srcview.paths().count());
+
+    // the same pdb under another module name has the same files
+    srcview.insert("example.dll", test_pdb_path()).unwrap();
+    assert_eq!(srcview.source_file_count(), count);
+
+    assert_eq!(SrcView::new().source_file_count(), 0);
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn coverage_for_file() {
+    let mut srcview = test_srcview();
+    let file = PathBuf::from("E:\\1f\\coverage\\example\\example.c");
+
+    let (covered, total) = srcview.coverage_for_file(&file, &[]);
+    assert_eq!(covered, 0);
+    assert!(total > 0);
+
+    let coverage: Vec<SrcLine> = srcview
+        .modoff(&ModOff::new("example.exe", 0x6f70))
+        .into_iter()
+        .collect();
+    assert_eq!(srcview.coverage_for_file(&file, &coverage), (1, total));
+
+    // paths are compared after normalizing
+    let normalized = PathBuf::from("/1f/coverage/example/EXAMPLE.C");
+    assert_eq!(
+        srcview.coverage_for_file(&normalized, &coverage),
+        (1, total)
+    );
+
+    let missing = PathBuf::from("z:\\does\\not\\exist.c");
+    assert_eq!(srcview.coverage_for_file(&missing, &coverage), (0, 0));
+
+    // the index is rebuilt when the pdb info changes
+    assert!(srcview.clear_module("example.exe"));
+    assert_eq!(srcview.coverage_for_file(&file, &coverage), (0, 0));
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn function_for_offset() {
+    let srcview = test_srcview();
+
+    assert_eq!(
+        srcview.function_for_offset("example.exe", 0x6f70),
+        Some("main")
+    );
+    assert_eq!(srcview.function_for_offset("example.exe", 0x4141), None);
+    assert_eq!(srcview.function_for_offset("foo.exe", 0x6f70), None);
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn line_for_offset() {
+    let srcview = test_srcview();
+
+    let main = SrcLine::new("E:\\1f\\coverage\\example\\example.c", 3);
+    assert_eq!(srcview.line_for_offset("example.exe", 0x6f70), Some(main));
+    assert_eq!(
+        srcview.line_for_offset("example.exe", 0x6f71),
+        srcview.line_for_offset("example.exe", 0x6f70)
+    );
+    assert_eq!(srcview.line_for_offset("example.exe", 0x4141), None);
+    assert_eq!(srcview.line_for_offset("foo.exe", 0x6f70), None);
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn unused_modules() {
+    let mut srcview = test_srcview();
+    let cache = srcview.pdb_cache("example.exe").unwrap().clone();
+    srcview.insert_cache("other.dll", cache);
+
+    assert_eq!(
+        srcview.unused_modules(),
+        vec!["example.exe".to_owned(), "other.dll".to_owned()]
+    );
+
+    // a lookup uses the module even if the offset does not resolve
+    srcview.modoff(&ModOff::new("EXAMPLE.EXE", 0x4141));
+    assert_eq!(srcview.unused_modules(), vec!["other.dll".to_owned()]);
+
+    let clone = srcview.clone();
+    assert_eq!(clone.unused_modules(), vec!["other.dll".to_owned()]);
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn function_coverage_summary() {
+    let srcview = test_srcview();
+
+    let find_main = |summary: Vec<FunctionCoverage>| {
+        summary
+            .into_iter()
+            .find(|function| function.name == "main")
+            .unwrap()
+    };
+
+    // functions without coverage are included
+    let main = find_main(srcview.function_coverage_summary(&[]));
+    assert!(main.total_lines > 0);
+    assert_eq!(main.covered_lines, 0);
+    assert_eq!(main.percent, 0.0);
+
+    let coverage: Vec<SrcLine> = srcview
+        .modoff(&ModOff::new("example.exe", 0x6f70))
+        .into_iter()
+        .collect();
+    let main = find_main(srcview.function_coverage_summary(&coverage));
+    assert_eq!(main.covered_lines, 1);
+    assert!(main.percent > 0.0);
+
+    assert!(SrcView::new().function_coverage_summary(&[]).is_empty());
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn function_hit_counts() {
+    let srcview = test_srcview();
+
+    let modoffs = [
+        ModOff::new("example.exe", 0x6f70),
+        ModOff::new("example.exe", 0x6f70),
+        ModOff::new("example.exe", 0x4141),
+        ModOff::new("foo.exe", 0x6f70),
+    ];
+
+    // repeated offsets are counted once, and unknown offsets are skipped
+    assert_eq!(
+        srcview.function_hit_counts(&modoffs),
+        vec![("example.exe!main".to_owned(), 1)]
+    );
+
+    assert!(srcview.function_hit_counts(&[]).is_empty());
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn pdb_cache_timeout() {
+    let complete = PdbCache::new_with_timeout(test_pdb_path(), None).unwrap();
+    assert!(complete.is_complete());
+
+    // an elapsed timeout stops loading before the first module
+    let partial = PdbCache::new_with_timeout(test_pdb_path(), Some(Duration::ZERO)).unwrap();
+    assert!(!partial.is_complete());
+    assert_eq!(partial.lines().count(), 0);
+
+    let mut srcview = SrcView::new();
+    srcview.insert_cache("example.exe", complete);
+    srcview.insert_cache("example.dll", partial);
+    assert_eq!(srcview.incomplete_modules(), vec!["example.dll".to_owned()]);
+
+    let report = Report::new(&[], &srcview, None).unwrap();
+    let mut xml = vec![];
+    report.cobertura(None, None, &mut xml).unwrap();
+    let xml = String::from_utf8(xml).unwrap();
+    assert!(xml.contains("<!-- incomplete analysis: "));
+    assert!(xml.contains("example.dll"));
+}
+
+#[test]
+fn inline_sites_missing() {
+    let srcview = SrcView::new();
+
+    assert!(srcview.inline_sites("foo.exe", 0x4141).is_empty());
+}
+
+#[test]
+fn report_empty_srcview() {
+    let srcview = SrcView::new();
+    assert!(srcview.is_empty());
+
+    assert!(Report::new(&[], &srcview, None).is_err());
+}
+
+#[test]
+fn line_table_missing() {
+    let srcview = SrcView::new();
+
+    assert!(srcview.line_table("foo.exe").is_none());
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn line_table() {
+    let srcview = test_srcview();
+
+    let lines = srcview.line_table("example.exe").unwrap();
+
+    let mut example_lines: Vec<u32> = lines
+        .iter()
+        .filter(|(path, _)| path == &PathBuf::from("E:\\1f\\coverage\\example\\example.c"))
+        .map(|(_, line)| *line)
+        .collect();
+    example_lines.sort_unstable();
+    example_lines.dedup();
+    assert_eq!(example_lines, vec![3, 4, 5, 6, 7, 10, 11]);
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn file_summary() {
+    let srcview = test_srcview();
+
+    let coverage: Vec<SrcLine> = srcview
+        .modoff(&ModOff::new("example.exe", 0x6f70))
+        .into_iter()
+        .collect();
+    let report = Report::new(&coverage, &srcview, Some(r"E:\\1f\\coverage\\example")).unwrap();
+
+    let path = PathBuf::from("E:\\1f\\coverage\\example\\example.c");
+    let summary: Vec<_> = report.file_summary().collect();
+    assert_eq!(summary, vec![(path.as_path(), 7, 1)]);
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn export() {
+    let srcview = test_srcview();
+
+    let export_path = env::temp_dir().join("srcview-export-test.json");
+    srcview
+        .export(std::fs::File::create(&export_path).unwrap())
+        .unwrap();
+
+    let mut imported = SrcView::new();
+    let modules = imported.insert_from_export(&export_path).unwrap();
+    std::fs::remove_file(&export_path).unwrap();
+
+    assert_eq!(modules, vec!["example.exe".to_owned()]);
+    assert_eq!(imported, srcview);
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn save_load() {
+    let srcview = test_srcview();
+
+    let cache_path = env::temp_dir().join("srcview-save-test.srcview_cache");
+    srcview.save(&cache_path).unwrap();
+    let loaded = SrcView::load(&cache_path).unwrap();
+    std::fs::remove_file(&cache_path).unwrap();
+
+    assert_eq!(loaded, srcview);
+    assert_eq!(
+        loaded.modoff(&ModOff::new("example.exe", 0x6f70)),
+        Some(SrcLine::new("E:\\1f\\coverage\\example\\example.c", 3))
+    );
+    assert!(loaded.pdb_cache("example.exe").is_some());
+    assert!(loaded.pdb_cache("foo.exe").is_none());
+
+    assert!(SrcView::load(env::temp_dir().join("does-not-exist.srcview_cache")).is_err());
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn pdb_stats() {
+    let stats = PdbStats::new(test_pdb_path()).unwrap();
+
+    assert_eq!(stats.guid.len(), 36);
+    assert_eq!(
+        stats.symbol_server_id(),
+        format!("{}{:X}", stats.guid.replace('-', ""), stats.age)
+    );
+    assert!(stats.section_count > 0);
+    assert!(stats.source_file_count > 0);
+}
+
+#[test]
+fn pdb_stats_missing() {
+    assert!(PdbStats::new("does-not-exist.pdb").is_err());
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn fuzzing_targets() {
+    let targets = FuzzingTarget::find(test_pdb_path()).unwrap();
+
+    // main takes (int, char**), not a pointer and a size
+    assert!(targets.iter().all(|target| target.name != "main"));
+}
+
+#[test]
+fn fuzzing_targets_missing() {
+    assert!(FuzzingTarget::find("does-not-exist.pdb").is_err());
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn public_symbols() {
+    let symbols = PublicSymbols::new(test_pdb_path()).unwrap();
+    assert!(!symbols.is_empty());
+
+    // main is a C function, so it has an undecorated public symbol
+    let main = symbols.lookup(0x6f70).unwrap();
+    assert!(main.name.contains("main"));
+
+    let next = symbols.lookup(0x6f70 + 1).unwrap();
+    assert_eq!(next.name, main.name);
+    assert_eq!(next.offset, main.offset + 1);
+
+    assert_eq!(symbols.lookup(0), None);
+}
+
+#[test]
+fn public_symbols_missing() {
+    assert!(PublicSymbols::new("does-not-exist.pdb").is_err());
+}
+
+#[test]
+fn insert_from_export_missing() {
+    let mut srcview = SrcView::new();
+
+    assert!(srcview
+        .insert_from_export("z:\\does\\not\\exist.json")
+        .is_err());
+}
+
+#[test]
+fn rename_module_missing() {
+    let mut srcview = SrcView::new();
+
+    assert!(srcview.rename_module("foo.exe", "bar.exe").is_err());
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn rename_module() {
+    let mut srcview = test_srcview();
+
+    srcview.rename_module("example.exe", "EXAMPLE.EXE").unwrap();
+
+    let old_modoff = ModOff::new("example.exe", 0x6f70);
+    assert_eq!(srcview.modoff(&old_modoff), None);
+
+    let new_modoff = ModOff::new("EXAMPLE.EXE", 0x6f70);
+    assert_eq!(
+        srcview.modoff(&new_modoff),
+        Some(SrcLine::new("E:\\1f\\coverage\\example\\example.c", 3))
+    );
+
+    // renaming onto an existing module must fail and leave both in place
+    srcview.insert("example.exe", test_pdb_path()).unwrap();
+    assert!(srcview.rename_module("example.exe", "EXAMPLE.EXE").is_err());
+    assert!(srcview.modoff(&old_modoff).is_some());
+    assert!(srcview.modoff(&new_modoff).is_some());
+}
+
+#[test]
+fn clear_module_missing() {
+    let mut srcview = SrcView::new();
+
+    assert!(!srcview.clear_module("foo.exe"));
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn clear_module() {
+    let mut srcview = test_srcview();
+    let modoff = ModOff::new("example.exe", 0x6f70);
+
+    assert!(srcview.clear_module("example.exe"));
+    assert_eq!(srcview.modoff(&modoff), None);
+
+    srcview.insert("example.exe", test_pdb_path()).unwrap();
+    assert!(srcview.modoff(&modoff).is_some());
+}
+
+#[test]
+#[cfg_attr(not(feature = "binary-tests"), ignore)]
+fn shared() {
+    let shared = test_srcview().into_shared();
+
+    assert_eq!(shared.modules(), vec!["example.exe".to_owned()]);
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let shared = shared.clone();
+            thread::spawn(move || shared.modoff(&ModOff::new("example.exe", 0x6f70)))
+        })
+        .collect();
+
+    for handle in handles {
+        assert_eq!(
+            handle.join().unwrap(),
+            Some(SrcLine::new("E:\\1f\\coverage\\example\\example.c", 3))
+        );
+    }
+}
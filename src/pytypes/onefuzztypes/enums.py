@@ -384,6 +384,14 @@ class AgentMode(Enum):
     proxy = "proxy"
 
 
+class WorkerLogLevel(Enum):
+    error = "error"
+    warn = "warn"
+    info = "info"
+    debug = "debug"
+    trace = "trace"
+
+
 class NodeState(Enum):
     init = "init"
     free = "free"
@@ -32,6 +32,7 @@ from .enums import (
     TaskState,
     TaskType,
     VmState,
+    WorkerLogLevel,
 )
 from .primitives import Container, PoolName, Region
 
@@ -501,11 +502,34 @@ class NodeCommandAddSshKey(BaseModel):
     public_key: str
 
 
+class NodeCommandAttachDebugger(BaseModel):
+    task_id: UUID
+    command: str
+
+
+class NodeCommandSetLogLevel(BaseModel):
+    level: str
+
+
+class NodeCommandPing(BaseModel):
+    token: str
+
+
+class NodeCommandRequeueTask(BaseModel):
+    task_id: UUID
+    # The agent's `WorkUnit`, which has no model here.
+    work_unit: Dict[str, Any]
+
+
 class NodeCommand(EnumModel):
     stop: Optional[StopNodeCommand]
     stop_task: Optional[StopTaskNodeCommand]
     add_ssh_key: Optional[NodeCommandAddSshKey]
     stop_if_free: Optional[NodeCommandStopIfFree]
+    attach_debugger: Optional[NodeCommandAttachDebugger]
+    set_log_level: Optional[NodeCommandSetLogLevel]
+    ping: Optional[NodeCommandPing]
+    requeue_task: Optional[NodeCommandRequeueTask]
 
 
 class NodeTasks(BaseModel):
@@ -652,6 +676,7 @@ class ProcessOutput(BaseModel):
 
 class WorkerRunningEvent(BaseModel):
     task_id: UUID
+    tags: Optional[Dict[str, str]]
 
 
 class WorkerDoneEvent(BaseModel):
@@ -659,23 +684,86 @@ class WorkerDoneEvent(BaseModel):
     exit_status: ExitStatus
     stderr: str
     stdout: str
+    tags: Optional[Dict[str, str]]
+
+
+class WorkerMetricEvent(BaseModel):
+    task_id: UUID
+    name: str
+    value: float
+    tags: Optional[Dict[str, str]]
+
+
+class LibFuzzerStats(BaseModel):
+    iterations: int
+    coverage: int
+    features: int
+    corpus_size: int
+    exec_per_sec: float
+    rss_mb: int
+
+
+class WorkerStatsEvent(BaseModel):
+    task_id: UUID
+    stats: LibFuzzerStats
+    tags: Optional[Dict[str, str]]
+
+
+class WorkerSetupOutputEvent(BaseModel):
+    script_output: ProcessOutput
+
+
+class WorkerCorpusPrunedEvent(BaseModel):
+    task_id: UUID
+    removed: int
+    kept: int
+    tags: Optional[Dict[str, str]]
+
+
+class WorkerResumedEvent(BaseModel):
+    task_id: UUID
+    previous_elapsed: int
+    tags: Optional[Dict[str, str]]
+
+
+class WorkerLogEvent(BaseModel):
+    task_id: UUID
+    level: WorkerLogLevel
+    message: str
+    tags: Optional[Dict[str, str]]
+
+
+class WorkerPongEvent(BaseModel):
+    token: str
 
 
 class WorkerEvent(EnumModel):
     done: Optional[WorkerDoneEvent]
     running: Optional[WorkerRunningEvent]
+    metric: Optional[WorkerMetricEvent]
+    stats: Optional[WorkerStatsEvent]
+    setup_output: Optional[WorkerSetupOutputEvent]
+    corpus_pruned: Optional[WorkerCorpusPrunedEvent]
+    resumed: Optional[WorkerResumedEvent]
+    log: Optional[WorkerLogEvent]
+    pong: Optional[WorkerPongEvent]
 
 
 class NodeSettingUpEventData(BaseModel):
     tasks: List[UUID]
 
 
+class NodeBusyEventData(BaseModel):
+    started_at: int
+    estimated_duration: Optional[int]
+
+
 class NodeDoneEventData(BaseModel):
     error: Optional[str]
     script_output: Optional[ProcessOutput]
 
 
-NodeStateData = Union[NodeSettingUpEventData, NodeDoneEventData]
+NodeStateData = Union[NodeSettingUpEventData, NodeBusyEventData, NodeDoneEventData]
 
 
 class NodeStateUpdate(BaseModel):
@@ -693,6 +781,10 @@ class NodeStateUpdate(BaseModel):
                 if isinstance(data, NodeSettingUpEventData):
                     return values
 
+            if state == NodeState.busy:
+                if isinstance(data, NodeBusyEventData):
+                    return values
+
             if state == NodeState.done:
                 if isinstance(data, NodeDoneEventData):
                     return values